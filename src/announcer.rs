@@ -0,0 +1,60 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::event_bus::{use_app_events, AppEvent};
+
+/// Messages for screen readers about changes that happen without focus
+/// moving, read out by the `LiveRegion`.
+#[derive(Copy, Clone)]
+pub struct Announcer {
+    pub message: ReadSignal<String>,
+    set_message: WriteSignal<String>,
+}
+
+impl Announcer {
+    pub fn announce(&self, message: impl Into<String>) {
+        let message = message.into();
+        // Screen readers only speak changed text, so repeat messages get a trailing space
+        let message = if self.message.get_untracked() == message { format!("{} ", message) } else { message };
+        self.set_message.set(message);
+    }
+}
+
+// Provide the announcer to this part of the tree
+pub fn provide_announcer() -> Announcer {
+    let (message, set_message) = signal(String::new());
+    let announcer = Announcer { message, set_message };
+    provide_context(announcer);
+    announcer
+}
+
+// Helper to get the announcer, creating one if none is provided
+pub fn use_announcer() -> Announcer {
+    use_context::<Announcer>().unwrap_or_else(provide_announcer)
+}
+
+/// Visually hidden `aria-live` region reading out announcements,
+/// including theme switches and finished imports
+#[component]
+pub fn LiveRegion() -> impl IntoView {
+    let announcer = use_announcer();
+
+    use_app_events(move |event| match event {
+        AppEvent::ThemeChanged(theme) => {
+            announcer.announce(if theme.is_dark() { "Switched to dark mode" } else { "Switched to light mode" });
+        },
+        AppEvent::ImportCompleted(summary) => announcer.announce(format!("Backup imported: {}", summary)),
+        _ => {},
+    });
+
+    view! {
+        <div
+            data-test-id="live-region"
+            class="sr-only"
+            role="status"
+            aria-live="polite"
+            aria-atomic="true"
+        >
+            {move || announcer.message.get()}
+        </div>
+    }
+}
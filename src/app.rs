@@ -1,12 +1,37 @@
 use leptos::*;
 use leptos::prelude::*;
+use leptos::ev;
 use crate::data::DataButton;
-use crate::theme::{ThemeProvider, use_container_class, use_card_class, use_header_class, 
-                  use_paragraph_class, use_button_class, use_toggle_class, use_toggle_text, use_theme};
-use log::{error, info}; // Import log macros
+use crate::friends::FriendsButton;
+use crate::profile::{self, LastActive, ProfileNameEditor};
+use crate::invite::{requested_invite, IncomingInvite};
+use crate::quota_warning::QuotaWarning;
+use crate::shortcuts::KeyboardShortcuts;
+use crate::announcer::{provide_announcer, LiveRegion};
+use crate::unsaved_changes::{provide_unsaved_changes, UnsavedChangesPrompt};
+use crate::data_service::provide_data_service;
+use crate::event_bus::provide_event_bus;
+use crate::panel::{provide_panel_nav, PanelId};
+use crate::utils::requested_panel;
+use crate::safe_mode::{is_safe_mode, safe_mode_requested, set_safe_mode};
+use crate::theme::{ThemeProvider, ThemePicker, use_container_class, use_card_class, use_header_class, 
+                  use_paragraph_class, use_button_class, use_toggle_class, use_toggle_text, use_theme,
+                  use_skeleton_class};
+use std::time::Duration;
+use log::{error, info, warn}; // Import log macros
 
 #[component]
 pub fn App() -> impl IntoView {
+    // "?safe=1" turns optional subsystems off, for narrowing down bugs
+    if safe_mode_requested() {
+        warn!("Safe mode: encryption, clipboard, invite links and cross-tab sync are off");
+        set_safe_mode(true);
+    }
+    
+    // One event bus for the whole app, provided above ThemeProvider so
+    // theme events reach the same subscribers as everything else
+    provide_event_bus();
+    
     // Message for user feedback
     let (storage_message, set_storage_message) = create_signal(Option::<String>::None);
     
@@ -14,7 +39,7 @@ pub fn App() -> impl IntoView {
     let error_class = "mt-4 p-2 bg-red-100 text-red-700 rounded-md text-sm";
     
     view! {
-        <ThemeProvider>
+        <ThemeProvider chrome_styles=true document_indicators=true>
             <AppContent storage_message={storage_message} set_storage_message={set_storage_message} error_class={error_class} />
         </ThemeProvider>
     }
@@ -28,16 +53,69 @@ fn AppContent(
 ) -> impl IntoView {
     // Get theme helpers
     let container_class = use_container_class();
-    let card_class = use_card_class();
+    // Also used inside the skeleton's view closure, so kept as Copy signals
+    let card_class = Signal::derive(use_card_class());
     let header_class = use_header_class();
     let paragraph_class = use_paragraph_class();
     let button_class = use_button_class();
     let toggle_class = use_toggle_class();
     let toggle_text = use_toggle_text();
     
+    let skeleton_class = Signal::derive(use_skeleton_class());
+    
+    // Destructive actions check this before discarding in-progress edits
+    provide_unsaved_changes();
+    
+    // Export, import and reset go through the shared data service
+    provide_data_service();
+    
+    // Stateful actions announce their outcome for screen readers
+    provide_announcer();
+    
+    // Panels open one at a time through the shared navigation
+    let nav = provide_panel_nav();
+    
     // Get theme context for the toggle action
     let theme = use_theme();
     
+    // Show when the identity was last used before this visit, then record
+    // this visit and keep it current while the user interacts
+    let previous_activity = profile::load_last_active();
+    let record_activity = || {
+        if let Err(err) = profile::touch_last_active() {
+            error!("Failed to record last activity: {:?}", err);
+        }
+    };
+    record_activity();
+    let keydown_handle = window_event_listener(ev::keydown, move |_| record_activity());
+    let click_handle = window_event_listener(ev::click, move |_| record_activity());
+    on_cleanup(move || {
+        keydown_handle.remove();
+        click_handle.remove();
+    });
+    
+    // Theme and player ID are read from storage while the first render is
+    // built; show placeholders until the next tick so it never looks half-drawn
+    let (ready, set_ready) = signal(false);
+    set_timeout(move || set_ready.set(true), Duration::ZERO);
+    
+    // Panels linked from the page URL (e.g. "?panel=data") open on load;
+    // unknown panel names are ignored
+    if let Some(panel) = requested_panel().as_deref().and_then(PanelId::from_name) {
+        nav.open(panel);
+    }
+    
+    // An invite link ("?profile=...") shows who it's from; unreadable invites are logged and ignored.
+    // Safe mode doesn't import anything from the URL.
+    let incoming_invite = match requested_invite().filter(|_| !is_safe_mode()) {
+        Some(Ok(invite)) => Some(invite),
+        Some(Err(err)) => {
+            error!("Ignoring invite link: {}", err);
+            None
+        },
+        None => None,
+    };
+    
     // Toggle function for the dark mode using the action from theme context
     let toggle_dark_mode = move |_| {
         theme.toggle_theme.dispatch(());
@@ -48,7 +126,14 @@ fn AppContent(
             data-test-id="app-container"
             class={container_class}
         >
-            <div class={card_class}>
+            {move || (!ready.get()).then(|| view! {
+                <div data-test-id="app-skeleton" class={card_class} aria-busy="true">
+                    <div class={skeleton_class} style="height: 2.25rem; width: 60%; margin: 0 auto 1rem;" />
+                    <div class={skeleton_class} style="height: 1rem; width: 90%; margin: 0 auto 1.5rem;" />
+                    <div class={skeleton_class} style="height: 2.5rem; width: 50%; margin: 0 auto;" />
+                </div>
+            })}
+            <div class={card_class} style:display={move || if ready.get() { "" } else { "none" }}>
                 <h1 data-test-id="hello-header" class={header_class}>"Hello Leptos"</h1>
                 <p class={paragraph_class}>"Welcome to your Tailwind-styled Leptos app!"</p>
                 <div class="flex justify-center space-x-4">
@@ -63,6 +148,11 @@ fn AppContent(
                         {toggle_text}
                     </button>
                 </div>
+                <ThemePicker />
+                <ProfileNameEditor />
+                <LastActive at={previous_activity} />
+                {incoming_invite.map(|invite| view! { <IncomingInvite invite={invite} /> })}
+                <QuotaWarning />
                 
                 // Show storage error message if any
                 {move || {
@@ -74,9 +164,14 @@ fn AppContent(
                         }
                     })
                 }}
+                
+                <FriendsButton />
             </div>
 
             <DataButton />
+            <UnsavedChangesPrompt />
+            <KeyboardShortcuts />
+            <LiveRegion />
         </div>
     }
 }
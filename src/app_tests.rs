@@ -102,6 +102,90 @@ mod app_wasm_tests {
         assert!(data_button.is_object(), "Data button should exist when integrated into App");
     }
     
+    #[wasm_bindgen_test]
+    async fn test_data_panel_opens_from_url_fragment() {
+        let location = web_sys::window().unwrap().location();
+        location.set_hash("data").unwrap();
+        
+        mount_to_body(|| view! { <App /> });
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        let panel = document.query_selector("[data-test-id='data-panel']").unwrap();
+        
+        // Clear the fragment so other tests start with the panel closed
+        location.set_hash("").unwrap();
+        
+        assert!(panel.is_some(), "Data panel should be open when the URL links to it");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_unknown_panel_fragment_is_ignored() {
+        let location = web_sys::window().unwrap().location();
+        location.set_hash("nonexistent").unwrap();
+        
+        // Mount into a root of its own, so panels other tests left in the body don't count
+        let document = web_sys::window().unwrap().document().unwrap();
+        let root = document.create_element("div").unwrap().dyn_into::<web_sys::HtmlElement>().unwrap();
+        document.body().unwrap().append_child(&root).unwrap();
+        let handle = leptos::mount::mount_to(root.clone(), || view! { <App /> });
+        
+        let panel = root.query_selector("[data-test-id='data-panel']").unwrap();
+        location.set_hash("").unwrap();
+        drop(handle);
+        root.remove();
+        
+        assert!(panel.is_none(), "Unknown panel names should not open anything");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_skeleton_shows_until_ready() {
+        mount_to_body(|| view! { <App /> });
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        let skeleton = document.query_selector("[data-test-id='app-skeleton']").unwrap();
+        assert!(skeleton.is_some(), "Skeleton should show before the app is ready");
+        
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        
+        let skeleton = document.query_selector("[data-test-id='app-skeleton']").unwrap();
+        assert!(skeleton.is_none(), "Skeleton should be gone once the app is ready");
+        let header = get_by_test_id("hello-header");
+        let style = web_sys::window().unwrap().get_computed_style(&header).unwrap().unwrap();
+        assert_ne!(style.get_property_value("display").unwrap(), "none", "Main content should be visible when ready");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_unsaved_profile_name_prompts_before_load() {
+        crate::utils::localStorage::reset_all_storage();
+        mount_to_body(|| view! { <App /> });
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        
+        // Edit the profile name without saving it
+        let name_input = get_by_test_id("profile-name-input")
+            .dyn_into::<web_sys::HtmlInputElement>()
+            .unwrap();
+        name_input.set_value("Unsaved Name");
+        name_input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        
+        click_and_wait(&get_by_test_id("data-button"), 100).await;
+        click_and_wait(&get_by_test_id("load-data-button"), 100).await;
+        
+        let modal = get_by_test_id("unsaved-changes-modal");
+        assert!(modal.text_content().unwrap().contains("You have unsaved changes, continue?"), "Load should ask first");
+        
+        // Cancelling drops the load and keeps the edit
+        click_and_wait(&get_by_test_id("unsaved-changes-cancel"), 100).await;
+        let document = window().unwrap().document().unwrap();
+        assert!(
+            document.query_selector("[data-test-id='unsaved-changes-modal']").unwrap().is_none(),
+            "Modal should close on cancel"
+        );
+        assert_eq!(name_input.value(), "Unsaved Name", "Edit should be kept");
+        
+        crate::utils::localStorage::reset_all_storage();
+    }
+    
     // New test for storage error handling
     #[wasm_bindgen_test]
     async fn test_storage_error_handling() {
@@ -119,5 +203,49 @@ mod app_wasm_tests {
         
         // For a complete test, we'd need to mock localStorage to fail
         // This is complex in WASM and would require additional test infrastructure
+    }    
+    #[wasm_bindgen_test]
+    async fn test_mounting_app_records_last_active() {
+        crate::utils::localStorage::reset_all_storage();
+        
+        mount_to_body(|| view! { <App /> });
+        
+        let last_active = crate::profile::load_last_active().expect("Mounting should record activity");
+        let age = chrono::Utc::now() - last_active;
+        assert!(age >= chrono::Duration::zero() && age < chrono::Duration::seconds(10), "last_active should be recent: {}", last_active);
+        
+        crate::utils::localStorage::reset_all_storage();
+    }    
+    #[wasm_bindgen_test]
+    async fn test_safe_mode_turns_off_encryption_and_invite_links() {
+        use crate::invite::{build_profile_link, ProfileInvite};
+        
+        reset_everything();
+        let _ = crate::utils::localStorage::set_storage_item("player_id", "safe_player");
+        
+        // Open the app from an invite link with safe mode on
+        let window = web_sys::window().unwrap();
+        let original_url = window.location().href().unwrap();
+        let invite = ProfileInvite { player_id: "inviter".to_string(), name: "Inviter".to_string(), handshake_token: None };
+        let link = build_profile_link(&original_url, &invite).unwrap();
+        let history = window.history().unwrap();
+        history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&format!("{}&safe=1", link))).unwrap();
+        
+        mount_to_body(|| view! { <App /> });
+        click_and_wait(&get_by_test_id("data-button"), 50).await;
+        
+        let document = window.document().unwrap();
+        for test_id in ["incoming-invite", "passphrase-toggle", "qr-export-button"] {
+            assert!(
+                document.query_selector(&format!("[data-test-id='{}']", test_id)).unwrap().is_none(),
+                "{} should be unavailable in safe mode", test_id
+            );
+        }
+        let backup = crate::data::export_backup().unwrap();
+        assert!(!backup.encrypted, "Safe mode exports are plaintext");
+        assert!(backup.json.contains("safe_player"));
+        
+        history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&original_url)).unwrap();
+        reset_everything();
     }
 }
\ No newline at end of file
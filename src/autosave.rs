@@ -0,0 +1,114 @@
+use std::cell::Cell;
+use std::time::Duration;
+use leptos::prelude::set_timeout;
+use log::{error, info};
+use crate::data::{DataError, ImportSummary, export_data, import_data};
+use crate::utils::{get_storage_item, get_storage_item_required, set_storage_item, StorageError};
+
+// Storage key holding the single autosave snapshot
+pub const AUTOSAVE_KEY: &str = "autosave";
+// Storage key for the opt-in setting
+pub const AUTOSAVE_ENABLED_KEY: &str = "autosave_enabled";
+
+// How long state must stay unchanged before a snapshot is written
+pub const AUTOSAVE_DEBOUNCE: Duration = Duration::from_millis(1000);
+
+thread_local! {
+    // Bumped on every change so only the last scheduled save runs
+    static AUTOSAVE_GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+// Whether the user has turned autosave on; off by default
+pub fn autosave_enabled() -> bool {
+    matches!(get_storage_item(AUTOSAVE_ENABLED_KEY), Ok(Some(value)) if value == "true")
+}
+
+pub fn set_autosave_enabled(enabled: bool) -> Result<(), StorageError> {
+    set_storage_item(AUTOSAVE_ENABLED_KEY, if enabled { "true" } else { "false" })
+}
+
+// Whether there's a snapshot to restore
+pub fn has_autosave() -> bool {
+    matches!(get_storage_item(AUTOSAVE_KEY), Ok(Some(_)))
+}
+
+/// Write an encrypted snapshot of the current state, replacing the previous one
+pub fn write_autosave() -> Result<(), DataError> {
+    let snapshot = export_data()?;
+    set_storage_item(AUTOSAVE_KEY, &snapshot)?;
+    info!("Autosave snapshot written");
+    Ok(())
+}
+
+/// Write a snapshot once state has settled, if autosave is on.
+/// Calls within `AUTOSAVE_DEBOUNCE` of each other produce a single write.
+pub fn schedule_autosave() {
+    if !autosave_enabled() {
+        return;
+    }
+
+    let generation = AUTOSAVE_GENERATION.with(|current| {
+        current.set(current.get() + 1);
+        current.get()
+    });
+    set_timeout(move || {
+        let latest = AUTOSAVE_GENERATION.with(|current| current.get());
+        if generation == latest && autosave_enabled() {
+            if let Err(err) = write_autosave() {
+                error!("Autosave failed: {}", err);
+            }
+        }
+    }, AUTOSAVE_DEBOUNCE);
+}
+
+/// Decrypt the autosave snapshot and apply it through the import flow
+pub fn restore_autosave() -> Result<ImportSummary, DataError> {
+    let snapshot = get_storage_item_required(AUTOSAVE_KEY)?;
+    import_data(&snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::localStorage;
+    use gloo_timers::future::TimeoutFuture;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_autosave_snapshot_restores_state() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "autosave_player");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+
+        // Nothing is saved while autosave is off
+        schedule_autosave();
+        TimeoutFuture::new(AUTOSAVE_DEBOUNCE.as_millis() as u32 + 100).await;
+        assert!(!has_autosave(), "Autosave should be off by default");
+
+        set_autosave_enabled(true).unwrap();
+        schedule_autosave();
+        TimeoutFuture::new(AUTOSAVE_DEBOUNCE.as_millis() as u32 + 100).await;
+        assert!(has_autosave(), "Snapshot should be written after the debounce");
+        let snapshot = localStorage::get_storage_item(AUTOSAVE_KEY).unwrap().unwrap();
+        assert!(!snapshot.contains("autosave_player"), "Snapshot should be encrypted");
+
+        // Lose the state, then recover it
+        let _ = localStorage::reset_storage_item("player_id");
+        let _ = localStorage::set_storage_item("dark_mode", "false");
+        restore_autosave().expect("Restoring should succeed");
+
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("autosave_player".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()));
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_restore_without_snapshot_fails() {
+        localStorage::reset_all_storage();
+        let result = restore_autosave();
+        assert!(matches!(result, Err(DataError::Storage(StorageError::NotFound(_)))), "{:?}", result);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use crate::copy_button::clipboard_available;
+use crate::crypto::crypto_available;
+use crate::utils::get_storage;
+
+/// Browser features the app can use, as detected right now
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub storage: bool,
+    pub clipboard: bool,
+    pub share: bool,
+    pub crypto: bool,
+    pub broadcast_channel: bool,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        Capabilities {
+            storage: get_storage().is_ok(),
+            clipboard: clipboard_available(),
+            share: share_available(),
+            crypto: crypto_available(),
+            broadcast_channel: broadcast_channel_available(),
+        }
+    }
+}
+
+// navigator.share exists and is callable
+fn share_available() -> bool {
+    web_sys::window()
+        .and_then(|win| js_sys::Reflect::get(&win.navigator(), &JsValue::from_str("share")).ok())
+        .is_some_and(|share| share.is_function())
+}
+
+fn broadcast_channel_available() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("BroadcastChannel"))
+        .is_ok_and(|constructor| constructor.is_function())
+}
+
+/// Feature support for host pages, as an object of booleans:
+/// `{ storage, clipboard, share, crypto, broadcast_channel }`
+#[wasm_bindgen]
+pub fn capabilities() -> JsValue {
+    serde_json::to_string(&Capabilities::detect())
+        .ok()
+        .and_then(|json| js_sys::JSON::parse(&json).ok())
+        .unwrap_or(JsValue::NULL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_capabilities_report_has_expected_keys() {
+        let report = capabilities();
+        assert!(report.is_object(), "Report should be an object");
+
+        for key in ["storage", "clipboard", "share", "crypto", "broadcast_channel"] {
+            let value = js_sys::Reflect::get(&report, &JsValue::from_str(key)).unwrap();
+            assert!(value.as_bool().is_some(), "{} should be a boolean", key);
+        }
+        let keys = js_sys::Object::keys(report.unchecked_ref::<js_sys::Object>());
+        assert_eq!(keys.length(), 5, "Report should only have the documented keys");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_capabilities_follow_internal_checks() {
+        let detected = Capabilities::detect();
+        assert!(detected.storage, "Test browser has localStorage");
+        assert!(detected.crypto, "Test browser has a secure RNG");
+
+        crate::utils::set_force_storage_error(Some(crate::utils::StorageError::StorageUnavailable));
+        assert!(!Capabilities::detect().storage, "Storage failures should be reported");
+        crate::utils::set_force_storage_error(None);
+    }
+}
@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use chrono::{DateTime, Local, Utc};
+
+/// Source of the current time for timestamps written by the app
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock backed by the system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that always returns the same instant, for deterministic tests
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+thread_local! {
+    static CLOCK: RefCell<Rc<dyn Clock>> = RefCell::new(Rc::new(SystemClock));
+}
+
+// Replace the clock used by `now`
+pub fn set_clock(clock: impl Clock + 'static) {
+    CLOCK.with(|current| *current.borrow_mut() = Rc::new(clock));
+}
+
+// Go back to the system clock
+pub fn reset_clock() {
+    set_clock(SystemClock);
+}
+
+// Current time from the active clock
+pub fn now() -> DateTime<Utc> {
+    let clock = CLOCK.with(|current| current.borrow().clone());
+    clock.now()
+}
+
+// Current time from the active clock, in the user's timezone
+pub fn local_now() -> DateTime<Local> {
+    now().with_timezone(&Local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_fixed_clock_overrides_now() {
+        let instant = Utc.with_ymd_and_hms(2025, 4, 9, 12, 0, 0).unwrap();
+        set_clock(FixedClock(instant));
+        assert_eq!(now(), instant, "now() should use the fixed clock");
+        
+        reset_clock();
+        assert!(now() > instant, "now() should use the system clock after reset");
+    }
+}
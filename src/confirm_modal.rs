@@ -0,0 +1,86 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::theme::{use_card_class, use_paragraph_class, use_button_class};
+
+// What the user types to confirm a `Severity::High` action
+pub const CONFIRM_PHRASE: &str = "CONFIRM";
+
+/// How strongly a `ConfirmModal` asks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Normal,
+    /// Shown as a warning, and confirming needs `CONFIRM_PHRASE` typed in
+    High,
+}
+
+/// Modal asking the user to confirm or cancel an action.
+///
+/// Test ids are derived from `test_id`, so `test_id="remove"` renders
+/// `remove-modal`, `remove-confirm` and `remove-cancel`, plus
+/// `remove-phrase-input` at `Severity::High`.
+#[component]
+pub fn ConfirmModal(
+    /// Question shown to the user
+    #[prop(into)] message: String,
+    /// Prefix used for the `data-test-id` attributes of the modal parts
+    test_id: &'static str,
+    /// Escalate for actions that are costly to undo
+    #[prop(optional)] severity: Severity,
+    /// Called when the user confirms
+    #[prop(into)] on_confirm: Callback<()>,
+    /// Called when the user cancels
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let typed = RwSignal::new(String::new());
+    let can_confirm = move || severity == Severity::Normal || typed.get().trim() == CONFIRM_PHRASE;
+    let paragraph_class = use_paragraph_class();
+    let message_class = move || match severity {
+        Severity::Normal => paragraph_class(),
+        Severity::High => format!("{} font-semibold text-red-600 dark:text-red-400", paragraph_class()),
+    };
+
+    view! {
+        <div
+            class="fixed inset-0 flex items-center justify-center bg-black/50"
+            role="dialog"
+            aria-modal="true"
+            data-test-id={format!("{}-modal", test_id)}
+        >
+            <div class={use_card_class()}>
+                <p class={message_class}>{message}</p>
+                {(severity == Severity::High).then(|| view! {
+                    <label class="text-sm" for={format!("{}-phrase-input", test_id)}>
+                        {format!("Type {} to continue", CONFIRM_PHRASE)}
+                    </label>
+                    <input
+                        id={format!("{}-phrase-input", test_id)}
+                        data-test-id={format!("{}-phrase-input", test_id)}
+                        type="text"
+                        autocomplete="off"
+                        class="w-full rounded border px-2 py-1 mb-4 text-gray-900"
+                        prop:value={move || typed.get()}
+                        on:input={move |ev| typed.set(event_target_value(&ev))}
+                    />
+                })}
+                <div class="flex justify-end space-x-2">
+                    <button
+                        data-test-id={format!("{}-cancel", test_id)}
+                        class={use_button_class()}
+                        on:click={move |_| on_cancel.run(())}
+                    >
+                        "Cancel"
+                    </button>
+                    <button
+                        data-test-id={format!("{}-confirm", test_id)}
+                        class={use_button_class()}
+                        disabled={move || !can_confirm()}
+                        on:click={move |_| if can_confirm() { on_confirm.run(()) }}
+                    >
+                        "Confirm"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
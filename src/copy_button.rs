@@ -0,0 +1,144 @@
+use leptos::*;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use log::warn;
+use crate::theme::use_button_class;
+use crate::data::{with_timeout, operation_timeout};
+use crate::safe_mode::is_safe_mode;
+
+// How long the "Copied!" label stays before reverting
+const COPIED_RESET: Duration = Duration::from_millis(1500);
+
+pub type ClipboardFuture = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+
+// Writes text to the clipboard, resolving once the copy is done
+pub type ClipboardWriter = Rc<dyn Fn(String) -> ClipboardFuture>;
+
+fn browser_clipboard_writer() -> ClipboardWriter {
+    Rc::new(|text: String| Box::pin(write_to_browser_clipboard(text)))
+}
+
+thread_local! {
+    static CLIPBOARD_WRITER: RefCell<ClipboardWriter> = RefCell::new(browser_clipboard_writer());
+}
+
+// Replace the clipboard writer, e.g. in tests where the browser denies clipboard access
+pub fn set_clipboard_writer(writer: impl Fn(String) -> ClipboardFuture + 'static) {
+    CLIPBOARD_WRITER.with(|current| *current.borrow_mut() = Rc::new(writer));
+}
+
+// Go back to the browser clipboard
+pub fn reset_clipboard_writer() {
+    CLIPBOARD_WRITER.with(|current| *current.borrow_mut() = browser_clipboard_writer());
+}
+
+// navigator.clipboard, missing on insecure origins and some browsers
+fn browser_clipboard() -> Option<JsValue> {
+    let navigator = web_sys::window()?.navigator();
+    js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))
+        .ok()
+        .filter(|clipboard| !clipboard.is_undefined())
+}
+
+pub fn clipboard_available() -> bool {
+    !is_safe_mode() && browser_clipboard().is_some()
+}
+
+// Call navigator.clipboard.writeText, which may be missing or denied
+async fn write_to_browser_clipboard(text: String) -> Result<(), String> {
+    let clipboard = browser_clipboard().ok_or("Clipboard is not available")?;
+    let write_text = js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText"))
+        .map_err(|_| "Clipboard can't write text")?
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| "Clipboard can't write text")?;
+    let promise = write_text
+        .call1(&clipboard, &JsValue::from_str(&text))
+        .map_err(|err| format!("{:?}", err))?
+        .dyn_into::<js_sys::Promise>()
+        .map_err(|_| "Clipboard did not return a promise")?;
+    JsFuture::from(promise).await.map(|_| ()).map_err(|err| format!("{:?}", err))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CopyState {
+    Idle,
+    Copied,
+    Failed,
+}
+
+/// Button that copies text to the clipboard and briefly shows "Copied!".
+///
+/// When the browser refuses clipboard access the button says so, and the
+/// text is shown so it can be selected by hand. Safe mode skips the
+/// clipboard and shows the text straight away.
+#[component]
+pub fn CopyButton(
+    /// Text to copy, read when the button is clicked
+    #[prop(into)] text: Signal<String>,
+    /// Button label while idle
+    #[prop(into)] label: String,
+    /// `data-test-id` of the button
+    test_id: &'static str,
+) -> impl IntoView {
+    let (state, set_state) = signal(CopyState::Idle);
+    let label = StoredValue::new(label);
+
+    let copy = move |_| {
+        let writer = CLIPBOARD_WRITER.with(|current| current.borrow().clone());
+        let copy_future = writer(text.get_untracked());
+        spawn_local(async move {
+            let result = with_timeout(copy_future, operation_timeout())
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()));
+            match result {
+                Ok(()) => {
+                    set_state.set(CopyState::Copied);
+                    set_timeout(move || {
+                        if state.get_untracked() == CopyState::Copied {
+                            set_state.set(CopyState::Idle);
+                        }
+                    }, COPIED_RESET);
+                },
+                Err(err) => {
+                    warn!("Copy to clipboard failed: {}", err);
+                    set_state.set(CopyState::Failed);
+                }
+            }
+        });
+    };
+
+    if is_safe_mode() {
+        return view! {
+            <span data-test-id={format!("{}-fallback", test_id)} class="text-sm select-all">
+                {move || text.get()}
+            </span>
+        }.into_any();
+    }
+
+    view! {
+        <span>
+            <button
+                data-test-id={test_id}
+                class={use_button_class()}
+                on:click={copy}
+            >
+                {move || match state.get() {
+                    CopyState::Copied => "Copied!".to_string(),
+                    _ => label.get_value(),
+                }}
+            </button>
+            {move || (state.get() == CopyState::Failed).then(|| view! {
+                <span data-test-id={format!("{}-fallback", test_id)} class="text-sm select-all">
+                    "Copy not allowed, select to copy: "{text.get()}
+                </span>
+            })}
+        </span>
+    }.into_any()
+}
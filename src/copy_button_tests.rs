@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod copy_button_tests {
+    use leptos::*;
+    use leptos::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen_test::*;
+    use gloo_timers::future::TimeoutFuture;
+    use crate::test_utils::test::*;
+    use crate::theme::ThemeProvider;
+    use crate::copy_button::{CopyButton, set_clipboard_writer, reset_clipboard_writer};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_copy_button_shows_copied_state() {
+        let copied: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let copied_writer = copied.clone();
+        set_clipboard_writer(move |text| {
+            *copied_writer.borrow_mut() = Some(text);
+            Box::pin(async { Ok(()) })
+        });
+
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <CopyButton text={Signal::derive(|| "copy me".to_string())} label="Copy" test_id="copy-test" />
+            </ThemeProvider>
+        });
+
+        let button = get_by_test_id("copy-test");
+        assert_eq!(button.text_content().unwrap(), "Copy");
+
+        click_and_wait(&button, 50).await;
+        assert_eq!(button.text_content().unwrap(), "Copied!", "Button should confirm the copy");
+        assert_eq!(*copied.borrow(), Some("copy me".to_string()), "Text should be written to the clipboard");
+
+        // The label reverts after a moment
+        TimeoutFuture::new(1600).await;
+        assert_eq!(button.text_content().unwrap(), "Copy", "Button should return to its label");
+
+        reset_clipboard_writer();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_copy_button_falls_back_when_denied() {
+        set_clipboard_writer(|_| Box::pin(async { Err("NotAllowedError".to_string()) }));
+
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <CopyButton text={Signal::derive(|| "manual copy".to_string())} label="Copy" test_id="copy-denied" />
+            </ThemeProvider>
+        });
+
+        let button = get_by_test_id("copy-denied");
+        click_and_wait(&button, 50).await;
+
+        let fallback = get_by_test_id("copy-denied-fallback");
+        assert!(fallback.text_content().unwrap().contains("manual copy"), "Text should be shown for manual copying");
+        assert_eq!(button.text_content().unwrap(), "Copy", "Button should not claim the copy worked");
+
+        reset_clipboard_writer();
+    }
+}
@@ -1,26 +1,124 @@
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Key, Nonce
 };
-use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use chacha20poly1305::ChaCha20Poly1305;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use base64::{Engine as _, engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD}};
+use pbkdf2::pbkdf2_hmac;
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+use log::warn;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::rc::Rc;
 
 // Structure to represent encrypted data
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct EncryptedData {
     pub ciphertext: String,  // Base64 encoded encrypted data
     pub iv: String,          // Base64 encoded initialization vector
-    pub tag: String,         // Base64 encoded authentication tag
+    pub tag: String,         // Base64 encoded authentication tag; empty when it's still on the ciphertext
+    /// Base64 encoded salt the key was derived with, for password-encrypted data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// Layout of the fields above; payloads from before versioning read as 0
+    #[serde(default)]
+    pub format_version: u8,
+    /// Whether the plaintext was deflated before encryption
+    #[serde(default)]
+    pub compressed: bool,
+    /// `CipherAlgorithm::name` of the cipher used; AES-256-GCM for older payloads
+    #[serde(default = "default_algorithm_name")]
+    pub algorithm: String,
+    /// Associated data the ciphertext is bound to, such as the exporting
+    /// player's ID; authenticated with the ciphertext but not encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aad: Option<String>,
+    /// Base64 encoded segments of data encrypted in chunks, each with its
+    /// tag appended; `ciphertext` and `tag` are empty when these are used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<String>,
 }
 
+fn default_algorithm_name() -> String {
+    CipherAlgorithm::default().name().to_string()
+}
+
+// Current `EncryptedData` layout: 1 split the GCM tag into its own field,
+// 2 added optional compression, 3 recorded the cipher, 4 added associated data,
+// 5 added chunked segments. Older payloads still decrypt.
+pub const ENCRYPTED_FORMAT_VERSION: u8 = 5;
+
+/// Ciphers a payload can be encrypted with. Both take the same 32-byte key
+/// and 12-byte nonce, and produce a 16-byte tag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CipherAlgorithm {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    /// Name stored in `EncryptedData::algorithm`
+    pub fn name(&self) -> &'static str {
+        match self {
+            CipherAlgorithm::Aes256Gcm => "AES-256-GCM",
+            CipherAlgorithm::ChaCha20Poly1305 => "ChaCha20-Poly1305",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        [CipherAlgorithm::Aes256Gcm, CipherAlgorithm::ChaCha20Poly1305]
+            .into_iter()
+            .find(|algorithm| algorithm.name() == name)
+    }
+    
+    // Encrypt `plaintext` bound to `aad`, returning the ciphertext with the tag appended
+    fn seal(&self, key: &[u8; 32], nonce: &[u8; 12], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, aes_gcm::Error> {
+        let payload = Payload { msg: plaintext, aad };
+        match self {
+            CipherAlgorithm::Aes256Gcm => Aes256Gcm::new(key.into()).encrypt(Nonce::from_slice(nonce), payload),
+            CipherAlgorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).encrypt(nonce.into(), payload),
+        }
+    }
+    
+    // Decrypt a ciphertext with its tag appended, checking it was bound to `aad`
+    fn open(&self, key: &[u8; 32], nonce: &[u8; 12], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, aes_gcm::Error> {
+        let payload = Payload { msg: ciphertext, aad };
+        match self {
+            CipherAlgorithm::Aes256Gcm => Aes256Gcm::new(key.into()).decrypt(Nonce::from_slice(nonce), payload),
+            CipherAlgorithm::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).decrypt(nonce.into(), payload),
+        }
+    }
+}
+
+// Plaintexts shorter than this aren't worth compressing
+pub const COMPRESSION_THRESHOLD: usize = 512;
+
+// Length of the authentication tag, the same for both ciphers
+const GCM_TAG_LEN: usize = 16;
+
 // Error type for crypto operations
 #[derive(Debug, Clone)]
 pub enum CryptoError {
     EncryptionError(String),
     DecryptionError(String),
     EncodingError(String),
+    /// Input that isn't well-formed encrypted data: bad JSON or base64,
+    /// or fields of the wrong length. `DecryptionError` is kept for data
+    /// that is well-formed but fails authentication.
+    MalformedInput(String),
+    /// A format version or cipher this build can't read
+    Unsupported(String),
+    /// Data bound to other associated data than expected, e.g. another player
+    AadMismatch(String),
     KeyError(String),
+    RngUnavailable(String),
 }
 
 impl std::fmt::Display for CryptoError {
@@ -29,15 +127,101 @@ impl std::fmt::Display for CryptoError {
             CryptoError::EncryptionError(msg) => write!(f, "Encryption error: {}", msg),
             CryptoError::DecryptionError(msg) => write!(f, "Decryption error: {}", msg),
             CryptoError::EncodingError(msg) => write!(f, "Encoding error: {}", msg),
+            CryptoError::MalformedInput(msg) => write!(f, "Malformed input: {}", msg),
+            CryptoError::Unsupported(msg) => write!(f, "Unsupported encrypted data: {}", msg),
+            CryptoError::AadMismatch(msg) => write!(f, "Associated data mismatch: {}", msg),
             CryptoError::KeyError(msg) => write!(f, "Key error: {}", msg),
+            CryptoError::RngUnavailable(msg) => write!(f, "Secure random numbers unavailable: {}", msg),
         }
     }
 }
 
 impl std::error::Error for CryptoError {}
 
-// Key derivation from environment or fixed for testing
-fn get_encryption_key() -> Result<Key<Aes256Gcm>, CryptoError> {
+// Fills a buffer with random bytes, or reports why it couldn't
+pub type RandomSource = Rc<dyn Fn(&mut [u8]) -> Result<(), String>>;
+
+fn platform_random_source() -> RandomSource {
+    Rc::new(|buf: &mut [u8]| getrandom::getrandom(buf).map_err(|e| e.to_string()))
+}
+
+thread_local! {
+    static RANDOM_SOURCE: RefCell<RandomSource> = RefCell::new(platform_random_source());
+}
+
+// Replace the random source used for IVs, e.g. to simulate a missing platform RNG
+pub fn set_random_source(source: impl Fn(&mut [u8]) -> Result<(), String> + 'static) {
+    RANDOM_SOURCE.with(|current| *current.borrow_mut() = Rc::new(source));
+}
+
+// Go back to the platform random source
+pub fn reset_random_source() {
+    RANDOM_SOURCE.with(|current| *current.borrow_mut() = platform_random_source());
+}
+
+fn fill_random(buf: &mut [u8]) -> Result<(), CryptoError> {
+    let source = RANDOM_SOURCE.with(|current| current.borrow().clone());
+    source(buf).map_err(CryptoError::RngUnavailable)
+}
+
+// Nonces remembered for the reuse check, and draws before giving up on a
+// source that keeps repeating itself
+const RECENT_NONCE_LIMIT: usize = 64;
+const NONCE_ATTEMPTS: usize = 3;
+
+thread_local! {
+    static RECENT_NONCES: RefCell<VecDeque<[u8; 12]>> = RefCell::new(VecDeque::with_capacity(RECENT_NONCE_LIMIT));
+}
+
+// Random GCM nonce not used recently in this session. Reusing a nonce
+// with the same key exposes the plaintext, so a repeat from a broken
+// random source is redrawn, and one that only repeats is an error.
+fn fresh_nonce() -> Result<[u8; 12], CryptoError> {
+    for _ in 0..NONCE_ATTEMPTS {
+        let mut nonce = [0u8; 12];
+        fill_random(&mut nonce)?;
+        let reused = RECENT_NONCES.with(|recent| {
+            let mut recent = recent.borrow_mut();
+            if recent.contains(&nonce) {
+                return true;
+            }
+            if recent.len() == RECENT_NONCE_LIMIT {
+                recent.pop_front();
+            }
+            recent.push_back(nonce);
+            false
+        });
+        if !reused {
+            return Ok(nonce);
+        }
+        warn!("Random source repeated a nonce; drawing another");
+    }
+    Err(CryptoError::RngUnavailable("the random source keeps repeating nonces".to_string()))
+}
+
+/// Whether this browser can produce the random IVs encryption needs
+pub fn crypto_available() -> bool {
+    fill_random(&mut [0u8; 12]).is_ok()
+}
+
+thread_local! {
+    static HOST_KEY: RefCell<Option<Zeroizing<[u8; 32]>>> = const { RefCell::new(None) };
+}
+
+/// Use `key` instead of the built-in key wherever no other key is given,
+/// e.g. so an embedding page's backups only open with its own key.
+/// `None` goes back to the built-in key. The replaced key is wiped.
+pub fn set_encryption_key(key: Option<[u8; 32]>) {
+    HOST_KEY.with(|current| *current.borrow_mut() = key.map(Zeroizing::new));
+}
+
+// Key derivation from environment or fixed for testing.
+// The copy handed out is wiped when dropped.
+fn get_encryption_key() -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    if let Some(key) = HOST_KEY.with(|current| current.borrow().clone()) {
+        return Ok(key);
+    }
+    
     // In production, you'd want to derive this from environment or secure storage
     // For testing purposes, we're using a fixed key (NEVER DO THIS IN PRODUCTION)
     let key_bytes = [
@@ -47,74 +231,542 @@ fn get_encryption_key() -> Result<Key<Aes256Gcm>, CryptoError> {
         0x6e, 0x5e, 0x02, 0x9b, 0xd3, 0xf6, 0xa1, 0xc7
     ];
     
-    Ok(Key::<Aes256Gcm>::from_slice(&key_bytes).clone())
+    Ok(Zeroizing::new(key_bytes))
 }
 
-// Encrypt data and return as JSON string
-pub fn encrypt_data(data: &str) -> Result<String, CryptoError> {
-    let key = get_encryption_key()?;
-    let cipher = Aes256Gcm::new(&key);
+// The given raw key, or the app key when `None`, wiped when dropped
+fn resolve_key(key_bytes: Option<&[u8; 32]>) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    match key_bytes {
+        Some(bytes) => Ok(Zeroizing::new(*bytes)),
+        None => get_encryption_key(),
+    }
+}
+
+// PBKDF2 rounds for passphrase keys; slow enough to make guessing costly
+// without stalling the page on older phones
+pub const PASSPHRASE_ITERATIONS: u32 = 100_000;
+pub const PASSPHRASE_SALT_LEN: usize = 16;
+
+/// `N` bytes from the random source
+pub fn random_bytes<const N: usize>() -> Result<[u8; N], CryptoError> {
+    let mut bytes = [0u8; N];
+    fill_random(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Fresh random salt for deriving a passphrase key
+pub fn new_passphrase_salt() -> Result<[u8; PASSPHRASE_SALT_LEN], CryptoError> {
+    random_bytes()
+}
+
+/// AES-256 key derived from a passphrase with PBKDF2-HMAC-SHA256, wiped when dropped
+pub fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PASSPHRASE_ITERATIONS, &mut *key);
+    key
+}
+
+/// A passphrase key together with the salt it was derived with, so it can
+/// be reused for backups sharing that salt without the passphrase
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassphraseKey {
+    pub key: Zeroizing<[u8; 32]>,
+    pub salt: Vec<u8>,
+}
+
+impl PassphraseKey {
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        PassphraseKey { key: derive_passphrase_key(passphrase, salt), salt: salt.to_vec() }
+    }
+}
+
+/// AES-256-GCM key for `password`, the same for the same password and salt
+pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<Key<Aes256Gcm>, CryptoError> {
+    if salt.is_empty() {
+        return Err(CryptoError::KeyError("A salt is needed to derive a key".to_string()));
+    }
+    Ok(Key::<Aes256Gcm>::from(*derive_passphrase_key(password, salt)))
+}
+
+/// Encrypt with a key derived from `password` and a fresh salt, stored
+/// alongside the ciphertext so `decrypt_data_with_password` can re-derive it
+pub fn encrypt_data_with_password(data: &str, password: &str) -> Result<String, CryptoError> {
+    let salt = new_passphrase_salt()?;
+    let key: Zeroizing<[u8; 32]> = Zeroizing::new(derive_key_from_password(password, &salt)?.into());
+    let encrypted = EncryptedData {
+        salt: Some(BASE64.encode(salt)),
+        ..encrypt_payload_with_key(data, Some(&key))?
+    };
+    
+    serde_json::to_string(&encrypted)
+        .map_err(|e| CryptoError::EncodingError(e.to_string()))
+}
+
+/// Decrypt JSON from `encrypt_data_with_password`; a wrong password fails
+/// with `DecryptionError`
+pub fn decrypt_data_with_password(encrypted_json: &str, password: &str) -> Result<String, CryptoError> {
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid JSON format: {}", e)))?;
+    let salt = encrypted.salt.as_deref()
+        .ok_or_else(|| CryptoError::KeyError("Data wasn't encrypted with a password".to_string()))?;
+    let salt = BASE64.decode(salt.as_bytes())
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 salt: {}", e)))?;
+    let key: Zeroizing<[u8; 32]> = Zeroizing::new(derive_key_from_password(password, &salt)?.into());
+    
+    decrypt_payload_with_key(&encrypted, Some(&key))
+}
+
+// Random bytes in a recovery code: 128 bits, shown as 32 hex digits
+pub const RECOVERY_CODE_LEN: usize = 16;
+
+/// Recovery code bytes as text, e.g. `3F2A-9C01-...`, in groups of four
+/// hex digits so it's easy to copy down by hand
+pub fn format_recovery_code(bytes: &[u8; RECOVERY_CODE_LEN]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    hex.as_bytes()
+        .chunks(4)
+        .map(|group| std::str::from_utf8(group).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The bytes of a recovery code, ignoring case, dashes and spaces; `None`
+/// when the text isn't one, e.g. because it's a passphrase
+pub fn parse_recovery_code(code: &str) -> Option<[u8; RECOVERY_CODE_LEN]> {
+    let digits: Vec<u8> = code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() != RECOVERY_CODE_LEN * 2 {
+        return None;
+    }
+    let mut bytes = [0u8; RECOVERY_CODE_LEN];
+    for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+        *byte = pair[0] << 4 | pair[1];
+    }
+    Some(bytes)
+}
+
+/// AES-256 key unlocked by a recovery code, derived like a passphrase key
+/// and wiped when dropped
+pub fn derive_recovery_key(code: &[u8; RECOVERY_CODE_LEN], salt: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(code, salt, PASSPHRASE_ITERATIONS, &mut *key);
+    key
+}
+
+/// One-way check value for a recovery key, so an entered code can be
+/// confirmed without storing anything that opens a backup
+pub fn recovery_key_verifier(key: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"recovery-verifier")
+        .chain_update(key)
+        .finalize()
+        .into()
+}
+
+/// Compare tags, checksums or tokens without the time taken revealing
+/// where they first differ; use this instead of `==` for integrity checks.
+/// Slices of different lengths are unequal.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+// Payloads above this many bytes are base64 encoded/decoded in windows
+pub const STREAMING_BASE64_THRESHOLD: usize = 64 * 1024;
+
+// Window sizes for the streaming path. Encode windows are a multiple of 3
+// bytes and decode windows a multiple of 4 characters, so padding can only
+// appear in the last window.
+const ENCODE_WINDOW: usize = 48 * 1024;
+const DECODE_WINDOW: usize = 64 * 1024;
+
+// Base64 encode, appending window by window into one pre-sized string
+// for large payloads instead of growing intermediate buffers
+fn encode_base64(bytes: &[u8]) -> String {
+    if bytes.len() <= STREAMING_BASE64_THRESHOLD {
+        return BASE64.encode(bytes);
+    }
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for window in bytes.chunks(ENCODE_WINDOW) {
+        BASE64.encode_string(window, &mut encoded);
+    }
+    encoded
+}
+
+// Base64 decode, streaming large inputs window by window into one buffer
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    if encoded.len() <= STREAMING_BASE64_THRESHOLD {
+        return BASE64.decode(encoded.as_bytes());
+    }
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    for window in encoded.as_bytes().chunks(DECODE_WINDOW) {
+        BASE64.decode_vec(window, &mut decoded)?;
+    }
+    Ok(decoded)
+}
+
+// Deflated `bytes`, or `None` when they're too short or don't shrink
+fn deflate_if_smaller(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < COMPRESSION_THRESHOLD {
+        return None;
+    }
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).ok()?;
+    encoder.finish().ok().filter(|deflated| deflated.len() < bytes.len())
+}
+
+fn inflate(bytes: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut inflated = Vec::new();
+    DeflateDecoder::new(bytes).read_to_end(&mut inflated)
+        .map_err(|e| CryptoError::MalformedInput(format!("Failed to decompress decrypted data: {}", e)))?;
+    Ok(inflated)
+}
+
+// Encrypt data into its structured form
+pub fn encrypt_payload(data: &str) -> Result<EncryptedData, CryptoError> {
+    encrypt_payload_with_key(data, None)
+}
+
+// Encrypt data with the given raw key, or the app key when `None`
+pub fn encrypt_payload_with_key(data: &str, key_bytes: Option<&[u8; 32]>) -> Result<EncryptedData, CryptoError> {
+    encrypt_payload_with(data, key_bytes, CipherAlgorithm::default(), None)
+}
+
+// Encrypt data with the given cipher and raw key, or the app key when `None`,
+// binding it to `aad` so it only decrypts for the same associated data
+pub fn encrypt_payload_with(
+    data: &str,
+    key_bytes: Option<&[u8; 32]>,
+    algorithm: CipherAlgorithm,
+    aad: Option<&str>,
+) -> Result<EncryptedData, CryptoError> {
+    let key = resolve_key(key_bytes)?;
     
     // Generate random IV (nonce)
-    let iv = Aes256Gcm::generate_nonce(&mut OsRng);
+    let iv_bytes = fresh_nonce()?;
+    
+    // Larger payloads are deflated first, when that makes them smaller
+    let compressed = deflate_if_smaller(data.as_bytes()).map(Zeroizing::new);
+    let plaintext = compressed.as_deref().map_or(data.as_bytes(), Vec::as_slice);
     
     // Encrypt the data
-    let ciphertext = cipher.encrypt(&iv, data.as_bytes().as_ref())
+    let mut ciphertext = algorithm.seal(&key, &iv_bytes, plaintext, aad.unwrap_or_default().as_bytes())
         .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
     
+    // The cipher appends the tag to the ciphertext; store it in its own field
+    let tag = ciphertext.split_off(ciphertext.len() - GCM_TAG_LEN);
+    
     // Create the encrypted data structure
-    let encrypted = EncryptedData {
-        ciphertext: BASE64.encode(&ciphertext),
-        iv: BASE64.encode(iv.as_slice()),
-        tag: String::new(), // AES-GCM includes the tag in the ciphertext
-    };
+    Ok(EncryptedData {
+        ciphertext: encode_base64(&ciphertext),
+        iv: BASE64.encode(iv_bytes),
+        tag: BASE64.encode(&tag),
+        salt: None,
+        format_version: ENCRYPTED_FORMAT_VERSION,
+        compressed: compressed.is_some(),
+        algorithm: algorithm.name().to_string(),
+        aad: aad.map(str::to_string),
+        segments: Vec::new(),
+    })
+}
+
+// Encrypt data and return as JSON string
+pub fn encrypt_data(data: &str) -> Result<String, CryptoError> {
+    encrypt_data_with_algorithm(data, CipherAlgorithm::default())
+}
+
+// Encrypt data with the given cipher and return as JSON string;
+// `decrypt_data` reads the cipher back from the payload
+pub fn encrypt_data_with_algorithm(data: &str, algorithm: CipherAlgorithm) -> Result<String, CryptoError> {
+    let encrypted = encrypt_payload_with(data, None, algorithm, None)?;
     
     // Serialize to JSON
     serde_json::to_string(&encrypted)
         .map_err(|e| CryptoError::EncodingError(e.to_string()))
 }
 
-// Decrypt data from JSON string
-pub fn decrypt_data(encrypted_json: &str) -> Result<String, CryptoError> {
-    // Parse the JSON
-    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
-        .map_err(|e| CryptoError::EncodingError(format!("Invalid JSON format: {}", e)))?;
+// Decrypt data from its structured form
+pub fn decrypt_payload(encrypted: &EncryptedData) -> Result<String, CryptoError> {
+    decrypt_payload_with_key(encrypted, None)
+}
+
+// Decrypt data with the given raw key, or the app key when `None`.
+// Bound data opens with the associated data it records, so this doesn't
+// check who it was bound to; use `decrypt_payload_bound` for that.
+pub fn decrypt_payload_with_key(encrypted: &EncryptedData, key_bytes: Option<&[u8; 32]>) -> Result<String, CryptoError> {
+    decrypt_payload_bound(encrypted, key_bytes, encrypted.aad.as_deref())
+}
+
+// Open a payload sealed in one piece
+fn open_single(encrypted: &EncryptedData, key: &[u8; 32], algorithm: CipherAlgorithm, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    // Decode base64 values
+    let mut ciphertext = decode_base64(&encrypted.ciphertext)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 ciphertext: {}", e)))?;
     
-    // Get the key
+    // Older blobs leave `tag` empty, with the tag still on the end of the ciphertext
+    if !encrypted.tag.is_empty() {
+        let tag = BASE64.decode(encrypted.tag.as_bytes())
+            .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 tag: {}", e)))?;
+        if tag.len() != GCM_TAG_LEN {
+            return Err(CryptoError::MalformedInput("Invalid tag length".to_string()));
+        }
+        ciphertext.extend_from_slice(&tag);
+    }
+    
+    let iv_bytes = BASE64.decode(encrypted.iv.as_bytes())
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 IV: {}", e)))?;
+    
+    let nonce: [u8; 12] = iv_bytes.as_slice().try_into()
+        .map_err(|_| CryptoError::MalformedInput("Invalid IV length".to_string()))?;
+    
+    algorithm.open(key, &nonce, &ciphertext, aad)
+        .map_err(|e| CryptoError::DecryptionError(format!("Decryption failed, data may be tampered: {}", e)))
+}
+
+// Chunked payloads seal each segment under the payload's nonce prefix
+// followed by the segment's index, and bind it to whether it's the last
+// one, so segments can't be reordered, dropped or cut short unnoticed
+const SEGMENT_NONCE_PREFIX_LEN: usize = 8;
+
+fn segment_nonce(prefix: &[u8], index: u32) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..SEGMENT_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[SEGMENT_NONCE_PREFIX_LEN..].copy_from_slice(&index.to_be_bytes());
+    nonce
+}
+
+fn segment_aad(last: bool, aad: &[u8]) -> Vec<u8> {
+    let mut segment_aad = vec![u8::from(last)];
+    segment_aad.extend_from_slice(aad);
+    segment_aad
+}
+
+// Open a chunked payload's segments into one plaintext
+fn open_segments(encrypted: &EncryptedData, key: &[u8; 32], algorithm: CipherAlgorithm, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let prefix = BASE64.decode(encrypted.iv.as_bytes())
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 IV: {}", e)))?;
+    if prefix.len() != SEGMENT_NONCE_PREFIX_LEN {
+        return Err(CryptoError::MalformedInput("Invalid IV length".to_string()));
+    }
+    
+    let mut plaintext = Zeroizing::new(Vec::new());
+    for (index, segment) in encrypted.segments.iter().enumerate() {
+        let sealed = decode_base64(segment)
+            .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64 in segment {}: {}", index, e)))?;
+        let index = u32::try_from(index)
+            .map_err(|_| CryptoError::MalformedInput("Too many segments".to_string()))?;
+        let last = index as usize + 1 == encrypted.segments.len();
+        let opened = Zeroizing::new(algorithm.open(key, &segment_nonce(&prefix, index), &sealed, &segment_aad(last, aad))
+            .map_err(|e| CryptoError::DecryptionError(format!("Segment {} failed to decrypt, data may be tampered: {}", index, e)))?);
+        plaintext.extend_from_slice(&opened);
+    }
+    Ok(std::mem::take(&mut *plaintext))
+}
+
+/// Encrypt `chunks` with the app key, sealing each one as its own segment
+/// with its own nonce and tag, and the last one marked so a truncated list
+/// fails to open. Every segment still ends up in the returned JSON, so this
+/// doesn't use less memory than `encrypt_data`. `decrypt_chunks`, or any of
+/// the single-shot decrypt functions, joins them back up.
+pub fn encrypt_chunks<'a>(chunks: impl Iterator<Item = &'a str>) -> Result<String, CryptoError> {
     let key = get_encryption_key()?;
-    let cipher = Aes256Gcm::new(&key);
+    let algorithm = CipherAlgorithm::default();
+    let nonce = fresh_nonce()?;
+    let prefix = &nonce[..SEGMENT_NONCE_PREFIX_LEN];
     
-    // Decode base64 values
-    let ciphertext = BASE64.decode(encrypted.ciphertext.as_bytes())
-        .map_err(|e| CryptoError::EncodingError(format!("Invalid base64 ciphertext: {}", e)))?;
+    // No chunks at all still gets one, empty, segment
+    let mut chunks = chunks.peekable();
+    let empty = chunks.peek().is_none();
+    let mut chunks = chunks.chain(empty.then_some("")).peekable();
     
-    let iv_bytes = BASE64.decode(encrypted.iv.as_bytes())
-        .map_err(|e| CryptoError::EncodingError(format!("Invalid base64 IV: {}", e)))?;
+    let mut segments = Vec::new();
+    let mut index: u32 = 0;
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let sealed = algorithm.seal(&key, &segment_nonce(prefix, index), chunk.as_bytes(), &segment_aad(last, &[]))
+            .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+        segments.push(encode_base64(&sealed));
+        index = index.checked_add(1)
+            .ok_or_else(|| CryptoError::EncryptionError("Too many chunks".to_string()))?;
+    }
+    
+    let encrypted = EncryptedData {
+        ciphertext: String::new(),
+        iv: BASE64.encode(prefix),
+        tag: String::new(),
+        salt: None,
+        format_version: ENCRYPTED_FORMAT_VERSION,
+        compressed: false,
+        algorithm: algorithm.name().to_string(),
+        aad: None,
+        segments,
+    };
+    serde_json::to_string(&encrypted)
+        .map_err(|e| CryptoError::EncodingError(e.to_string()))
+}
+
+/// Decrypt JSON from `encrypt_chunks` back into the joined plaintext
+pub fn decrypt_chunks(encrypted_json: &str) -> Result<String, CryptoError> {
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid JSON format: {}", e)))?;
+    if encrypted.segments.is_empty() {
+        return Err(CryptoError::MalformedInput("Data wasn't encrypted in chunks".to_string()));
+    }
     
-    if iv_bytes.len() != 12 {
-        return Err(CryptoError::DecryptionError("Invalid IV length".to_string()));
+    decrypt_payload(&encrypted)
+}
+
+/// Decrypt data that must be bound to `aad`, failing with a clear error
+/// when it was encrypted for other associated data or none
+pub fn decrypt_payload_bound(encrypted: &EncryptedData, key_bytes: Option<&[u8; 32]>, aad: Option<&str>) -> Result<String, CryptoError> {
+    if encrypted.aad.as_deref() != aad {
+        return Err(CryptoError::AadMismatch(format!(
+            "encrypted for {}, expected {}",
+            encrypted.aad.as_deref().map_or("nothing".to_string(), |bound| format!("\"{}\"", bound)),
+            aad.map_or("nothing".to_string(), |expected| format!("\"{}\"", expected)),
+        )));
     }
     
-    // Create nonce from bytes
-    let nonce = Nonce::from_slice(&iv_bytes);
+    // Get the key
+    let key = resolve_key(key_bytes)?;
+    
+    // Layouts from newer builds can't be read reliably, so say so up front
+    if encrypted.format_version > ENCRYPTED_FORMAT_VERSION {
+        return Err(CryptoError::Unsupported(format!("format version {}", encrypted.format_version)));
+    }
+    let algorithm = CipherAlgorithm::from_name(&encrypted.algorithm)
+        .ok_or_else(|| CryptoError::Unsupported(format!("cipher {}", encrypted.algorithm)))?;
     
     // Decrypt the data
-    let plaintext = cipher.decrypt(nonce, ciphertext.as_ref())
+    let aad = aad.unwrap_or_default().as_bytes();
+    let mut plaintext = Zeroizing::new(if encrypted.segments.is_empty() {
+        open_single(encrypted, &key, algorithm, aad)?
+    } else {
+        open_segments(encrypted, &key, algorithm, aad)?
+    });
+    
+    if encrypted.compressed {
+        plaintext = Zeroizing::new(inflate(&plaintext)?);
+    }
+    
+    // Convert bytes to string. The buffer moves into the string rather than
+    // being copied, and is wiped if it turns out not to be text.
+    String::from_utf8(std::mem::take(&mut *plaintext)).map_err(|e| {
+        let message = format!("Invalid UTF-8 in decrypted data: {}", e.utf8_error());
+        drop(Zeroizing::new(e.into_bytes()));
+        CryptoError::MalformedInput(message)
+    })
+}
+
+// Decrypt data from JSON string that must be bound to `aad`
+pub fn decrypt_data_with_aad(encrypted_json: &str, aad: &str) -> Result<String, CryptoError> {
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid JSON format: {}", e)))?;
+    
+    decrypt_payload_bound(&encrypted, None, Some(aad))
+}
+
+// Decrypt data from JSON string
+pub fn decrypt_data(encrypted_json: &str) -> Result<String, CryptoError> {
+    // Parse the JSON
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid JSON format: {}", e)))?;
+    
+    decrypt_payload(&encrypted)
+}
+
+// Encrypt with the given raw key into one compact URL-safe string:
+// unpadded base64url of the IV followed by the ciphertext and tag
+pub fn encrypt_url_safe(data: &str, key_bytes: &[u8; 32]) -> Result<String, CryptoError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+    
+    let iv_bytes = fresh_nonce()?;
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&iv_bytes), data.as_bytes())
+        .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+    
+    let mut blob = iv_bytes.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(URL_SAFE_NO_PAD.encode(blob))
+}
+
+// Decrypt a string produced by `encrypt_url_safe` with the same key
+pub fn decrypt_url_safe(encoded: &str, key_bytes: &[u8; 32]) -> Result<String, CryptoError> {
+    let blob = URL_SAFE_NO_PAD.decode(encoded.as_bytes())
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid base64url data: {}", e)))?;
+    if blob.len() < 12 {
+        return Err(CryptoError::MalformedInput("Data too short to hold an IV".to_string()));
+    }
+    let (iv_bytes, ciphertext) = blob.split_at(12);
+    
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+    let plaintext = cipher.decrypt(Nonce::from_slice(iv_bytes), ciphertext)
         .map_err(|e| CryptoError::DecryptionError(format!("Decryption failed, data may be tampered: {}", e)))?;
     
-    // Convert bytes to string
     String::from_utf8(plaintext)
-        .map_err(|e| CryptoError::DecryptionError(format!("Invalid UTF-8 in decrypted data: {}", e)))
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid UTF-8 in decrypted data: {}", e)))
+}
+
+// Non-secret details of an encrypted blob, to help diagnose failed decryption
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct EncryptedMeta {
+    pub algorithm: &'static str,
+    /// `version` field next to the encrypted fields, if the blob has one
+    pub version: Option<String>,
+    /// Decoded lengths in bytes; `None` when the field isn't valid base64
+    pub ciphertext_len: Option<usize>,
+    pub iv_len: Option<usize>,
+    pub tag_len: Option<usize>,
+}
+
+impl EncryptedMeta {
+    fn describe(encrypted: &EncryptedData, version: Option<String>) -> Self {
+        let decoded_len = |field: &str| BASE64.decode(field.as_bytes()).ok().map(|bytes| bytes.len());
+        EncryptedMeta {
+            algorithm: CipherAlgorithm::from_name(&encrypted.algorithm).map_or("unknown", |algorithm| algorithm.name()),
+            version,
+            ciphertext_len: decoded_len(&encrypted.ciphertext),
+            iv_len: decoded_len(&encrypted.iv),
+            tag_len: decoded_len(&encrypted.tag),
+        }
+    }
+}
+
+// Decrypt data from JSON string, returning the blob's metadata alongside any error
+// so a wrong key can be told apart from a structurally broken blob
+pub fn decrypt_data_verbose(encrypted_json: &str) -> Result<String, (CryptoError, Option<EncryptedMeta>)> {
+    decrypt_data_verbose_with_key(encrypted_json, None)
+}
+
+fn decrypt_data_verbose_with_key(encrypted_json: &str, key_bytes: Option<&[u8; 32]>) -> Result<String, (CryptoError, Option<EncryptedMeta>)> {
+    let encrypted: EncryptedData = serde_json::from_str(encrypted_json)
+        .map_err(|e| (CryptoError::MalformedInput(format!("Invalid JSON format: {}", e)), None))?;
+    
+    let version = serde_json::from_str::<serde_json::Value>(encrypted_json)
+        .ok()
+        .and_then(|value| value.get("version")?.as_str().map(str::to_string));
+    
+    decrypt_payload_with_key(&encrypted, key_bytes)
+        .map_err(|err| (err, Some(EncryptedMeta::describe(&encrypted, version))))
 }
 
-// Verify data integrity without decrypting fully
+/// Check that encrypted data is intact and opens with the app key, without
+/// handing back the plaintext. `Ok(false)` means the authentication tag
+/// didn't verify: it was tampered with or needs another key. Data this build
+/// can't read, or that isn't an encrypted payload at all, is an error.
 pub fn verify_data_integrity(encrypted_json: &str) -> Result<bool, CryptoError> {
-    // This is a lightweight check that the JSON is valid and has expected fields
-    match serde_json::from_str::<EncryptedData>(encrypted_json) {
-        Ok(_) => Ok(true),  // Structure is valid
-        Err(e) => Err(CryptoError::EncodingError(format!("Invalid encrypted data format: {}", e)))
+    let encrypted = serde_json::from_str::<EncryptedData>(encrypted_json)
+        .map_err(|e| CryptoError::MalformedInput(format!("Invalid encrypted data format: {}", e)))?;
+    
+    // Decrypting checks the authentication tag; the plaintext is wiped unread
+    match decrypt_payload(&encrypted) {
+        Ok(plaintext) => {
+            drop(Zeroizing::new(plaintext));
+            Ok(true)
+        },
+        Err(CryptoError::DecryptionError(_)) => Ok(false),
+        Err(err) => Err(err),
     }
-    // Note: Full integrity verification happens during decryption with AES-GCM
 }
 
 #[cfg(test)]
@@ -142,6 +794,204 @@ mod tests {
         assert_eq!(decrypted, original_data, "Decrypted data should match original");
     }    
         
+    #[wasm_bindgen_test]
+    fn test_tag_is_split_from_ciphertext() {
+        let original_data = r#"{"player_id":"split_tag","dark_mode":true}"#;
+        let encrypted = encrypt_payload(original_data).expect("Encryption should succeed");
+        
+        assert_eq!(BASE64.decode(&encrypted.tag).unwrap().len(), GCM_TAG_LEN);
+        assert_eq!(BASE64.decode(&encrypted.ciphertext).unwrap().len(), original_data.len(), "GCM adds no padding");
+        assert_eq!(decrypt_payload(&encrypted).unwrap(), original_data);
+        
+        let json = serde_json::to_string(&encrypted).unwrap();
+        assert_eq!(decrypt_data(&json).unwrap(), original_data);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_embedded_tag_still_decrypts() {
+        let original_data = r#"{"player_id":"embedded_tag","dark_mode":false}"#;
+        let split = encrypt_payload(original_data).expect("Encryption should succeed");
+        
+        // Older exports kept the tag on the end of the ciphertext and left `tag` empty
+        let mut combined = BASE64.decode(&split.ciphertext).unwrap();
+        combined.extend(BASE64.decode(&split.tag).unwrap());
+        let legacy = EncryptedData { ciphertext: BASE64.encode(&combined), tag: String::new(), format_version: 0, ..split.clone() };
+        assert_eq!(decrypt_payload(&legacy).unwrap(), original_data);
+        
+        let wrong_length = EncryptedData { tag: BASE64.encode([0u8; 8]), ..split };
+        assert!(matches!(decrypt_payload(&wrong_length), Err(CryptoError::MalformedInput(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_unknown_format_version_is_rejected() {
+        let original_data = r#"{"player_id":"versioned","dark_mode":false}"#;
+        let encrypted = encrypt_payload(original_data).expect("Encryption should succeed");
+        assert_eq!(encrypted.format_version, ENCRYPTED_FORMAT_VERSION);
+        
+        let mut future: serde_json::Value = serde_json::to_value(&encrypted).unwrap();
+        future["format_version"] = serde_json::json!(99);
+        match decrypt_data(&future.to_string()) {
+            Err(CryptoError::Unsupported(message)) => assert_eq!(message, "format version 99"),
+            other => panic!("Expected a version error, got {:?}", other),
+        }
+        
+        // Payloads from before versioning read as version 0
+        let mut unversioned = serde_json::to_value(&encrypted).unwrap();
+        unversioned.as_object_mut().unwrap().remove("format_version");
+        let unversioned: EncryptedData = serde_json::from_value(unversioned).unwrap();
+        assert_eq!(unversioned.format_version, 0);
+        assert_eq!(decrypt_payload(&unversioned).unwrap(), original_data);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_both_ciphers_round_trip() {
+        let original_data = r#"{"player_id":"cipher_test","dark_mode":true}"#;
+        for algorithm in [CipherAlgorithm::Aes256Gcm, CipherAlgorithm::ChaCha20Poly1305] {
+            let encrypted = encrypt_data_with_algorithm(original_data, algorithm).expect("Encryption should succeed");
+            let payload: EncryptedData = serde_json::from_str(&encrypted).unwrap();
+            assert_eq!(payload.algorithm, algorithm.name());
+            assert_eq!(decrypt_data(&encrypted).unwrap(), original_data, "{} should round trip", algorithm.name());
+        }
+        
+        // The stored name picks the cipher, so a relabelled payload fails authentication
+        let mut relabelled: EncryptedData = serde_json::from_str(&encrypt_data(original_data).unwrap()).unwrap();
+        relabelled.algorithm = CipherAlgorithm::ChaCha20Poly1305.name().to_string();
+        assert!(matches!(decrypt_payload(&relabelled), Err(CryptoError::DecryptionError(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_unknown_cipher_is_rejected() {
+        let mut payload: EncryptedData = serde_json::from_str(&encrypt_data("secret").unwrap()).unwrap();
+        payload.algorithm = "ROT13".to_string();
+        match decrypt_payload(&payload) {
+            Err(CryptoError::Unsupported(message)) => assert_eq!(message, "cipher ROT13"),
+            other => panic!("Expected a cipher error, got {:?}", other),
+        }
+        
+        // Payloads from before the field existed were AES-256-GCM
+        let mut unlabelled = serde_json::to_value(encrypt_payload("secret").unwrap()).unwrap();
+        unlabelled.as_object_mut().unwrap().remove("algorithm");
+        assert_eq!(decrypt_data(&unlabelled.to_string()).unwrap(), "secret");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_associated_data_must_match() {
+        let original_data = r#"{"player_id":"bound_player"}"#;
+        let bound = encrypt_payload_with(original_data, None, CipherAlgorithm::default(), Some("bound_player")).unwrap();
+        assert_eq!(bound.aad.as_deref(), Some("bound_player"));
+        let json = serde_json::to_string(&bound).unwrap();
+        
+        assert_eq!(decrypt_data_with_aad(&json, "bound_player").unwrap(), original_data);
+        match decrypt_data_with_aad(&json, "other_player") {
+            Err(CryptoError::AadMismatch(message)) => assert!(message.contains("expected \"other_player\""), "{}", message),
+            other => panic!("Expected a mismatch error, got {:?}", other),
+        }
+        assert!(matches!(decrypt_data_with_aad(&encrypt_data(original_data).unwrap(), "bound_player"), Err(CryptoError::AadMismatch(_))));
+        
+        // Relabelling the recorded binding fails authentication
+        let relabelled = EncryptedData { aad: Some("other_player".to_string()), ..bound };
+        assert!(matches!(decrypt_payload(&relabelled), Err(CryptoError::DecryptionError(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_password_encryption_round_trip() {
+        let original_data = r#"{"player_id":"password_player","dark_mode":true}"#;
+        let encrypted = encrypt_data_with_password(original_data, "correct horse").expect("Encryption should succeed");
+        
+        let payload: EncryptedData = serde_json::from_str(&encrypted).unwrap();
+        assert_eq!(payload.salt.as_deref().map(|salt| BASE64.decode(salt).unwrap().len()), Some(PASSPHRASE_SALT_LEN));
+        assert_eq!(decrypt_data_with_password(&encrypted, "correct horse").unwrap(), original_data);
+        assert!(matches!(decrypt_data_with_password(&encrypted, "wrong horse"), Err(CryptoError::DecryptionError(_))));
+        assert!(decrypt_data(&encrypted).is_err(), "The app key must not open password-encrypted data");
+        
+        // Data without a salt wasn't password-encrypted
+        let plain = encrypt_data(original_data).unwrap();
+        assert!(matches!(decrypt_data_with_password(&plain, "correct horse"), Err(CryptoError::KeyError(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_password_key_is_deterministic() {
+        let salt = [3u8; PASSPHRASE_SALT_LEN];
+        let key = derive_key_from_password("same password", &salt).unwrap();
+        assert_eq!(key, derive_key_from_password("same password", &salt).unwrap());
+        assert_ne!(key, derive_key_from_password("same password", &[4u8; PASSPHRASE_SALT_LEN]).unwrap());
+        assert_ne!(key, derive_key_from_password("other password", &salt).unwrap());
+        assert!(matches!(derive_key_from_password("same password", &[]), Err(CryptoError::KeyError(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_key_material_is_wiped() {
+        use zeroize::Zeroize;
+        
+        let mut key = get_encryption_key().expect("The app key should be available");
+        assert!(key.iter().any(|&byte| byte != 0));
+        key.zeroize();
+        assert!(key.iter().all(|&byte| byte == 0), "Zeroizing should clear every byte");
+        drop(key);
+        
+        // Keys and plaintext buffers are dropped on every path, including failures
+        let encrypted = encrypt_payload("wiped after use").unwrap();
+        assert_eq!(decrypt_payload(&encrypted).unwrap(), "wiped after use");
+        assert!(decrypt_payload_with_key(&encrypted, Some(&[9u8; 32])).is_err());
+        
+        let key = get_encryption_key().unwrap();
+        let iv = fresh_nonce().unwrap();
+        let sealed = CipherAlgorithm::Aes256Gcm.seal(&key, &iv, &[0xff, 0xfe], &[]).unwrap();
+        let not_text = EncryptedData {
+            ciphertext: BASE64.encode(&sealed),
+            iv: BASE64.encode(iv),
+            tag: String::new(),
+            ..encrypt_payload("").unwrap()
+        };
+        assert!(matches!(decrypt_payload(&not_text), Err(CryptoError::MalformedInput(_))), "Invalid UTF-8 is wiped and reported");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_malformed_input_is_told_apart_from_tampering() {
+        let encrypted = encrypt_payload("structure check").unwrap();
+        
+        let truncated_iv = EncryptedData { iv: BASE64.encode(&BASE64.decode(&encrypted.iv).unwrap()[..8]), ..encrypted.clone() };
+        match decrypt_payload(&truncated_iv) {
+            Err(CryptoError::MalformedInput(message)) => assert_eq!(message, "Invalid IV length"),
+            other => panic!("A truncated IV should be malformed input, got {:?}", other),
+        }
+        let bad_base64 = EncryptedData { ciphertext: "not base64!".to_string(), ..encrypted.clone() };
+        assert!(matches!(decrypt_payload(&bad_base64), Err(CryptoError::MalformedInput(_))));
+        assert!(matches!(decrypt_data(r#"{"iv":"AAAA"}"#), Err(CryptoError::MalformedInput(_))), "Missing fields are malformed");
+        
+        // Well-formed data that fails authentication
+        let mut ciphertext = BASE64.decode(&encrypted.ciphertext).unwrap();
+        ciphertext[0] ^= 0x01;
+        let tampered = EncryptedData { ciphertext: BASE64.encode(&ciphertext), ..encrypted };
+        assert!(matches!(decrypt_payload(&tampered), Err(CryptoError::DecryptionError(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_chunked_round_trip_matches_single_shot() {
+        let payload: String = (0..1024 * 1024).map(|i| char::from(b'a' + (i * 7 % 26) as u8)).collect();
+        let chunks = || payload.as_bytes().chunks(64 * 1024).map(|chunk| std::str::from_utf8(chunk).unwrap());
+        
+        let chunked = encrypt_chunks(chunks()).expect("Chunked encryption should succeed");
+        let single = encrypt_data(&payload).expect("Single-shot encryption should succeed");
+        assert_eq!(decrypt_chunks(&chunked).unwrap(), payload);
+        assert_eq!(decrypt_chunks(&chunked).unwrap(), decrypt_data(&single).unwrap());
+        assert_eq!(decrypt_data(&chunked).unwrap(), payload, "The single-shot path reads chunked data too");
+        
+        let encrypted: EncryptedData = serde_json::from_str(&chunked).unwrap();
+        assert_eq!(encrypted.segments.len(), 16);
+        
+        // Segments can't be reordered or cut short
+        let mut reordered = encrypted.clone();
+        reordered.segments.swap(0, 1);
+        assert!(matches!(decrypt_payload(&reordered), Err(CryptoError::DecryptionError(_))));
+        let mut truncated = encrypted;
+        truncated.segments.pop();
+        assert!(matches!(decrypt_payload(&truncated), Err(CryptoError::DecryptionError(_))));
+        
+        assert_eq!(decrypt_chunks(&encrypt_chunks(std::iter::empty()).unwrap()).unwrap(), "");
+        assert!(matches!(decrypt_chunks(&single), Err(CryptoError::MalformedInput(_))));
+    }
+    
     #[wasm_bindgen_test]
     fn test_tampering_detection() {
         let original_data = r#"{"player_id":"tamper_test","dark_mode":false}"#;
@@ -228,10 +1078,39 @@ mod tests {
         let integrity = verify_data_integrity(&encrypted);
         assert!(integrity.is_ok() && integrity.unwrap(), "Integrity check should succeed for valid data");
         
+        // Well-formed, but with one ciphertext byte flipped
+        let mut tampered: EncryptedData = serde_json::from_str(&encrypted).unwrap();
+        let mut ciphertext = BASE64.decode(&tampered.ciphertext).unwrap();
+        ciphertext[0] ^= 0x01;
+        tampered.ciphertext = BASE64.encode(&ciphertext);
+        let integrity = verify_data_integrity(&serde_json::to_string(&tampered).unwrap());
+        assert!(matches!(integrity, Ok(false)), "Integrity check should fail for tampered data: {:?}", integrity);
+        
         // Test with valid JSON but invalid structure
         let invalid = r#"{"not_cipher":"test","not_iv":"test"}"#;
         let integrity = verify_data_integrity(invalid);
         assert!(integrity.is_err(), "Integrity check should fail for invalid structure");
+        
+        // Data from a newer build or with an unknown cipher can't be judged, so it's an error
+        let mut future: EncryptedData = serde_json::from_str(&encrypted).unwrap();
+        future.format_version = ENCRYPTED_FORMAT_VERSION + 1;
+        let integrity = verify_data_integrity(&serde_json::to_string(&future).unwrap());
+        assert!(matches!(integrity, Err(CryptoError::Unsupported(_))), "{:?}", integrity);
+        let mut unknown: EncryptedData = serde_json::from_str(&encrypted).unwrap();
+        unknown.algorithm = "ROT13".to_string();
+        let integrity = verify_data_integrity(&serde_json::to_string(&unknown).unwrap());
+        assert!(matches!(integrity, Err(CryptoError::Unsupported(_))), "{:?}", integrity);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_recovery_code_text_round_trips() {
+        let bytes: [u8; RECOVERY_CODE_LEN] = std::array::from_fn(|i| (i * 17) as u8);
+        let code = format_recovery_code(&bytes);
+        assert_eq!(code.len(), RECOVERY_CODE_LEN * 2 + 7, "Eight groups of four digits");
+        assert_eq!(parse_recovery_code(&code), Some(bytes));
+        assert_eq!(parse_recovery_code(&code.to_lowercase().replace('-', "  ")), Some(bytes));
+        assert_eq!(parse_recovery_code("correct horse battery staple"), None);
+        assert_eq!(parse_recovery_code(&code[..code.len() - 1]), None);
     }
     
     #[wasm_bindgen_test]
@@ -254,6 +1133,73 @@ mod tests {
         assert_eq!(decrypted, large_data, "Large data should survive round trip");
     }
     
+    #[wasm_bindgen_test]
+    fn test_large_payload_is_compressed() {
+        let mut large_data = String::from(r#"{"player_id":"compressed_test","items":["#);
+        for i in 0..100 {
+            if i > 0 {
+                large_data.push_str(",");
+            }
+            large_data.push_str(&format!(r#"{{"id":{},"name":"Item {}","value":{}}}"#, i, i, i * 10));
+        }
+        large_data.push_str("]}");
+        
+        let encrypted = encrypt_payload(&large_data).expect("Encryption should succeed");
+        assert!(encrypted.compressed);
+        let ciphertext_len = BASE64.decode(&encrypted.ciphertext).unwrap().len();
+        assert!(ciphertext_len * 2 < large_data.len(), "{} bytes encrypted to {}", large_data.len(), ciphertext_len);
+        assert_eq!(decrypt_payload(&encrypted).unwrap(), large_data);
+        
+        // Short payloads are left as they are
+        let small_data = r#"{"player_id":"small"}"#;
+        let encrypted = encrypt_payload(small_data).expect("Encryption should succeed");
+        assert!(!encrypted.compressed);
+        assert_eq!(BASE64.decode(&encrypted.ciphertext).unwrap().len(), small_data.len());
+        assert_eq!(decrypt_payload(&encrypted).unwrap(), small_data);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_streaming_base64_matches_one_shot() {
+        // Not a multiple of either window, so the last window is partial and padded
+        let bytes: Vec<u8> = (0..STREAMING_BASE64_THRESHOLD * 3 + 7).map(|i| (i * 31 % 251) as u8).collect();
+
+        let encoded = encode_base64(&bytes);
+        assert_eq!(encoded, BASE64.encode(&bytes), "Streaming encode should match one-shot encode");
+        assert_eq!(decode_base64(&encoded).unwrap(), bytes, "Streaming decode should restore the bytes");
+
+        let mut corrupted = encoded.clone();
+        corrupted.replace_range(DECODE_WINDOW + 10..DECODE_WINDOW + 11, "!");
+        assert!(decode_base64(&corrupted).is_err(), "Invalid characters past the first window should be caught");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_large_payload_round_trip_uses_streaming_path() {
+        // ~1 MB, well past the streaming threshold
+        let record = r#"{"id":12345,"name":"Streaming ✓","notes":"lorem ipsum dolor sit amet"},"#;
+        let large_data = format!("[{}]", record.repeat(1024 * 1024 / record.len()).trim_end_matches(','));
+        assert!(large_data.len() > STREAMING_BASE64_THRESHOLD * 10);
+
+        let encrypted = encrypt_data(&large_data).expect("Encryption of a large payload should succeed");
+        let decrypted = decrypt_data(&encrypted).expect("Decryption of a large payload should succeed");
+
+        assert_eq!(decrypted.len(), large_data.len());
+        assert!(decrypted == large_data, "Large payload should survive the streaming round trip");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_passphrase_key_round_trip() {
+        let salt = new_passphrase_salt().expect("Salt should be generated");
+        let key = derive_passphrase_key("correct horse battery staple", &salt);
+        assert_eq!(key, derive_passphrase_key("correct horse battery staple", &salt), "Derivation should be deterministic");
+        assert_ne!(key, derive_passphrase_key("correct horse battery staple", &[0u8; PASSPHRASE_SALT_LEN]), "Salt should change the key");
+        
+        let encrypted = encrypt_payload_with_key("secret", Some(&key)).unwrap();
+        assert_eq!(decrypt_payload_with_key(&encrypted, Some(&key)).unwrap(), "secret");
+        
+        let wrong_key = derive_passphrase_key("wrong passphrase", &salt);
+        assert!(matches!(decrypt_payload_with_key(&encrypted, Some(&wrong_key)), Err(CryptoError::DecryptionError(_))));
+    }
+    
     #[wasm_bindgen_test]
     fn test_special_characters() {
         // Test with special characters and unicode
@@ -266,4 +1212,75 @@ mod tests {
         // Verify round trip
         assert_eq!(decrypted, special_data, "Special characters should survive round trip");
     }
-}
\ No newline at end of file
+    
+    #[wasm_bindgen_test]
+    fn test_encrypt_reports_missing_rng() {
+        set_random_source(|_| Err("crypto.getRandomValues is not available".to_string()));
+        
+        let result = encrypt_data("secret");
+        assert!(!crypto_available(), "Crypto should be reported unavailable");
+        reset_random_source();
+        
+        assert!(matches!(result, Err(CryptoError::RngUnavailable(_))), "Encryption should fail cleanly: {:?}", result);
+        assert!(crypto_available(), "Crypto should be available again after reset");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_repeated_nonce_is_redrawn() {
+        // The first two draws repeat, as a broken RNG might
+        let draws = Rc::new(std::cell::Cell::new(0u8));
+        let source_draws = draws.clone();
+        set_random_source(move |buf| {
+            let draw = source_draws.get();
+            source_draws.set(draw + 1);
+            buf.fill(if draw < 2 { 0x5a } else { 0x5a ^ draw });
+            Ok(())
+        });
+        
+        let first = encrypt_payload("first").expect("First encryption should succeed");
+        let second = encrypt_payload("second").expect("A repeated nonce should be redrawn, not fail");
+        assert_ne!(first.iv, second.iv, "The nonce must not be reused");
+        assert_eq!(draws.get(), 3, "The repeat should cost exactly one extra draw");
+        assert_eq!(decrypt_payload(&second).unwrap(), "second");
+        
+        // A source that only repeats can't be recovered from
+        set_random_source(|buf| {
+            buf.fill(0x5a);
+            Ok(())
+        });
+        let result = encrypt_payload("third");
+        reset_random_source();
+        assert!(matches!(result, Err(CryptoError::RngUnavailable(_))), "{:?}", result);
+        assert_eq!(decrypt_payload(&first).unwrap(), "first");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_verbose_decrypt_reports_metadata_on_wrong_key() {
+        let encrypted = encrypt_data("secret").expect("Encryption should succeed");
+        
+        let (err, meta) = decrypt_data_verbose_with_key(&encrypted, Some(&[9u8; 32]))
+            .expect_err("Decrypting with the wrong key should fail");
+        assert!(matches!(err, CryptoError::DecryptionError(_)), "Wrong key should be a decryption error: {:?}", err);
+        
+        let meta = meta.expect("Well-formed blobs should come with metadata");
+        assert_eq!(meta.algorithm, "AES-256-GCM");
+        assert_eq!(meta.iv_len, Some(12), "IV length should be reported");
+        assert!(meta.ciphertext_len.unwrap() > 0);
+        
+        // Structurally broken blobs have no metadata to give
+        let (_, meta) = decrypt_data_verbose("not json").expect_err("Garbage should fail");
+        assert!(meta.is_none());
+        
+        assert_eq!(decrypt_data_verbose(&encrypted).unwrap(), "secret", "The right key should still decrypt");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"tag-1234", b"tag-1234"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"tag-1234", b"tag-1235"), "Same length, last byte differs");
+        assert!(!constant_time_eq(b"xag-1234", b"tag-1234"), "Same length, first byte differs");
+        assert!(!constant_time_eq(b"tag-1234", b"tag-123"), "Different lengths");
+        assert!(!constant_time_eq(b"", b"t"));
+    }
+}
@@ -1,52 +1,600 @@
 use leptos::ev::play;
 use leptos::*;
 use leptos::prelude::*;
-use crate::utils::get_player_id;
+use crate::utils::{get_player_id, get_locale, set_locale, get_storage_item_required, batch_write_verified, storage_usage, stored_size, StorageError, STORAGE_QUOTA_ESTIMATE};
+use crate::crypto::{
+    constant_time_eq, derive_recovery_key, format_recovery_code, new_passphrase_salt, parse_recovery_code,
+    random_bytes, recovery_key_verifier, CryptoError, PassphraseKey, RECOVERY_CODE_LEN,
+};
 use crate::theme::{
+    Theme,
+    AccentPicker,
+    ContrastCheck,
+    load_accent,
+    load_theme,
+    ThemeState,
     use_theme,
+    use_dark_mode,
     use_dark_mode_toggle_button_class, 
     use_button_class, 
-    use_data_panel_class, 
-    use_data_header_class, 
-    use_data_close_button_class, 
-    use_data_content_class,
     use_error_message_class, 
-    use_player_id_class
+    use_player_id_class,
+    use_data_panel_class,
+    use_data_header_class,
+    use_data_content_class,
 };
-use log::{error, info};
+use log::{error, info, warn, Level};
+use crate::logging::{log_event, LogCategory};
 use wasm_bindgen::prelude::*;
 use serde::{Serialize, Deserialize};
 use serde_json::{json, Value};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use web_sys::{Blob, BlobPropertyBag, Url, HtmlAnchorElement, Document};
 use js_sys;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use gloo_timers::future::TimeoutFuture;
+use zeroize::Zeroizing;
+use leptos::task::spawn_local;
 use crate::utils::localStorage;
+use crate::panel::{Panel, PanelId, use_panel_nav};
+use crate::copy_button::CopyButton;
+use crate::invite::CopyInviteLink;
+use crate::confirm_modal::{ConfirmModal, Severity};
+use crate::passphrase_prompt::{PassphraseEntry, PassphrasePrompt};
+use crate::profile::ProfileNameError;
+use crate::qr_export::QrExport;
+use crate::safe_mode::is_safe_mode;
+use crate::event_bus::{use_event_bus, AppEvent, EventBus};
+use crate::session_lock::{auto_lock_after, session_key, set_auto_lock_after, unlock_session, use_idle_auto_lock, DEFAULT_AUTO_LOCK_AFTER};
+use crate::data_service::use_data_service;
+use crate::unsaved_changes::use_unsaved_changes;
+use crate::announcer::use_announcer;
+use crate::autosave::{autosave_enabled, set_autosave_enabled, has_autosave, schedule_autosave, restore_autosave};
+use crate::clock;
 
 // Data export type
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ExportedData {
     pub version: String,
     pub timestamp: String,
     pub data: ExportedAppData,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ExportedAppData {
     pub player_id: String,
     pub dark_mode: bool,
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Values of other registered backup keys, by storage key
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl ExportedAppData {
+    /// The theme this data describes, falling back to `dark_mode` for
+    /// backups made before the theme name was stored
+    pub fn theme(&self) -> Theme {
+        crate::theme::resolve_theme(self.extra.get(crate::theme::THEME_KEY).map(String::as_str), self.dark_mode)
+    }
+}
+
+/// A storage key that backups know about
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupKey {
+    pub key: String,
+    pub included_by_default: bool,
+}
+
+// Keys with their own fields in `ExportedAppData`
+const CORE_BACKUP_KEYS: [&str; 3] = ["player_id", "dark_mode", "locale"];
+
+thread_local! {
+    static BACKUP_KEYS: RefCell<Vec<BackupKey>> = RefCell::new(
+        CORE_BACKUP_KEYS
+            .iter()
+            .map(|key| BackupKey { key: key.to_string(), included_by_default: true })
+            .collect()
+    );
+}
+
+/// Register a storage key a module persists so backups can find it.
+/// Registering a key again updates whether it's included by default.
+pub fn register_backup_key(key: &str, included_by_default: bool) {
+    BACKUP_KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        match keys.iter_mut().find(|k| k.key == key) {
+            Some(existing) => existing.included_by_default = included_by_default,
+            None => keys.push(BackupKey { key: key.to_string(), included_by_default }),
+        }
+    });
+}
+
+/// Every storage key the app has registered for backups
+pub fn registered_backup_keys() -> Vec<BackupKey> {
+    BACKUP_KEYS.with(|keys| keys.borrow().clone())
+}
+
+// Keys the app stores but never backs up
+const LOCAL_ONLY_KEYS: [&str; 4] = [
+    crate::autosave::AUTOSAVE_KEY,
+    crate::autosave::AUTOSAVE_ENABLED_KEY,
+    PASSPHRASE_REQUIRED_KEY,
+    crate::session_lock::AUTO_LOCK_KEY,
+];
+
+/// Every storage key the app uses: registered backup keys, then local-only ones
+pub fn list_storage_keys() -> Vec<String> {
+    registered_backup_keys()
+        .into_iter()
+        .map(|k| k.key)
+        .chain(LOCAL_ONLY_KEYS.iter().map(|key| key.to_string()))
+        .collect()
+}
+
+// Whether a backup's `extra` may write `key`. Only registered non-core keys
+// qualify, so a crafted backup can't overwrite local-only keys such as the
+// autosave or passphrase settings, or slip core keys past the import mode.
+fn is_importable_extra_key(key: &str) -> bool {
+    !CORE_BACKUP_KEYS.contains(&key) && registered_backup_keys().iter().any(|k| k.key == key)
+}
+
+// The `extra` values an import may write, logging the ones it skips
+fn importable_extra(extra: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    extra
+        .iter()
+        .filter(|(key, _)| {
+            let importable = is_importable_extra_key(key);
+            if !importable {
+                warn!("Skipping unregistered key in backup: {}", key);
+            }
+            importable
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+// Read the registered non-core keys that exports include by default
+fn collect_extra_backup_values() -> BTreeMap<String, String> {
+    registered_backup_keys()
+        .into_iter()
+        .filter(|k| k.included_by_default && !CORE_BACKUP_KEYS.contains(&k.key.as_str()))
+        .filter_map(|k| {
+            let value = localStorage::get_storage_item(&k.key).ok().flatten()?;
+            Some((k.key, value))
+        })
+        .collect()
+}
+
+// Schema version written into new backups
+pub const EXPORT_VERSION: &str = "0.1.0";
+
+// Backup layout with cleartext metadata and only the app data encrypted
+pub const SPLIT_FORMAT: &str = "split-v1";
+
+/// Backup envelope readable without the key.
+/// `data` holds an `EncryptedData` object when `encrypted` is set,
+/// otherwise the plain `ExportedAppData`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BackupEnvelope {
+    pub version: String,
+    pub timestamp: String,
+    pub format: String,
+    pub encrypted: bool,
+    /// Base64 PBKDF2 salt, present when `data` is encrypted with a passphrase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// Reminder of the passphrase, readable without it; never the passphrase itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// The passphrase key, unlockable with a recovery code instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoveryBlock>,
+    pub data: Value,
+}
+
+/// A backup's passphrase key, encrypted with the key of a recovery code
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecoveryBlock {
+    /// Base64 PBKDF2 salt for the recovery code
+    pub salt: String,
+    /// The base64 passphrase key
+    pub key: crate::crypto::EncryptedData,
+}
+
+/// Errors from data operations, shown in the panel and exposed to embedders
+#[derive(Debug, Clone)]
+pub enum DataError {
+    MissingPlayerId,
+    Storage(StorageError),
+    Crypto(CryptoError),
+    InvalidFormat(String),
+    Parse(String),
+    Serialization(String),
+    Download(DownloadError),
+    FileRead(String),
+    /// The text or file to import is empty or only whitespace
+    EmptyInput,
+    NotEncrypted,
+    EncryptedBackup,
+    /// The backup is protected by a passphrase and none was given
+    PassphraseRequired,
+    /// The password hint would give the passphrase away
+    HintRevealsPassphrase,
+    /// The entered recovery code isn't the one set up on this device
+    WrongRecoveryCode,
+    /// The player name can't be saved as given
+    InvalidName(ProfileNameError),
+    /// The payload is longer than its destination can hold, e.g. a QR code
+    TooLarge { len: usize, max: usize },
+    /// The feature is turned off by safe mode
+    SafeMode(String),
+    /// The embedding page's `EncryptionPolicy` rules the operation out
+    PolicyViolation(String),
+    /// The backup was exported by another player; carries their player ID
+    OtherPlayer(String),
+    /// The friends list is full; carries the cap
+    FriendLimit(usize),
+    VerificationFailed(String),
+    Timeout(Duration),
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DataError::MissingPlayerId => write!(f, "Missing player ID required for export"),
+            DataError::Storage(err) => write!(f, "Storage error: {:?}", err),
+            DataError::Crypto(CryptoError::MalformedInput(msg)) => write!(f, "The file is corrupt: {}", msg),
+            DataError::Crypto(CryptoError::DecryptionError(msg)) => {
+                write!(f, "The data was tampered with, or needs a different key or passphrase: {}", msg)
+            },
+            // The inner error says whether encrypting or decrypting failed
+            DataError::Crypto(err) => write!(f, "{}", err),
+            DataError::InvalidFormat(msg) => write!(f, "Invalid data format: {}", msg),
+            DataError::Parse(msg) => write!(f, "Failed to parse imported data: {}", msg),
+            DataError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            DataError::Download(err) => write!(f, "{}", err),
+            DataError::FileRead(msg) => write!(f, "{}", msg),
+            DataError::EmptyInput => write!(f, "Nothing to import"),
+            DataError::InvalidName(err) => write!(f, "Invalid name: {}", err),
+            DataError::NotEncrypted => write!(f, "This backup isn't encrypted"),
+            DataError::EncryptedBackup => write!(f, "This backup is encrypted and needs a key to import"),
+            DataError::PassphraseRequired => write!(f, "This backup is protected by a passphrase"),
+            DataError::HintRevealsPassphrase => write!(f, "The password hint can't contain the passphrase"),
+            DataError::WrongRecoveryCode => write!(f, "That isn't the recovery code set up on this device"),
+            DataError::TooLarge { len, max } => write!(f, "Data is too large ({} characters, the limit is {})", len, max),
+            DataError::SafeMode(feature) => write!(f, "Safe mode is on, so {} are unavailable", feature),
+            DataError::PolicyViolation(reason) => write!(f, "Not allowed by the backup policy: {}", reason),
+            DataError::OtherPlayer(player_id) => {
+                write!(f, "This backup belongs to player {}; restore it as a new profile to replace your current player", player_id)
+            },
+            DataError::FriendLimit(max) => write!(f, "{}", crate::friends::FriendError::LimitReached(*max)),
+            DataError::VerificationFailed(msg) => write!(f, "Backup failed verification and was not saved: {}", msg),
+            DataError::Timeout(limit) => write!(f, "Operation timed out after {} seconds", limit.as_secs_f32()),
+        }
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::Storage(err) => Some(err),
+            DataError::Crypto(err) => Some(err),
+            DataError::Download(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DownloadError> for DataError {
+    fn from(err: DownloadError) -> Self {
+        DataError::Download(err)
+    }
+}
+
+impl From<StorageError> for DataError {
+    fn from(err: StorageError) -> Self {
+        DataError::Storage(err)
+    }
+}
+
+impl From<CryptoError> for DataError {
+    fn from(err: CryptoError) -> Self {
+        DataError::Crypto(err)
+    }
+}
+
+/// Busy and error state of data operations, shared through context so
+/// host components can drive their own loading UI.
+#[derive(Copy, Clone)]
+pub struct DataStatus {
+    pub is_busy: Signal<bool>,
+    pub last_error: Signal<Option<DataError>>,
+    /// Fraction of the current operation completed, when it reports progress
+    pub progress: Signal<Option<f32>>,
+    set_busy: WriteSignal<bool>,
+    set_progress: WriteSignal<Option<f32>>,
+    set_last_error: WriteSignal<Option<DataError>>,
+    bus: EventBus,
+}
+
+impl DataStatus {
+    /// Mark an operation as started, clearing the previous error
+    pub fn begin(&self) {
+        self.set_last_error.set(None);
+        self.set_progress.set(None);
+        self.set_busy.set(true);
+    }
+    
+    /// Report how far through the current operation we are, from 0.0 to 1.0
+    pub fn report_progress(&self, fraction: f32) {
+        self.set_progress.set(Some(fraction.clamp(0.0, 1.0)));
+    }
+    
+    /// Mark the current operation as finished, recording its error if any
+    pub fn finish<T>(&self, result: &Result<T, DataError>) {
+        self.set_busy.set(false);
+        self.set_progress.set(None);
+        if let Err(err) = result {
+            self.report_error(err.clone());
+        }
+    }
+    
+    /// Record an error outside of a tracked operation
+    pub fn report_error(&self, err: DataError) {
+        self.bus.emit(AppEvent::ErrorReported(err.to_string()));
+        self.set_last_error.set(Some(err));
+    }
+    
+    pub fn clear_error(&self) {
+        self.set_last_error.set(None);
+    }
+}
+
+// How long async operations may take before they're abandoned
+pub const DEFAULT_OPERATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+thread_local! {
+    static OPERATION_TIMEOUT: Cell<Duration> = const { Cell::new(DEFAULT_OPERATION_TIMEOUT) };
+}
+
+// Change how long async operations may take
+pub fn set_operation_timeout(limit: Duration) {
+    OPERATION_TIMEOUT.with(|current| current.set(limit));
+}
+
+pub fn operation_timeout() -> Duration {
+    OPERATION_TIMEOUT.with(|current| current.get())
+}
+
+/// Import size past which the user has to confirm more firmly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LargeImportThreshold {
+    /// Length of the backup file's text
+    pub max_bytes: usize,
+    /// Friends in the backup; only counted when it opens without a passphrase
+    pub max_friends: usize,
+}
+
+pub const DEFAULT_LARGE_IMPORT_THRESHOLD: LargeImportThreshold = LargeImportThreshold {
+    max_bytes: 256 * 1024,
+    max_friends: 100,
+};
+
+thread_local! {
+    static LARGE_IMPORT_THRESHOLD: Cell<LargeImportThreshold> = const { Cell::new(DEFAULT_LARGE_IMPORT_THRESHOLD) };
+}
+
+// Change what counts as a large import
+pub fn set_large_import_threshold(threshold: LargeImportThreshold) {
+    LARGE_IMPORT_THRESHOLD.with(|current| current.set(threshold));
+}
+
+pub fn large_import_threshold() -> LargeImportThreshold {
+    LARGE_IMPORT_THRESHOLD.with(|current| current.get())
+}
+
+/// What applying a backup would write to storage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImportImpact {
+    /// Keys the import writes
+    pub keys: usize,
+    /// Bytes of the keys and values written
+    pub bytes: usize,
+    /// Storage in use afterwards would be past `STORAGE_QUOTA_ESTIMATE`
+    pub exceeds_quota: bool,
+}
+
+impl ImportImpact {
+    /// e.g. "This import will use ~12 KB"
+    pub fn summary(&self) -> String {
+        let kilobytes = self.bytes.div_ceil(1024);
+        let mut summary = format!("This import will use ~{} KB", kilobytes);
+        if self.exceeds_quota {
+            summary.push_str(", which may not fit in this browser's storage");
+        }
+        summary
+    }
+}
+
+/// Work out what importing `json_data` would write, without writing it.
+/// Values the import replaces are taken off current usage before checking
+/// against the quota estimate.
+pub fn estimate_import_impact(json_data: &str) -> Result<ImportImpact, DataError> {
+    estimate_import_impact_with(json_data, &KeyOptions::default())
+}
+
+/// `estimate_import_impact` for backups that may need a passphrase or custom key
+pub fn estimate_import_impact_with(json_data: &str, key_options: &KeyOptions) -> Result<ImportImpact, DataError> {
+    let plain = unwrap_backup_with(&normalize_import_text(json_data), key_options)?;
+    let backup: PartialExportedData = serde_json::from_str(&plain)
+        .map_err(|err| DataError::Parse(err.to_string()))?;
+    let entries = backup.data.storage_entries();
+    
+    let bytes: usize = entries.iter().map(|(key, value)| stored_size(key, value)).sum();
+    let replaced: usize = entries
+        .iter()
+        .filter_map(|(key, _)| {
+            let current = localStorage::get_storage_item(key).ok().flatten()?;
+            Some(stored_size(key, &current))
+        })
+        .sum();
+    let usage_after = (storage_usage()? + bytes).saturating_sub(replaced);
+    
+    Ok(ImportImpact {
+        keys: entries.len(),
+        bytes,
+        exceeds_quota: usage_after > STORAGE_QUOTA_ESTIMATE,
+    })
+}
+
+/// How firmly importing `json_data` should be confirmed: `Severity::High`
+/// past the large import threshold, otherwise `Severity::Normal`
+pub fn import_severity(json_data: &str) -> Severity {
+    let threshold = large_import_threshold();
+    if json_data.len() > threshold.max_bytes {
+        return Severity::High;
+    }
+    let friend_count = unwrap_backup(&normalize_import_text(json_data))
+        .ok()
+        .and_then(|plain| serde_json::from_str::<PartialExportedData>(&plain).ok())
+        .and_then(|backup| backup.data.extra.get(crate::friends::FRIENDS_KEY).cloned())
+        .and_then(|friends| serde_json::from_str::<Vec<Value>>(&friends).ok())
+        .map_or(0, |friends| friends.len());
+    if friend_count > threshold.max_friends {
+        Severity::High
+    } else {
+        Severity::Normal
+    }
+}
+
+// Resolves with the operation's output, or with a timeout error if the timer fires first
+struct WithTimeout<F> {
+    operation: Pin<Box<F>>,
+    timer: TimeoutFuture,
+    limit: Duration,
+}
+
+impl<F: Future> Future for WithTimeout<F> {
+    type Output = Result<F::Output, DataError>;
+    
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.operation.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match Pin::new(&mut self.timer).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(DataError::Timeout(self.limit))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Run an async operation, giving up with `DataError::Timeout` after `limit`
+pub async fn with_timeout<F: Future>(operation: F, limit: Duration) -> Result<F::Output, DataError> {
+    WithTimeout {
+        operation: Box::pin(operation),
+        timer: TimeoutFuture::new(limit.as_millis().min(u32::MAX as u128) as u32),
+        limit,
+    }.await
+}
+
+/// Provide a `DataStatus` for this part of the tree.
+/// Hosts that want to observe `DataButton` call this above it.
+pub fn provide_data_status() -> DataStatus {
+    let (is_busy, set_busy) = signal(false);
+    let (last_error, set_last_error) = signal(Option::<DataError>::None);
+    let (progress, set_progress) = signal(Option::<f32>::None);
+    
+    let status = DataStatus {
+        is_busy: is_busy.into(),
+        last_error: last_error.into(),
+        progress: progress.into(),
+        set_busy,
+        set_last_error,
+        set_progress,
+        bus: use_event_bus(),
+    };
+    provide_context(status);
+    status
+}
+
+// Helper to get the data status, providing one if no host did
+pub fn use_data_status() -> DataStatus {
+    use_context::<DataStatus>().unwrap_or_else(provide_data_status)
 }
 
-// JavaScript console logging helper
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
+// Languages offered by the locale selector
+pub const SUPPORTED_LOCALES: &[(&str, &str)] = &[
+    ("en-US", "English (US)"),
+    ("en-GB", "English (UK)"),
+    ("es-ES", "Español"),
+    ("fr-FR", "Français"),
+    ("de-DE", "Deutsch"),
+    ("ja-JP", "日本語"),
+];
+
+/// The step of starting a download that failed; details are the
+/// browser's own error text
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadError {
+    /// Not running in a page, e.g. in a worker
+    NoDocument,
+    /// The page has no `<body>` to attach the download link to, as in some sandboxed iframes
+    NoBody,
+    /// The file contents couldn't be turned into a `Blob`
+    Blob(String),
+    /// No object URL for the blob, usually blocked by browser policy
+    ObjectUrl(String),
+    /// The hidden download link couldn't be created, attached, or removed
+    Link(String),
+    /// The download started, but its object URL couldn't be released
+    Revoke(String),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DownloadError::NoDocument => write!(f, "Downloads only work inside a web page"),
+            DownloadError::NoBody => write!(f, "Download can't start until the page has finished loading; please try again"),
+            DownloadError::Blob(detail) => write!(f, "Your browser couldn't prepare the file, it may be out of memory ({})", detail),
+            DownloadError::ObjectUrl(detail) => write!(f, "Your browser blocked the download; check its download settings ({})", detail),
+            DownloadError::Link(detail) => write!(f, "Your browser blocked the download link; try again outside any embedding page ({})", detail),
+            DownloadError::Revoke(detail) => write!(f, "The download started, but its temporary file couldn't be released ({})", detail),
+        }
+    }
 }
 
+impl std::error::Error for DownloadError {}
+
 /// Creates a download for the user with the given content and filename
-pub fn trigger_download(content: &str, filename: &str) -> Result<(), JsValue> {
+pub fn trigger_download(content: &str, filename: &str) -> Result<(), DownloadError> {
+    trigger_download_as(content, filename, "application/json")
+}
+
+/// `trigger_download` for content that isn't JSON, e.g. an SVG image
+pub fn trigger_download_as(content: &str, filename: &str, mime_type: &str) -> Result<(), DownloadError> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or(DownloadError::NoDocument)?;
+    trigger_download_in(&document, content, filename, mime_type)
+}
+
+// Download through a link attached to `document`'s body
+fn trigger_download_in(document: &Document, content: &str, filename: &str, mime_type: &str) -> Result<(), DownloadError> {
+    // Sandboxed iframes can run this before the body exists; check before creating anything
+    let body = document.body().ok_or(DownloadError::NoBody)?;
+    let detail = |err: JsValue| err.as_string().unwrap_or_else(|| format!("{:?}", err));
+    
     // Create a Blob from the content string
     let mut blob_properties = BlobPropertyBag::new();
-    blob_properties.type_("application/json");
+    blob_properties.type_(mime_type);
     
     let blob_parts = js_sys::Array::new();
     blob_parts.push(&JsValue::from_str(content));
@@ -54,557 +602,4103 @@ pub fn trigger_download(content: &str, filename: &str) -> Result<(), JsValue> {
     let blob = Blob::new_with_str_sequence_and_options(
         &blob_parts,
         &blob_properties,
-    )?;
+    ).map_err(|err| DownloadError::Blob(detail(err)))?;
     
     // Create a URL for the blob
-    let url = Url::create_object_url_with_blob(&blob)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(|err| DownloadError::ObjectUrl(detail(err)))?;
+    
+    let clicked = click_download_link(document, &body, &url, filename).map_err(|err| DownloadError::Link(detail(err)));
     
-    // Create and click an anchor element to trigger the download
-    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window found"))?;
-    let document = window.document().ok_or_else(|| JsValue::from_str("No document found"))?;
+    // Release the URL object, even if the click failed
+    let revoked = Url::revoke_object_url(&url).map_err(|err| DownloadError::Revoke(detail(err)));
+    clicked.and(revoked)
+}
+
+// Create and click an anchor element to trigger the download
+fn click_download_link(document: &Document, body: &web_sys::HtmlElement, url: &str, filename: &str) -> Result<(), JsValue> {
     let a = document.create_element("a")?
         .dyn_into::<HtmlAnchorElement>()?;
     
-    a.set_href(&url);
+    a.set_href(url);
     a.set_download(filename);
     a.set_attribute("style", "display: none;")?;
     
-    let body = document.body().ok_or_else(|| JsValue::from_str("No body found"))?;
     body.append_child(&a)?;
     a.click();
     body.remove_child(&a)?;
-    
-    // Release the URL object
-    Url::revoke_object_url(&url)?;
-    
     Ok(())
 }
 
-// Import application data from a JSON string
-// Returns a Result with either a success message or an error
-pub fn import_data(json_data: &str) -> Result<String, String> {
-    // First, try to decrypt the data if it's encrypted
-    let decrypted_data = match crate::crypto::decrypt_data(json_data) {
-        Ok(decrypted) => decrypted,
-        Err(_) => {
-            // If decryption fails, assume it's not encrypted and proceed with original data
-            json_data.to_string()
+// Partial export used when merging several backups; any field may be missing
+#[derive(Deserialize, Clone, Debug)]
+struct PartialExportedData {
+    version: String,
+    data: PartialAppData,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct PartialAppData {
+    player_id: Option<String>,
+    dark_mode: Option<bool>,
+    locale: Option<String>,
+    #[serde(default)]
+    extra: BTreeMap<String, String>,
+}
+
+impl PartialAppData {
+    // The storage writes that importing this data makes
+    fn storage_entries(&self) -> Vec<(&str, &str)> {
+        let mut entries = Vec::new();
+        if let Some(player_id) = &self.player_id {
+            entries.push(("player_id", player_id.as_str()));
         }
-    };
-    
-    // Parse the JSON string
-    let parsed_data: Result<ExportedData, _> = serde_json::from_str(&decrypted_data);
-    
-    match parsed_data {
-        Ok(data) => {
-            // Validate version (in a real implementation, you might check compatibility)
-            if data.version.is_empty() {
-                return Err("Invalid data format: missing version".to_string());
+        if let Some(dark_mode) = self.dark_mode {
+            entries.push(("dark_mode", if dark_mode { "true" } else { "false" }));
+        }
+        if let Some(locale) = &self.locale {
+            entries.push(("locale", locale.as_str()));
+        }
+        for (key, value) in &self.extra {
+            if is_importable_extra_key(key) {
+                entries.push((key.as_str(), value.as_str()));
+            } else {
+                warn!("Skipping unregistered key in backup: {}", key);
             }
-            
-            // Extract the actual app data
-            let app_data = data.data;
-            
-            // Store player_id
-            match localStorage::set_storage_item("player_id", &app_data.player_id) {
-                Ok(_) => {},
-                Err(err) => {
-                    error!("Failed to store player_id during import: {:?}", err);
-                    return Err(format!("Storage error: {:?}", err));
-                }
+        }
+        entries
+    }
+}
+
+/// Outcome of importing a single file as part of a batch restore
+#[derive(Clone, Debug)]
+pub struct FileImportResult {
+    pub file_name: String,
+    pub result: Result<ImportSummary, DataError>,
+}
+
+// File contents collected during a batch load, indexed by selection order
+type LoadedFiles = Rc<RefCell<Vec<Option<(String, Result<String, DataError>)>>>>;
+
+// A FileReader with the handlers it calls back into
+struct FileReadHandlers {
+    reader: web_sys::FileReader,
+    _onload: Closure<dyn FnMut(web_sys::Event)>,
+    _onerror: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl Drop for FileReadHandlers {
+    fn drop(&mut self) {
+        // Detach before the closures are freed so a late event can't call them
+        self.reader.set_onload(None);
+        self.reader.set_onerror(None);
+        self.reader.abort();
+    }
+}
+
+/// Reads of one batch of selected files, applied together once all are in.
+///
+/// The reader handlers live as long as the batch rather than being leaked.
+/// Dropping the batch aborts unfinished reads, so a superseded batch can't
+/// complete and import after a newer one has started.
+pub struct FileReadBatch {
+    loaded: LoadedFiles,
+    _handlers: Vec<FileReadHandlers>,
+}
+
+impl FileReadBatch {
+    /// Start reading `files` as text, calling `on_complete` with every
+    /// file's name and contents in selection order once all reads finish.
+    /// A `None` entry is a file the browser couldn't hand over.
+    pub fn start(
+        files: Vec<Option<web_sys::File>>,
+        on_complete: impl Fn(Vec<(String, Result<String, DataError>)>) + 'static,
+    ) -> Self {
+        let loaded: LoadedFiles = Rc::new(RefCell::new(vec![None; files.len()]));
+        let on_complete: Rc<dyn Fn(Vec<(String, Result<String, DataError>)>)> = Rc::new(on_complete);
+        
+        // Record one finished read and apply the batch once every file is in
+        let record_read = move |loaded: &LoadedFiles, index: usize, entry: (String, Result<String, DataError>)| {
+            let complete = {
+                let mut slots = loaded.borrow_mut();
+                // Reads finishing after the batch was dropped or timed out are ignored
+                let Some(slot) = slots.get_mut(index) else { return };
+                *slot = Some(entry);
+                slots.iter().all(Option::is_some)
+            };
+            if complete {
+                let entries = loaded.borrow_mut().drain(..).flatten().collect();
+                on_complete(entries);
             }
+        };
+        
+        let mut handlers = Vec::new();
+        for (index, file) in files.into_iter().enumerate() {
+            let Some(file) = file else {
+                let error_msg = "Could not access selected file".to_string();
+                error!("{}", &error_msg);
+                record_read(&loaded, index, (format!("file {}", index + 1), Err(DataError::FileRead(error_msg))));
+                continue;
+            };
+            let file_name = file.name();
             
-            // Store dark_mode preference
-            let dark_mode_value = if app_data.dark_mode { "true" } else { "false" };
-            match localStorage::set_storage_item("dark_mode", dark_mode_value) {
-                Ok(_) => {},
-                Err(err) => {
-                    error!("Failed to store dark_mode during import: {:?}", err);
-                    return Err(format!("Storage error: {:?}", err));
+            let reader = web_sys::FileReader::new().expect("Failed to create FileReader");
+            let reader_clone = reader.clone();
+            let loaded_on_load = loaded.clone();
+            let name_on_load = file_name.clone();
+            let record_on_load = record_read.clone();
+            let onload = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                let read_result = match reader_clone.result() {
+                    Ok(result) => result
+                        .as_string()
+                        .ok_or_else(|| DataError::FileRead("Failed to read file as text".to_string())),
+                    Err(_) => Err(DataError::FileRead("Error getting result from FileReader".to_string())),
+                };
+                if let Err(err) = &read_result {
+                    error!("{}: {}", name_on_load, err);
                 }
-            }
+                record_on_load(&loaded_on_load, index, (name_on_load.clone(), read_result));
+            }) as Box<dyn FnMut(_)>);
+            reader.set_onload(Some(onload.as_ref().unchecked_ref()));
             
-            // Log successful import
-            let log_msg = format!("DATA_IMPORT: Successfully imported data with player_id: {}", app_data.player_id);
-            info!("{}", log_msg);
-            log(&log_msg);
+            let loaded_on_error = loaded.clone();
+            let name_on_error = file_name.clone();
+            let record_on_error = record_read.clone();
+            let onerror = Closure::wrap(Box::new(move |_: web_sys::Event| {
+                let error_msg = "Error reading file".to_string();
+                error!("{}: {}", name_on_error, &error_msg);
+                record_on_error(&loaded_on_error, index, (name_on_error.clone(), Err(DataError::FileRead(error_msg))));
+            }) as Box<dyn FnMut(_)>);
+            reader.set_onerror(Some(onerror.as_ref().unchecked_ref()));
             
-            Ok("Data imported successfully".to_string())
-        },
-        Err(err) => {
-            // Handle parsing error
-            let error_msg = format!("Failed to parse imported data: {:?}", err);
-            error!("{}", &error_msg);
-            Err(error_msg)
+            if let Err(err) = reader.read_as_text(&file) {
+                let error_msg = format!("Failed to read file: {:?}", err);
+                error!("{}", &error_msg);
+                record_read(&loaded, index, (file_name, Err(DataError::FileRead(error_msg))));
+            }
+            handlers.push(FileReadHandlers { reader, _onload: onload, _onerror: onerror });
         }
+        
+        FileReadBatch { loaded, _handlers: handlers }
     }
-}
-
-/// Export all application data to a JSON string for backup purposes
-/// Returns a Result with either the JSON string or an error message
-pub fn export_data() -> Result<String, String> {
-    // Get player_id from storage
-    let player_id = match localStorage::get_storage_item("player_id") {
-        Ok(Some(id)) => id,
-        Ok(None) => {
-            // No ID exists in storage - throw an error
-            error!("No player ID found in storage during export");
-            return Err("Missing player ID required for export".to_string());
-        },
-        Err(err) => {
-            // Error accessing storage
-            error!("Failed to access player ID during export: {:?}", err);
-            return Err(format!("Storage error: {:?}", err));
-        }
-    };
-
-    // Get dark mode preference
-    let dark_mode = match localStorage::get_storage_item("dark_mode") {
-        Ok(Some(value)) => value == "true",
-        _ => false // Default to light mode
-    };
-    
-    // Create the export data structure
-    let export_data = ExportedData {
-        version: "0.1.0".to_string(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        data: ExportedAppData {
-            player_id,
-            dark_mode,
-        },
-    };
     
-    // Serialize to JSON
-    match serde_json::to_string(&export_data) {
-        Ok(json_string) => {
-            info!("Data successfully serialized");
-            
-            // Encrypt the data before exporting
-            match crate::crypto::encrypt_data(&json_string) {
-                Ok(encrypted_data) => {
-                    info!("Data successfully encrypted and exported");
-                    Ok(encrypted_data)
-                },
-                Err(err) => {
-                    error!("Failed to encrypt export data: {:?}", err);
-                    Err(format!("Encryption error: {:?}", err))
-                }
+    /// Returns a check that gives up on the batch if reads are still
+    /// pending, reporting whether it did
+    pub fn stall_check(&self) -> impl FnOnce() -> bool + 'static {
+        let loaded = self.loaded.clone();
+        move || {
+            let mut slots = loaded.borrow_mut();
+            let stalled = slots.iter().any(Option::is_none);
+            if stalled {
+                slots.clear();
             }
-        },
-        Err(err) => {
-            error!("Failed to serialize export data: {:?}", err);
-            Err(format!("Serialization error: {:?}", err))
+            stalled
         }
     }
 }
 
-#[component]
-pub fn DataButton() -> impl IntoView {
-    // Create a signal to track whether we're showing the button or panel
-    let (show_panel, set_show_panel) = create_signal(false);
-    let (storage_error, set_storage_error) = create_signal(Option::<String>::None);
-    let (export_success, set_export_success) = create_signal(Option::<String>::None);
-    let (load_success, set_load_success) = create_signal(Option::<String>::None);
+impl Drop for FileReadBatch {
+    fn drop(&mut self) {
+        self.loaded.borrow_mut().clear();
+    }
+}
 
-    // Get the player ID when the component initializes
-    let id = get_player_id();
-    
-    // Log the player ID to the console for debugging
-    if !id.is_empty() {
-        let log_msg = format!("PLAYER_ID_DATA: {}", id);
-        log(&log_msg);
-        info!("{}", log_msg);
-    } else {
-        let err_msg = "Failed to get or generate player ID".to_string();
-        error!("{}", err_msg);
-        set_storage_error.set(Some(err_msg));
+// The hidden file input of a load in progress, with its event handlers
+struct FilePicker {
+    input: web_sys::Element,
+    _onchange: Closure<dyn FnMut(web_sys::Event)>,
+    _oncancel: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl Drop for FilePicker {
+    fn drop(&mut self) {
+        self.input.remove();
     }
-    
-    let theme = use_theme();
-    let dark_mode = theme.dark_mode;
-    let player_id = create_rw_signal(id);
-    let dark_mode_preference = create_rw_signal(dark_mode);
-    let dark_mode_signal = create_memo(move |_| theme.dark_mode);
-    create_effect(move |_| {
-        // Update our local reactive signal to match the global state
-        let current_theme_value = dark_mode_signal.get();
-        if dark_mode_preference.get() != current_theme_value {
-            dark_mode_preference.set(current_theme_value);
-        }
-    });
+}
 
-    // Click handler for the button to show the panel
-    let show_panel_click = move |_| {
-        set_show_panel.set(true);
-        
-        // Log the player ID again when the panel is shown
-        let current_id = player_id.get();
-        if !current_id.is_empty() {
-            let log_msg = format!("PLAYER_ID_PANEL_OPENED: {}", current_id);
-            log(&log_msg);
-            info!("{}", log_msg);
-        }
+// Decrypt the data if it's encrypted, otherwise return it unchanged. Input
+// that is an encrypted payload must open, so a corrupt or tampered backup
+// is reported as such rather than parsed as plaintext.
+fn decrypt_if_encrypted(json_data: &str, key_options: &KeyOptions) -> Result<String, DataError> {
+    let Ok(payload) = serde_json::from_str::<crate::crypto::EncryptedData>(json_data) else {
+        return Ok(json_data.to_string());
     };
+    open_payload(&payload, key_options.key.as_deref(), key_options)
+}
 
-    // Click handler for the close button to hide the panel
-    let hide_panel_click = move |_| {
-        set_show_panel.set(false);
-        
-        // Clear any success/error messages when panel is closed
-        set_export_success.set(None);
-        set_load_success.set(None);
-        set_storage_error.set(None);
-    };
+// Which action the passphrase prompt is for
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PassphraseRequest {
+    Export,
+    /// Files are read and waiting; shows the first backup's hint, if any
+    Import { hint: Option<String> },
+}
 
-    let toggle_dark_mode = move |_| {
-        theme.toggle_theme.dispatch(());
-        
-        // Log the dark mode change
-        let new_preference = !dark_mode.get(); // Predict new value
-        let log_msg = format!("DARK_MODE_CHANGED: {}", new_preference);
-        log(&log_msg);
-        info!("{}", log_msg);
-    };
+/// Key used to open an encrypted backup
+#[derive(Clone, Debug, Default)]
+pub struct KeyOptions {
+    /// Raw AES-256 key; `None` uses the app's built-in key
+    pub key: Option<Zeroizing<[u8; 32]>>,
+    /// Passphrase for backups exported with one; never stored
+    pub passphrase: Option<Zeroizing<String>>,
+    /// Key derived earlier, e.g. kept by an unlocked session; opens
+    /// passphrase backups with the same salt without deriving again
+    pub passphrase_key: Option<PassphraseKey>,
+    /// Accept an encrypted backup bound to a different player than the
+    /// current one, for restoring onto a fresh profile. Without this,
+    /// such backups fail with `DataError::OtherPlayer` so one player's
+    /// export can't overwrite another player's data.
+    pub restore_other_player: bool,
+}
 
-    // Export button click handler
-    let export_button_click = move |_| {
-        // Clear any previous messages
-        set_export_success.set(None);
-        set_load_success.set(None);
-        set_storage_error.set(None);
+impl KeyOptions {
+    pub fn with_passphrase(passphrase: &str) -> Self {
+        KeyOptions { passphrase: Some(Zeroizing::new(passphrase.to_string())), ..Default::default() }
+    }
+    
+    pub fn with_passphrase_key(passphrase_key: PassphraseKey) -> Self {
+        KeyOptions { passphrase_key: Some(passphrase_key), ..Default::default() }
+    }
+    
+    // Key for an envelope, where a recovery code works in place of the
+    // passphrase if the backup has a recovery block. Text that only looks
+    // like a code falls back to being a passphrase.
+    fn key_for_envelope(&self, envelope: &BackupEnvelope) -> Result<Option<Zeroizing<[u8; 32]>>, DataError> {
+        let code = self.passphrase.as_deref().map(String::as_str).and_then(parse_recovery_code);
+        match envelope.recovery.as_ref().zip(code) {
+            Some((block, code)) => match open_recovery_block(block, &code) {
+                Some(key) => Ok(Some(key)),
+                None => self.key_for(envelope.salt.as_deref()),
+            },
+            None => self.key_for(envelope.salt.as_deref()),
+        }
+    }
+    
+    // Key for an envelope: the passphrase key when the backup has a salt,
+    // otherwise the raw key (or the app key)
+    fn key_for(&self, salt: Option<&str>) -> Result<Option<Zeroizing<[u8; 32]>>, DataError> {
+        let Some(salt) = salt else { return Ok(self.key.clone()) };
+        let salt = BASE64.decode(salt)
+            .map_err(|err| DataError::InvalidFormat(format!("passphrase salt: {}", err)))?;
+        if let Some(passphrase_key) = self.passphrase_key.as_ref().filter(|key| key.salt == salt) {
+            return Ok(Some(passphrase_key.key.clone()));
+        }
+        let passphrase = self.passphrase.as_deref().ok_or(DataError::PassphraseRequired)?;
+        Ok(Some(crate::crypto::derive_passphrase_key(passphrase, &salt)))
+    }
+}
+
+// Storage key for the device's recovery check: the code's salt and a
+// one-way check value. Neither opens a backup without the code itself.
+pub const RECOVERY_KEY_KEY: &str = "recovery_key";
+
+// What's stored under `RECOVERY_KEY_KEY`, both base64
+#[derive(Serialize, Deserialize)]
+struct RecoveryCheck {
+    salt: String,
+    verifier: String,
+}
+
+/// Key unlocked by entering the device's recovery code, for adding a
+/// recovery block to a passphrase backup; wiped when dropped
+#[derive(Clone, Debug)]
+pub struct RecoveryKey {
+    salt: String,
+    key: Zeroizing<[u8; 32]>,
+}
+
+// The stored recovery check. Older versions stored the recovery key
+// itself here, which is removed rather than kept readable.
+fn load_recovery_check() -> Result<Option<RecoveryCheck>, DataError> {
+    let Some(check_json) = crate::utils::get_storage_item(RECOVERY_KEY_KEY)? else { return Ok(None) };
+    match serde_json::from_str(&check_json) {
+        Ok(check) => Ok(Some(check)),
+        Err(err) => {
+            warn!("Removing unreadable recovery check: {}", err);
+            crate::utils::remove_storage_item(RECOVERY_KEY_KEY)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Whether a recovery code has been set up on this device
+pub fn has_recovery_code() -> bool {
+    matches!(load_recovery_check(), Ok(Some(_)))
+}
+
+/// Set up a recovery code, replacing any earlier one. Only a check value is
+/// stored, so this is the only time the code can be shown, and it has to be
+/// entered again for each backup it should open.
+pub fn create_recovery_code() -> Result<String, DataError> {
+    let code = random_bytes::<RECOVERY_CODE_LEN>()?;
+    let salt = new_passphrase_salt()?;
+    let check = RecoveryCheck {
+        salt: BASE64.encode(salt),
+        verifier: BASE64.encode(recovery_key_verifier(&derive_recovery_key(&code, &salt))),
+    };
+    let check_json = serde_json::to_string(&check)
+        .map_err(|err| DataError::Serialization(err.to_string()))?;
+    crate::utils::set_storage_item(RECOVERY_KEY_KEY, &check_json)?;
+    Ok(format_recovery_code(&code))
+}
+
+/// The key behind `code`, once it's confirmed to be this device's recovery code
+pub fn unlock_recovery_code(code: &str) -> Result<RecoveryKey, DataError> {
+    let code = parse_recovery_code(code).ok_or(DataError::WrongRecoveryCode)?;
+    let check = load_recovery_check()?.ok_or(DataError::WrongRecoveryCode)?;
+    let salt = BASE64.decode(&check.salt)
+        .map_err(|err| DataError::InvalidFormat(format!("recovery salt: {}", err)))?;
+    let verifier = BASE64.decode(&check.verifier)
+        .map_err(|err| DataError::InvalidFormat(format!("recovery check: {}", err)))?;
+    let key = derive_recovery_key(&code, &salt);
+    if !constant_time_eq(&recovery_key_verifier(&key), &verifier) {
+        return Err(DataError::WrongRecoveryCode);
+    }
+    Ok(RecoveryKey { salt: check.salt, key })
+}
+
+// `passphrase_key` wrapped for the recovery code behind `recovery`
+fn recovery_block(passphrase_key: &[u8; 32], recovery: &RecoveryKey) -> Result<RecoveryBlock, DataError> {
+    let encoded_key = Zeroizing::new(BASE64.encode(passphrase_key));
+    let key = crate::crypto::encrypt_payload_with_key(&encoded_key, Some(&recovery.key))?;
+    Ok(RecoveryBlock { salt: recovery.salt.clone(), key })
+}
+
+// The passphrase key in `block`, or `None` if `code` doesn't open it
+fn open_recovery_block(block: &RecoveryBlock, code: &[u8; RECOVERY_CODE_LEN]) -> Option<Zeroizing<[u8; 32]>> {
+    let salt = BASE64.decode(&block.salt).ok()?;
+    let recovery_key = derive_recovery_key(code, &salt);
+    let encoded = Zeroizing::new(crate::crypto::decrypt_payload_with_key(&block.key, Some(&recovery_key)).ok()?);
+    let decoded = Zeroizing::new(BASE64.decode(encoded.as_bytes()).ok()?);
+    <[u8; 32]>::try_from(decoded.as_slice()).ok().map(Zeroizing::new)
+}
+
+// Storage key for the opt-in passphrase setting
+pub const PASSPHRASE_REQUIRED_KEY: &str = "passphrase_required";
+
+// Whether export and import ask for a passphrase; off by default and in safe mode
+pub fn passphrase_required() -> bool {
+    !is_safe_mode() && matches!(localStorage::get_storage_item(PASSPHRASE_REQUIRED_KEY), Ok(Some(value)) if value == "true")
+}
+
+pub fn set_passphrase_required(required: bool) -> Result<(), StorageError> {
+    crate::utils::set_storage_item(PASSPHRASE_REQUIRED_KEY, if required { "true" } else { "false" })
+}
+
+/// Whether a hint contains the passphrase, ignoring case and surrounding space
+pub fn hint_reveals_passphrase(passphrase: &str, hint: &str) -> bool {
+    let passphrase = passphrase.trim().to_lowercase();
+    !passphrase.is_empty() && hint.to_lowercase().contains(&passphrase)
+}
+
+/// The salt a passphrase backup's key is derived with, read without decrypting it
+pub fn backup_salt(json_data: &str) -> Option<Vec<u8>> {
+    serde_json::from_str::<BackupEnvelope>(&normalize_import_text(json_data))
+        .ok()
+        .filter(|envelope| envelope.format == SPLIT_FORMAT)
+        .and_then(|envelope| envelope.salt)
+        .and_then(|salt| BASE64.decode(salt).ok())
+}
+
+/// The password hint of a backup, read without decrypting it
+pub fn backup_hint(json_data: &str) -> Option<String> {
+    serde_json::from_str::<BackupEnvelope>(&normalize_import_text(json_data))
+        .ok()
+        .filter(|envelope| envelope.format == SPLIT_FORMAT)
+        .and_then(|envelope| envelope.hint)
+}
+
+// Whether a backup's data needs decrypting, in either the split or legacy format
+fn is_encrypted_backup(json_data: &str) -> bool {
+    match serde_json::from_str::<BackupEnvelope>(json_data) {
+        Ok(envelope) if envelope.format == SPLIT_FORMAT => envelope.encrypted,
+        _ => serde_json::from_str::<crate::crypto::EncryptedData>(json_data).is_ok(),
+    }
+}
+
+/// What kind of input an import was handed, decided from its structure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupFormat {
+    /// Unencrypted backup, split or legacy
+    Plain,
+    /// Encrypted backup: a split envelope with encrypted data, or a legacy
+    /// file with top-level `ciphertext`/`iv`
+    Encrypted,
+    /// Not text at all, e.g. an image or archive picked by mistake
+    Binary,
+    /// Text that isn't a backup we recognize
+    Unknown,
+}
+
+// Leading bytes of common non-backup files (zip, PNG, PDF, gzip)
+const BINARY_MAGIC: &[&str] = &["PK\u{3}\u{4}", "\u{89}PNG", "%PDF", "\u{1f}\u{8b}"];
+
+impl BackupFormat {
+    pub fn detect(input: &str) -> Self {
+        let input = input.trim_start_matches('\u{feff}');
+        if BINARY_MAGIC.iter().any(|magic| input.starts_with(magic)) {
+            return BackupFormat::Binary;
+        }
         
-        // Get the data to export
-        match export_data() {
-            Ok(export_json) => {
-                // Generate a filename with timestamp for uniqueness
-                let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-                let filename = format!("game_data_export_{}.json", timestamp);
-                
-                // Trigger the download
-                match trigger_download(&export_json, &filename) {
-                    Ok(_) => {
-                        // Set success message
-                        set_export_success.set(Some("Data exported successfully".to_string()));
-                        
-                        // Log export action
-                        let log_msg = format!("DATA_EXPORT: Export initiated: {}", filename);
-                        info!("{}", log_msg);
-                        log(&log_msg);
-                    },
-                    Err(err) => {
-                        // Handle download error
-                        let error_msg = format!("Failed to download data: {:?}", err);
-                        error!("{}", &error_msg);
-                        set_storage_error.set(Some(error_msg));
-                    }
-                }
+        let fields = match serde_json::from_str::<Value>(input) {
+            Ok(Value::Object(fields)) => fields,
+            // Files read as text turn invalid UTF-8 into replacement characters.
+            // Only checked once parsing fails, since JSON strings may hold any of them.
+            Err(_) if input.chars().any(|c| c == '\u{fffd}' || (c.is_control() && !matches!(c, '\n' | '\r' | '\t'))) => {
+                return BackupFormat::Binary;
             },
-            Err(err) => {
-                // Handle export error
-                set_storage_error.set(Some(err));
+            _ => return BackupFormat::Unknown,
+        };
+        if is_encrypted_backup(input) {
+            BackupFormat::Encrypted
+        } else if fields.contains_key("version") && fields.contains_key("data") {
+            BackupFormat::Plain
+        } else {
+            BackupFormat::Unknown
+        }
+    }
+}
+
+impl TryFrom<&str> for BackupFormat {
+    type Error = DataError;
+    
+    /// Detect the format, rejecting input that isn't a backup at all
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        match BackupFormat::detect(input) {
+            BackupFormat::Binary => Err(DataError::InvalidFormat("this is a binary file, not a backup".to_string())),
+            BackupFormat::Unknown => Err(DataError::InvalidFormat("not a recognized backup".to_string())),
+            format => Ok(format),
+        }
+    }
+}
+
+// Decrypt a backup payload. One bound to a player must be bound to the
+// current player, unless the options allow restoring another player or
+// there's no current player to protect.
+fn open_payload(payload: &crate::crypto::EncryptedData, key: Option<&[u8; 32]>, key_options: &KeyOptions) -> Result<String, DataError> {
+    let current = localStorage::get_storage_item("player_id").ok().flatten().unwrap_or_default();
+    let expected = match payload.aad.as_deref() {
+        // Backups from before player binding
+        None => None,
+        Some(bound) if key_options.restore_other_player || current.is_empty() => Some(bound),
+        Some(bound) if bound != current => return Err(DataError::OtherPlayer(bound.to_string())),
+        Some(_) => Some(current.as_str()),
+    };
+    crate::crypto::decrypt_payload_bound(payload, key, expected).map_err(DataError::from)
+}
+
+// Rebuild plaintext `ExportedData` JSON from a split envelope
+fn open_envelope(envelope: BackupEnvelope, key_options: &KeyOptions) -> Result<String, DataError> {
+    if envelope.version.is_empty() {
+        return Err(DataError::InvalidFormat("missing version".to_string()));
+    }
+    
+    let app_data = if envelope.encrypted {
+        let key = key_options.key_for_envelope(&envelope)?;
+        let payload: crate::crypto::EncryptedData = serde_json::from_value(envelope.data)
+            .map_err(|err| DataError::InvalidFormat(format!("encrypted data section: {}", err)))?;
+        let plaintext = open_payload(&payload, key.as_deref(), key_options)?;
+        let app_data = serde_json::from_str::<Value>(&plaintext)
+            .map_err(|err| DataError::Parse(err.to_string()))?;
+        
+        // Data bound to a player must be that player's
+        if let Some(bound) = payload.aad.as_deref() {
+            if app_data.get("player_id").and_then(Value::as_str) != Some(bound) {
+                return Err(DataError::Crypto(CryptoError::AadMismatch(
+                    "the backup's data belongs to a different player than it was encrypted for".to_string(),
+                )));
             }
         }
+        app_data
+    } else {
+        envelope.data
     };
     
+    Ok(json!({
+        "version": envelope.version,
+        "timestamp": envelope.timestamp,
+        "data": app_data,
+    }).to_string())
+}
 
-// Load button click handler
-let load_button_click = move |_| {
-    // Clear any previous messages
-    set_export_success.set(None);
-    set_load_success.set(None);
-    set_storage_error.set(None);
-    
-    // Create a file input element
-    let window = web_sys::window().expect("No window found");
-    let document = window.document().expect("No document found");
-    
-    // Create a file input element
-    let file_input = document
-        .create_element("input")
-        .expect("Failed to create input element");
-    
-    // Set attributes for the file input
-    file_input
-        .set_attribute("type", "file")
-        .expect("Failed to set input type");
-    file_input
-        .set_attribute("accept", ".json")
-        .expect("Failed to set accept attribute");
-    file_input
-        .set_attribute("style", "display: none;")
-        .expect("Failed to set style attribute");
-    
-    // Add the input to the document body
-    let body = document.body().expect("No body found");
-    body.append_child(&file_input)
-        .expect("Failed to append file input");
-    
-    // Create a reference to file_input that will be shared by the closure
-    let file_input_ref = file_input.clone();
-    
-    // Use FnMut instead of FnOnce
-    let onchange_callback = Closure::wrap(Box::new(move |_event: web_sys::Event| {
-        // Create a separate clone here to avoid moving file_input_ref
-        let input_elem = file_input_ref.clone();
-        let file_input = input_elem
-            .dyn_into::<web_sys::HtmlInputElement>()
-            .expect("Failed to cast to HtmlInputElement");
-        
-        // Get the selected file - files is a property, not a method
-        let files = file_input.files();
-        if let Some(files) = files {
-            if files.length() > 0 {
-                if let Some(file_js) = files.get(0) {
-                    let file = file_js.dyn_into::<web_sys::File>().expect("Failed to cast to File");
-
-                    // Create a FileReader to read the file
-                    let reader = web_sys::FileReader::new().expect("Failed to create FileReader");
-                    let reader_clone = reader.clone();
-                    
-                    // Set up onload handler for the FileReader
-                    let onload_closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
-                        // Get the file content as text
-                        if let Ok(result) = reader_clone.result() {
-                            if let Some(text) = result.as_string() {
-                                match import_data(&text) {
-                                    Ok(success_msg) => {
-                                        // Update the UI with success message
-                                        set_load_success.set(Some(success_msg));
-                                        
-                                        // Log successful import
-                                        let log_msg = "DATA_IMPORT: File import successful";
-                                        info!("{}", log_msg);
-                                        log(log_msg);
-                                        
-                                        // Refresh the player ID display
-                                        if let Ok(Some(id)) = localStorage::get_storage_item("player_id") {
-                                            player_id.set(id);
-                                        }
-                                        
-                                        // Refresh dark mode preference display
-                                        if let Ok(Some(mode)) = localStorage::get_storage_item("dark_mode") {
-                                            let is_dark = mode == "true";
-                                            // Only toggle if different from current state to avoid double toggle
-                                            if dark_mode.get() != is_dark {
-                                                theme.toggle_theme.dispatch(());
-                                            }
-                                        }
-                                    },
-                                    Err(err) => {
-                                        // Clone or copy the error string before using it
-                                        let error_string = err.clone(); // If err is a String or has Clone implemented
-                                        
-                                        // Update the UI with error message
-                                        set_storage_error.set(Some(error_string));
-                                        
-                                        // Log import error using the original err
-                                        let error_msg = format!("DATA_IMPORT_ERROR: {}", err);
-                                        error!("{}", &error_msg);
-                                        log(&error_msg);
-                                    }
-                                }
-                                } else {
-                                // Handle case where result is not a string
-                                let error_msg = "Failed to read file as text".to_string();
-                                error!("{}", &error_msg);
-                                set_storage_error.set(Some(error_msg));
-                            }
-                        } else {
-                            // Handle case where result() returns an error
-                            let error_msg = "Error getting result from FileReader".to_string();
-                            error!("{}", &error_msg);
-                            set_storage_error.set(Some(error_msg));
-                        }
-                    }) as Box<dyn FnMut(_)>);
-                    
-                    // Set the onload handler
-                    reader.set_onload(Some(onload_closure.as_ref().unchecked_ref()));
-                    onload_closure.forget(); // Prevent closure from being dropped
-                    
-                    // Set up error handler for the FileReader
-                    let reader_error_clone = reader.clone();
-                    let onerror_closure = Closure::wrap(Box::new(move |_: web_sys::Event| {
-                        let error_msg = "Error reading file".to_string();
-                        error!("{}", &error_msg);
-                        set_storage_error.set(Some(error_msg));
-                    }) as Box<dyn FnMut(_)>);
-                    
-                    // Set the onerror handler
-                    reader.set_onerror(Some(onerror_closure.as_ref().unchecked_ref()));
-                    onerror_closure.forget(); // Prevent closure from being dropped
-                    
-                    // Start reading the file as text
-                    if let Err(err) = reader.read_as_text(&file) {
-                        let error_msg = format!("Failed to read file: {:?}", err);
-                        error!("{}", &error_msg);
-                        set_storage_error.set(Some(error_msg));
-                    }
-                } else {
-                    // File is None
-                    let error_msg = "Could not access selected file".to_string();
-                    error!("{}", &error_msg);
-                    set_storage_error.set(Some(error_msg));
-                }
-            } else {
-                // No file selected
-                let error_msg = "No file selected".to_string();
-                error!("{}", &error_msg);
-                set_storage_error.set(Some(error_msg));
-            }
-        } else {
-            // No files property
-            let error_msg = "Failed to access file input files".to_string();
-            error!("{}", &error_msg);
-            set_storage_error.set(Some(error_msg));
-        }
-        
-        // Use another clone of file_input_ref to avoid moving it
-        let document_clone = window.document().expect("No document found");
-        if let Some(body) = document_clone.body() {
-            let input_to_remove = file_input_ref.clone();
-            let _ = body.remove_child(&input_to_remove);
-        }
-    }) as Box<dyn FnMut(_)>);
-    
-    // Set the onchange handler
-    file_input
-        .add_event_listener_with_callback("change", onchange_callback.as_ref().unchecked_ref())
-        .expect("Failed to add event listener");
-    onchange_callback.forget(); // Prevent closure from being dropped
-    
-    // Trigger click on the file input to open file dialog
-    let file_input_html = file_input
-        .dyn_into::<web_sys::HtmlElement>()
-        .expect("Failed to cast to HtmlElement");
-    file_input_html.click();
-    
-    // Log load action
-    let log_msg = "DATA_LOAD: File picker dialog opened";
-    info!("{}", log_msg);
-    log(log_msg);
-};
+/// Turn any supported backup into plaintext `ExportedData` JSON.
+/// Split envelopes are checked for a version before their data is decrypted;
+/// older backups are either fully encrypted or plain JSON.
+pub fn unwrap_backup(json_data: &str) -> Result<String, DataError> {
+    unwrap_backup_with(json_data, &KeyOptions::default())
+}
 
-    view! {
-        <div class="mt-6">
-            {move || {
-                if show_panel.get() {
-                    // Panel view
-                    view! {
-                        <div class={use_data_panel_class}
-                            data-test-id="data-panel">
-                            <div class="flex justify-between items-center mb-4">
-                                <h2 
-                                    data-test-id="data-header"
-                                    class={use_data_header_class}
-                                >
-                                    "Locally Stored Data"
-                                </h2>
-                                <button
-                                    data-test-id="data-close-button"
-                                    class={use_data_close_button_class}
-                                    on:click={hide_panel_click}
-                                >
-                                    "×"
-                                </button>
-                            </div>
-                            <div 
-                                data-test-id="data-content"
-                                class={use_data_content_class}
-                            >
-                                <p>"Your locally stored data:"</p>
-                                {move || {
-                                    if let Some(error) = storage_error.get() {
-                                        view! {
-                                            <p 
-                                                data-test-id="storage-error"
-                                                class={use_error_message_class}
-                                            >
-                                                {"Error: "}{error}
-                                            </p>
-                                        }.into_any()
-                                    } else {
-                                        view! {
-                                            <div>
-                                                <p 
-                                                    data-test-id="player-id"
-                                                    class={use_player_id_class}
-                                                >
-                                                    {"Player ID: "}{player_id.get()}
-                                                </p>
-                                                <p>
-                                                    <span>{"Dark Mode: "}{if dark_mode.get() { "Enabled" } else { "Disabled" }}</span>
-                                                    <button
-                                                        data-test-id="dark-mode-toggle"
-                                                        class={use_dark_mode_toggle_button_class}
-                                                        on:click={toggle_dark_mode}
-                                                    >
-                                                        {if dark_mode.get() { "Disable" } else { "Enable" }}
-                                                    </button>
-                                                </p>
-                                                
-                                                <div class="mt-4 flex space-x-2">
-                                                    <button
-                                                        data-test-id="export-data-button"
-                                                        class={use_button_class}
-                                                        on:click={export_button_click}
-                                                    >
-                                                        "Export Data"
-                                                    </button>
-                                                    
-                                                    <button
-                                                        data-test-id="load-data-button"
-                                                        class={use_button_class}
-                                                        on:click={load_button_click}
-                                                    >
-                                                        "Load Data"
-                                                    </button>
-                                                </div>
-                                                
-                                                <div class="mt-2">
-                                                    {move || {
-                                                        if let Some(success) = export_success.get() {
-                                                            view! {
-                                                                <p 
-                                                                    data-test-id="export-success-message"
-                                                                    class="text-green-600 dark:text-green-400"
-                                                                >
-                                                                    {success}
-                                                                </p>
-                                                            }.into_any()
-                                                        } else if let Some(success) = load_success.get() {
-                                                            view! {
-                                                                <p 
-                                                                    data-test-id="load-success-message"
-                                                                    class="text-green-600 dark:text-green-400"
-                                                                >
-                                                                    {success}
-                                                                </p>
-                                                            }.into_any()
-                                                        } else {
-                                                            view! {}.into_any()
-                                                        }
-                                                    }}
-                                                </div>
-                                            </div>
-                                        }.into_any()
-                                    }
-                                }}
-                            </div>
-                        </div>
-                    }.into_any()
-                } else {
-                    // Button view
-                    view! {
-                        <button
-                            data-test-id="data-button"
-                            class={use_button_class}
-                            on:click={show_panel_click}
-                        >
-                            "Locally Stored Data"
-                        </button>
-                    }.into_any()
-                }
-            }}
-        </div>
+/// `unwrap_backup` for backups that may need a passphrase or custom key
+pub fn unwrap_backup_with(json_data: &str, key_options: &KeyOptions) -> Result<String, DataError> {
+    let envelope = match serde_json::from_str::<BackupEnvelope>(json_data) {
+        Ok(envelope) if envelope.format == SPLIT_FORMAT => envelope,
+        _ => return decrypt_if_encrypted(json_data, key_options),
+    };
+    
+    open_envelope(envelope, key_options)
+}
+
+// Strip a UTF-8 byte order mark and normalize line endings, which some
+// editors add when a backup is opened and saved
+fn normalize_import_text(text: &str) -> String {
+    text.trim_start_matches('\u{feff}').replace("\r\n", "\n")
+}
+
+// Catch blank pastes and empty files before they surface as parse errors
+fn ensure_not_empty(text: &str) -> Result<(), DataError> {
+    if text.trim().is_empty() {
+        return Err(DataError::EmptyInput);
+    }
+    Ok(())
+}
+
+// Import application data from a JSON string, detecting whether it's encrypted
+// Returns a Result with either a success message or an error
+pub fn import_data(json_data: &str) -> Result<ImportSummary, DataError> {
+    import_data_with_progress(json_data, None)
+}
+
+/// Like `import_data`, reporting progress from 0.0 to 1.0 as the backup
+/// is parsed, decrypted, validated and committed to storage.
+pub fn import_data_with_progress(json_data: &str, on_progress: Option<Callback<f32>>) -> Result<ImportSummary, DataError> {
+    import_data_inner(json_data, ImportMode::Overwrite, on_progress)
+}
+
+/// Like `import_data`, choosing what happens to values already in storage
+pub fn import_data_with_mode(json_data: &str, mode: ImportMode) -> Result<ImportSummary, DataError> {
+    import_data_inner(json_data, mode, None)
+}
+
+fn import_data_inner(json_data: &str, mode: ImportMode, on_progress: Option<Callback<f32>>) -> Result<ImportSummary, DataError> {
+    let report = |fraction: f32| {
+        if let Some(on_progress) = on_progress {
+            on_progress.run(fraction);
+        }
+    };
+    
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    let format = BackupFormat::detect(json_data);
+    report(0.25);
+    
+    let decrypted_data = match format {
+        BackupFormat::Encrypted => decrypt_backup(json_data, &KeyOptions::default())?,
+        BackupFormat::Binary => return Err(DataError::InvalidFormat("this is a binary file, not a backup".to_string())),
+        // Unrecognized text goes through the plain path so parse errors explain what's wrong
+        BackupFormat::Plain | BackupFormat::Unknown => unwrap_backup(json_data)?,
+    };
+    report(0.5);
+    
+    let data = parse_exported_data(&decrypted_data)?;
+    report(0.75);
+    
+    let summary = commit_exported_data(&data.data, mode)?;
+    report(1.0);
+    Ok(summary)
+}
+
+/// Import a backup that must be encrypted.
+/// Plaintext backups are rejected with `DataError::NotEncrypted`.
+pub fn import_encrypted(json_data: &str, key_options: &KeyOptions) -> Result<ImportSummary, DataError> {
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    if BackupFormat::detect(json_data) != BackupFormat::Encrypted {
+        return Err(DataError::NotEncrypted);
+    }
+    
+    let decrypted_data = decrypt_backup(json_data, key_options)?;
+    commit_exported_data(&parse_exported_data(&decrypted_data)?.data, ImportMode::Overwrite)
+}
+
+// Decrypt an encrypted backup into plaintext `ExportedData` JSON
+fn decrypt_backup(json_data: &str, key_options: &KeyOptions) -> Result<String, DataError> {
+    let decrypted = match serde_json::from_str::<BackupEnvelope>(json_data) {
+        Ok(envelope) if envelope.format == SPLIT_FORMAT => open_envelope(envelope, key_options),
+        _ => {
+            // Legacy backups encrypt the whole file
+            let payload: crate::crypto::EncryptedData = serde_json::from_str(json_data)
+                .map_err(|err| DataError::InvalidFormat(err.to_string()))?;
+            open_payload(&payload, key_options.key.as_deref(), key_options)
+        }
+    };
+    decrypted.map_err(|err| {
+        error!("{}", err);
+        err
+    })
+}
+
+/// Import a backup that must not be encrypted.
+/// Encrypted backups are rejected with `DataError::EncryptedBackup`.
+pub fn import_plain(json_data: &str) -> Result<ImportSummary, DataError> {
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    if BackupFormat::detect(json_data) == BackupFormat::Encrypted {
+        return Err(DataError::EncryptedBackup);
+    }
+    commit_exported_data(&parse_exported_data(&unwrap_backup(json_data)?)?.data, ImportMode::Overwrite)
+}
+
+// Parse and validate plaintext `ExportedData` JSON
+fn parse_exported_data(decrypted_data: &str) -> Result<ExportedData, DataError> {
+    ensure_not_empty(decrypted_data)?;
+    
+    // Parse the JSON string
+    let value: Value = serde_json::from_str(decrypted_data).map_err(|err| {
+        // Handle parsing error
+        let parse_error = DataError::Parse(format!("{:?}", err));
+        error!("{}", parse_error);
+        parse_error
+    })?;
+    
+    // Bring older backups up to the current shape
+    let from_version = value.get("version").and_then(Value::as_str).unwrap_or_default().to_string();
+    let data = migrate(value, &from_version).map_err(|err| {
+        let parse_error = DataError::Parse(err);
+        error!("{}", parse_error);
+        parse_error
+    })?;
+    
+    if data.version.is_empty() {
+        return Err(DataError::InvalidFormat("missing version".to_string()));
+    }
+    
+    Ok(data)
+}
+
+// "major.minor.patch" as numbers, for ordering schema versions
+fn schema_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// Upgrade backup JSON written by schema `from_version` to the current
+/// `ExportedData`. Versions that aren't older than `EXPORT_VERSION`, or
+/// don't parse as one, are read as they are.
+pub fn migrate(mut data: Value, from_version: &str) -> Result<ExportedData, String> {
+    let older_than = |version: &str| match (schema_version(from_version), schema_version(version)) {
+        (Some(from), Some(version)) => from < version,
+        _ => false,
+    };
+    
+    // 0.0.x backups predate the theme setting
+    if older_than("0.1.0") {
+        if let Some(app_data) = data.get_mut("data").and_then(Value::as_object_mut) {
+            app_data.entry("dark_mode").or_insert(Value::Bool(false));
+        }
+        info!("Migrated backup from schema {} to {}", from_version, EXPORT_VERSION);
+        data["version"] = Value::String(EXPORT_VERSION.to_string());
+    }
+    
+    serde_json::from_value(data).map_err(|err| format!("{:?}", err))
+}
+
+// Message for an import that wouldn't change anything
+pub const NO_OP_MESSAGE: &str = "This backup matches your current data";
+
+// Message for a merge that kept every stored value
+pub const KEPT_LOCAL_MESSAGE: &str = "Your current data was kept; the backup had nothing missing from it";
+
+/// What an import does with values that are already stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Every value in the backup replaces the stored one
+    #[default]
+    Overwrite,
+    /// Stored values are kept; the backup only fills in missing ones
+    MergeKeepLocal,
+    /// The stored player ID is kept; the backup's other values win
+    MergePreferImported,
+}
+
+impl ImportMode {
+    pub const ALL: [ImportMode; 3] = [ImportMode::Overwrite, ImportMode::MergeKeepLocal, ImportMode::MergePreferImported];
+
+    // Value used for the mode in the import mode select
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportMode::Overwrite => "overwrite",
+            ImportMode::MergeKeepLocal => "merge-keep-local",
+            ImportMode::MergePreferImported => "merge-prefer-imported",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportMode::Overwrite => "Replace my data",
+            ImportMode::MergeKeepLocal => "Keep my data, add what's missing",
+            ImportMode::MergePreferImported => "Keep my player ID, take the rest",
+        }
+    }
+
+    // Whether importing leaves the stored value of `key` alone
+    fn keeps_local(&self, key: &str) -> bool {
+        let stored = || localStorage::get_storage_item(key).ok().flatten().map_or(false, |value| !value.is_empty());
+        match self {
+            ImportMode::Overwrite => false,
+            ImportMode::MergeKeepLocal => stored(),
+            ImportMode::MergePreferImported => key == "player_id" && stored(),
+        }
+    }
+
+    // The state importing `incoming` over `current` leaves behind
+    fn apply(&self, current: &AppState, incoming: &AppState) -> AppState {
+        AppState {
+            player_id: if self.keeps_local("player_id") { current.player_id.clone() } else { incoming.player_id.clone() },
+            dark_mode: if self.keeps_local("dark_mode") { current.dark_mode } else { incoming.dark_mode },
+            locale: if self.keeps_local("locale") { current.locale.clone() } else { incoming.locale.clone() },
+            extra: incoming
+                .extra
+                .iter()
+                .filter(|(key, _)| !self.keeps_local(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for ImportMode {
+    type Err = ImportModeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ImportMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == s)
+            .ok_or_else(|| ImportModeParseError(s.to_string()))
+    }
+}
+
+/// An import mode value that doesn't match any `ImportMode`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportModeParseError(pub String);
+
+impl std::fmt::Display for ImportModeParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown import mode: {}", self.0)
+    }
+}
+
+impl std::error::Error for ImportModeParseError {}
+
+/// Outcome of a full import
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportSummary {
+    pub message: String,
+    /// The backup matched the current data, so nothing was written
+    pub no_op: bool,
+    pub changes: Vec<FieldChange>,
+}
+
+// Write imported app data into storage, keeping stored values `mode` says to
+fn commit_exported_data(app_data: &ExportedAppData, mode: ImportMode) -> Result<ImportSummary, DataError> {
+    let app_data = &ExportedAppData { extra: importable_extra(&app_data.extra), ..app_data.clone() };
+    
+    // Importing the data we already have is usually a mistake worth pointing out
+    let current = current_app_state();
+    if current == *app_data {
+        info!("DATA_IMPORT: Backup matches current data, nothing to import");
+        return Ok(ImportSummary {
+            message: NO_OP_MESSAGE.to_string(),
+            no_op: true,
+            changes: Vec::new(),
+        });
+    }
+    
+    let app_data = &mode.apply(&current, app_data);
+    let changes = diff_state(&current, app_data);
+    let extra_unchanged = app_data.extra.iter().all(|(key, value)| current.extra.get(key) == Some(value));
+    if mode != ImportMode::Overwrite && changes.is_empty() && extra_unchanged {
+        info!("DATA_IMPORT: Merge kept the current data");
+        return Ok(ImportSummary {
+            message: KEPT_LOCAL_MESSAGE.to_string(),
+            no_op: true,
+            changes,
+        });
+    }
+    
+    // Store player_id and dark_mode preference
+    let mut entries = vec![
+        ("player_id", app_data.player_id.as_str()),
+        ("dark_mode", if app_data.dark_mode { "true" } else { "false" }),
+    ];
+    
+    // Store locale preference, if the backup has one
+    if let Some(locale) = &app_data.locale {
+        entries.push(("locale", locale.as_str()));
+    }
+    
+    // Store values of other registered keys
+    for (key, value) in &app_data.extra {
+        entries.push((key.as_str(), value.as_str()));
+    }
+    
+    // All or nothing, so a failed import can't leave a mix of old and new data
+    batch_write_verified(&entries).map_err(|err| {
+        error!("Failed to store imported data: {:?}", err);
+        DataError::Storage(err)
+    })?;
+    
+    // Log successful import
+    log_event(LogCategory::DataImport, Level::Info, &format!("Successfully imported data with player_id: {}", app_data.player_id));
+    
+    Ok(ImportSummary {
+        message: "Data imported successfully".to_string(),
+        no_op: false,
+        changes,
+    })
+}
+
+/// Merge a (possibly partial) backup into storage.
+/// Only the fields present in the backup are written, so applying several
+/// backups in turn keeps fields from earlier ones that later ones omit.
+pub fn merge_import_data(json_data: &str) -> Result<ImportSummary, DataError> {
+    merge_import_data_with(json_data, &KeyOptions::default())
+}
+
+/// `merge_import_data` for backups that may need a passphrase or custom key
+pub fn merge_import_data_with(json_data: &str, key_options: &KeyOptions) -> Result<ImportSummary, DataError> {
+    merge_import_data_with_mode(json_data, key_options, ImportMode::Overwrite)
+}
+
+/// `merge_import_data_with`, choosing what happens to values already in storage
+pub fn merge_import_data_with_mode(json_data: &str, key_options: &KeyOptions, mode: ImportMode) -> Result<ImportSummary, DataError> {
+    let decrypted_data = open_merge_backup(json_data, key_options)?;
+    let data = parse_merge_backup(&decrypted_data)?;
+    commit_merge_backup(&data, mode)
+}
+
+/// `merge_import_data_with_mode`, reporting progress from 0.0 to 1.0 as the
+/// backup is decrypted, validated and committed. Yields to the browser after
+/// each stage so a progress bar can paint between them.
+pub async fn merge_import_data_with_progress(
+    json_data: &str,
+    key_options: &KeyOptions,
+    mode: ImportMode,
+    on_progress: Callback<f32>,
+) -> Result<ImportSummary, DataError> {
+    let decrypted_data = open_merge_backup(json_data, key_options)?;
+    on_progress.run(1.0 / 3.0);
+    TimeoutFuture::new(0).await;
+    
+    let data = parse_merge_backup(&decrypted_data)?;
+    on_progress.run(2.0 / 3.0);
+    TimeoutFuture::new(0).await;
+    
+    let summary = commit_merge_backup(&data, mode)?;
+    on_progress.run(1.0);
+    Ok(summary)
+}
+
+// Decrypt stage of a merge import
+fn open_merge_backup(json_data: &str, key_options: &KeyOptions) -> Result<String, DataError> {
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    unwrap_backup_with(json_data, key_options)
+}
+
+// Parse and validate stage of a merge import
+fn parse_merge_backup(decrypted_data: &str) -> Result<PartialExportedData, DataError> {
+    let data: PartialExportedData = serde_json::from_str(decrypted_data).map_err(|err| {
+        let parse_error = DataError::Parse(format!("{:?}", err));
+        error!("{}", parse_error);
+        parse_error
+    })?;
+    
+    if data.version.is_empty() {
+        return Err(DataError::InvalidFormat("missing version".to_string()));
+    }
+    Ok(data)
+}
+
+// Commit stage of a merge import
+fn commit_merge_backup(data: &PartialExportedData, mode: ImportMode) -> Result<ImportSummary, DataError> {
+    // Every field present already matches what's stored
+    let current = current_app_state();
+    let unchanged = data.data.player_id.as_ref().map_or(true, |id| *id == current.player_id)
+        && data.data.dark_mode.map_or(true, |dark| dark == current.dark_mode)
+        && data.data.locale.as_ref().map_or(true, |locale| current.locale.as_ref() == Some(locale))
+        && data.data.extra.iter().all(|(key, value)| current.extra.get(key) == Some(value));
+    let all_entries = data.data.storage_entries();
+    if unchanged && !all_entries.is_empty() {
+        return Ok(ImportSummary {
+            message: NO_OP_MESSAGE.to_string(),
+            no_op: true,
+            changes: Vec::new(),
+        });
+    }
+    if all_entries.is_empty() {
+        return Err(DataError::InvalidFormat("backup contains no data to import".to_string()));
+    }
+    
+    let entries: Vec<(&str, &str)> = all_entries.into_iter().filter(|(key, _)| !mode.keeps_local(key)).collect();
+    if entries.is_empty() {
+        return Ok(ImportSummary {
+            message: KEPT_LOCAL_MESSAGE.to_string(),
+            no_op: true,
+            changes: Vec::new(),
+        });
+    }
+    
+    let mut imported_fields = Vec::new();
+    for (key, _) in &entries {
+        let field = match *key {
+            "player_id" => "player ID",
+            "dark_mode" => "dark mode",
+            "locale" => "language",
+            _ => "other saved data",
+        };
+        if !imported_fields.contains(&field) {
+            imported_fields.push(field);
+        }
     }
-}
\ No newline at end of file
+    
+    batch_write_verified(&entries).map_err(|err| {
+        error!("Failed to store merged data: {:?}", err);
+        DataError::Storage(err)
+    })?;
+    
+    log_event(LogCategory::DataImport, Level::Info, &format!("Merged fields: {}", imported_fields.join(", ")));
+    
+    Ok(ImportSummary {
+        message: format!("Imported {}", imported_fields.join(" and ")),
+        no_op: false,
+        changes: diff_state(&current, &current_app_state()),
+    })
+}
+
+/// Import several backups in order, merging each over the previous ones
+/// under `mode`. Failures are reported per file and don't stop the
+/// remaining files.
+pub fn import_batch(files: &[(String, String)], key_options: &KeyOptions, mode: ImportMode) -> Vec<FileImportResult> {
+    files
+        .iter()
+        .map(|(file_name, contents)| FileImportResult {
+            file_name: file_name.clone(),
+            result: merge_import_data_with_mode(contents, key_options, mode),
+        })
+        .collect()
+}
+
+/// Summarise a batch restore, e.g. "Imported 2 of 3 files"
+pub fn batch_summary(results: &[FileImportResult]) -> String {
+    if let [single] = results {
+        return match &single.result {
+            Ok(summary) if summary.no_op => summary.message.clone(),
+            Ok(_) => "Data imported successfully".to_string(),
+            Err(err) => err.to_string(),
+        };
+    }
+    let succeeded = results.iter().filter(|r| r.result.is_ok()).count();
+    format!("Imported {} of {} files", succeeded, results.len())
+}
+
+/// Every profile on this device in one backup, one entry per profile
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProfileBundle {
+    pub version: String,
+    pub timestamp: String,
+    pub profiles: Vec<AppState>,
+}
+
+/// Outcome of restoring one profile from a bundle
+#[derive(Clone, Debug)]
+pub struct ProfileImportResult {
+    pub player_id: String,
+    pub result: Result<ImportSummary, DataError>,
+}
+
+/// Export a bundle of every profile on this device. The device keeps one
+/// profile, the current player's, so the bundle holds that one.
+pub fn export_all_profiles() -> Result<String, DataError> {
+    let current = current_app_state();
+    if current.player_id.is_empty() {
+        return Err(DataError::MissingPlayerId);
+    }
+    let bundle = ProfileBundle {
+        version: EXPORT_VERSION.to_string(),
+        timestamp: clock::now().to_rfc3339(),
+        profiles: vec![current],
+    };
+    serde_json::to_string(&bundle).map_err(|err| DataError::Serialization(err.to_string()))
+}
+
+/// Restore the profiles in a bundle, with a result for each. The profile
+/// for the stored player, or the first one when no player is stored, is
+/// imported under `mode`; the device has no room for the others, so they
+/// fail with `DataError::OtherPlayer` and leave the stored data alone.
+pub fn import_all_profiles(json_data: &str, mode: ImportMode) -> Result<Vec<ProfileImportResult>, DataError> {
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    let bundle: ProfileBundle = serde_json::from_str(json_data)
+        .map_err(|err| DataError::Parse(format!("profile bundle: {}", err)))?;
+    if bundle.version.is_empty() {
+        return Err(DataError::InvalidFormat("missing version".to_string()));
+    }
+
+    let stored = current_app_state().player_id;
+    let restored = bundle
+        .profiles
+        .iter()
+        .position(|profile| profile.player_id == stored)
+        .or_else(|| stored.is_empty().then_some(0));
+    Ok(bundle
+        .profiles
+        .iter()
+        .enumerate()
+        .map(|(index, profile)| ProfileImportResult {
+            player_id: profile.player_id.clone(),
+            result: if Some(index) == restored {
+                commit_exported_data(profile, mode)
+            } else {
+                Err(DataError::OtherPlayer(profile.player_id.clone()))
+            },
+        })
+        .collect())
+}
+
+/// Whether exports are encrypted, set by the embedding page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncryptionPolicy {
+    /// Always encrypted; no plaintext fallback and no toggle
+    Always,
+    /// Always plaintext; no toggle
+    Never,
+    /// Encrypted unless the user turns it off
+    #[default]
+    UserChoice,
+}
+
+/// A finished export, with a warning when it couldn't be encrypted
+#[derive(Clone, Debug)]
+pub struct ExportedBackup {
+    pub json: String,
+    pub encrypted: bool,
+    pub warning: Option<String>,
+}
+
+/// File name for a backup, e.g. `fc_v0.1.0_enc_20250101_120000.enc.json`.
+///
+/// `scope` names a partial backup and is left out for a full one. Encrypted
+/// backups end in `.enc.json`, plain ones in `.json`. The timestamp is local
+/// time from the app clock.
+pub fn backup_filename(scope: Option<&str>, encrypted: bool, version: &str) -> String {
+    let scope = scope.map(|scope| format!("{}_", scope)).unwrap_or_default();
+    let (encryption, extension) = if encrypted { ("enc", "enc.json") } else { ("plain", "json") };
+    let timestamp = clock::local_now().format("%Y%m%d_%H%M%S");
+    format!("fc_{}v{}_{}_{}.{}", scope, version, encryption, timestamp, extension)
+}
+
+// Values longer than this are summarized in the flat export
+const MAX_FLAT_VALUE_LEN: usize = 120;
+
+// Keep just enough of the player ID to tell two players apart
+fn mask_player_id(player_id: &str) -> String {
+    let prefix: String = player_id.chars().take(4).collect();
+    format!("{}…", prefix)
+}
+
+/// Stored data as `key=value` lines, for support and diagnostics only;
+/// it can't be imported. The player ID is masked, long values (like the
+/// autosave snapshot) are replaced by their length and missing keys are
+/// listed as `key=<unset>`.
+pub fn export_flat() -> String {
+    list_storage_keys()
+        .into_iter()
+        .map(|key| {
+            let value = match localStorage::get_storage_item(&key).ok().flatten() {
+                None => "<unset>".to_string(),
+                Some(value) if key == "player_id" => mask_player_id(&value),
+                Some(value) if value.chars().count() > MAX_FLAT_VALUE_LEN => {
+                    format!("<{} characters>", value.chars().count())
+                },
+                Some(value) => value.replace('\n', "\\n"),
+            };
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Export all application data to a JSON string for backup purposes
+/// Returns a Result with either the JSON string or an error
+pub fn export_data() -> Result<String, DataError> {
+    export_data_with(false)
+}
+
+// Stand-ins for identifying values in anonymized exports; the ID is a
+// valid UUID so the export still imports
+pub const ANONYMOUS_PLAYER_ID: &str = "00000000-0000-4000-8000-000000000000";
+pub const ANONYMOUS_NAME: &str = "Anonymous";
+
+/// `export_data`, optionally anonymized for attaching to a bug report: the
+/// player ID and name are replaced with placeholders and everything else is
+/// kept as is. Anonymized exports are plaintext so they can be read in the
+/// report, and they import like any other backup.
+pub fn export_data_with(anonymize: bool) -> Result<String, DataError> {
+    let policy = if anonymize { EncryptionPolicy::Never } else { EncryptionPolicy::UserChoice };
+    export_backup_inner(None, None, policy, anonymize).map(|backup| backup.json)
+}
+
+// Swap identifying values for placeholders, leaving the structure alone
+fn anonymize_app_data(app_data: &mut ExportedAppData) {
+    app_data.player_id = ANONYMOUS_PLAYER_ID.to_string();
+    if let Some(name) = app_data.extra.get_mut(crate::profile::PROFILE_NAME_KEY) {
+        *name = ANONYMOUS_NAME.to_string();
+    }
+}
+
+/// Export all application data, falling back to an unencrypted backup
+/// when the browser can't provide secure random numbers.
+pub fn export_backup() -> Result<ExportedBackup, DataError> {
+    export_backup_with(None, None)
+}
+
+/// Export all application data, encrypted with a key derived from
+/// `passphrase` when one is given. Passphrase backups never fall back to
+/// plaintext, and may carry a `hint` stored in the clear; a hint without a
+/// passphrase is ignored.
+pub fn export_backup_with(passphrase: Option<&str>, hint: Option<&str>) -> Result<ExportedBackup, DataError> {
+    export_backup_with_policy(passphrase, hint, EncryptionPolicy::UserChoice)
+}
+
+/// `export_backup_with` under an encryption policy: `Never` exports
+/// plaintext and can't take a passphrase, `Always` fails rather than fall
+/// back to plaintext, and `UserChoice` encrypts with the usual fallback.
+pub fn export_backup_with_policy(passphrase: Option<&str>, hint: Option<&str>, policy: EncryptionPolicy) -> Result<ExportedBackup, DataError> {
+    export_backup_inner(passphrase, hint, policy, false)
+}
+
+fn export_backup_inner(passphrase: Option<&str>, hint: Option<&str>, policy: EncryptionPolicy, anonymize: bool) -> Result<ExportedBackup, DataError> {
+    let hint = passphrase
+        .and(hint)
+        .map(str::trim)
+        .filter(|hint| !hint.is_empty());
+    if let (Some(passphrase), Some(hint)) = (passphrase, hint) {
+        if hint_reveals_passphrase(passphrase, hint) {
+            return Err(DataError::HintRevealsPassphrase);
+        }
+    }
+    check_export_allowed(passphrase.is_some(), policy)?;
+    
+    // A passphrase backup gets its own salt, kept next to the data
+    let passphrase_key = match passphrase {
+        Some(passphrase) => Some(PassphraseKey::derive(passphrase, &new_passphrase_salt()?)),
+        None => None,
+    };
+    export_backup_keyed(passphrase_key.as_ref(), None, hint, policy, anonymize)
+}
+
+/// `export_backup_with_policy` with a passphrase key derived earlier, e.g.
+/// the one an unlocked session keeps, so the passphrase isn't needed again.
+/// The backup uses the key's salt. The hint isn't checked against the
+/// passphrase, which the key doesn't reveal. With `recovery`, from
+/// `unlock_recovery_code`, the backup also opens with the recovery code.
+pub fn export_backup_with_key(passphrase_key: &PassphraseKey, recovery: Option<&RecoveryKey>, hint: Option<&str>, policy: EncryptionPolicy) -> Result<ExportedBackup, DataError> {
+    let hint = hint.map(str::trim).filter(|hint| !hint.is_empty());
+    check_export_allowed(true, policy)?;
+    export_backup_keyed(Some(passphrase_key), recovery, hint, policy, false)
+}
+
+// Whether safe mode and the policy allow this export
+fn check_export_allowed(with_passphrase: bool, policy: EncryptionPolicy) -> Result<(), DataError> {
+    if with_passphrase && is_safe_mode() {
+        return Err(DataError::SafeMode("passphrase-protected backups".to_string()));
+    }
+    if policy == EncryptionPolicy::Always && is_safe_mode() {
+        return Err(DataError::SafeMode("encrypted backups".to_string()));
+    }
+    if with_passphrase && policy == EncryptionPolicy::Never {
+        return Err(DataError::PolicyViolation("passphrase backups are encrypted".to_string()));
+    }
+    Ok(())
+}
+
+fn export_backup_keyed(passphrase_key: Option<&PassphraseKey>, recovery: Option<&RecoveryKey>, hint: Option<&str>, policy: EncryptionPolicy, anonymize: bool) -> Result<ExportedBackup, DataError> {
+    // Get player_id from storage
+    let player_id = match get_storage_item_required("player_id") {
+        Ok(id) => id,
+        Err(StorageError::NotFound(_)) => {
+            // No ID exists in storage - throw an error
+            error!("No player ID found in storage during export");
+            return Err(DataError::MissingPlayerId);
+        },
+        Err(err) => {
+            // Error accessing storage
+            error!("Failed to access player ID during export: {:?}", err);
+            return Err(DataError::Storage(err));
+        }
+    };
+
+    // Get dark mode preference
+    let dark_mode = match localStorage::get_storage_item("dark_mode") {
+        Ok(Some(value)) => value == "true",
+        _ => false // Default to light mode
+    };
+    
+    // Only export a locale the user has actually chosen
+    let locale = localStorage::get_storage_item("locale").ok().flatten();
+    
+    let mut app_data = ExportedAppData {
+        player_id,
+        dark_mode,
+        locale,
+        extra: collect_extra_backup_values(),
+    };
+    if anonymize {
+        anonymize_app_data(&mut app_data);
+    }
+    
+    // Serialize the app data; only this part gets encrypted
+    let app_json = match serde_json::to_string(&app_data) {
+        Ok(json_string) => json_string,
+        Err(err) => {
+            error!("Failed to serialize export data: {:?}", err);
+            return Err(DataError::Serialization(err.to_string()));
+        }
+    };
+    info!("Data successfully serialized");
+    
+    // A passphrase backup keeps its salt next to the data
+    let (salt, key_options) = match passphrase_key {
+        Some(passphrase_key) => (
+            Some(BASE64.encode(&passphrase_key.salt)),
+            KeyOptions::with_passphrase_key(passphrase_key.clone()),
+        ),
+        None => (None, KeyOptions::default()),
+    };
+    let key = key_options.key_for(salt.as_deref())?;
+    
+    // Encrypt the data before exporting, unless the policy or safe mode rules it out
+    let encrypt = policy != EncryptionPolicy::Never && !is_safe_mode();
+    // Bound to the player ID, so the data can't be passed off as another player's
+    let encrypted = encrypt.then(|| crate::crypto::encrypt_payload_with(
+        &app_json,
+        key.as_deref(),
+        crate::crypto::CipherAlgorithm::default(),
+        Some(&app_data.player_id),
+    ));
+    let is_encrypted = matches!(encrypted, Some(Ok(_)));
+    let (data, warning) = match encrypted {
+        None => (
+            serde_json::to_value(&app_data)
+                .map_err(|err| DataError::Serialization(err.to_string()))?,
+            is_safe_mode().then(|| "Safe mode is on, so this backup is not encrypted".to_string()),
+        ),
+        Some(Ok(encrypted_data)) => (
+            serde_json::to_value(&encrypted_data)
+                .map_err(|err| DataError::Serialization(err.to_string()))?,
+            None,
+        ),
+        Some(Err(CryptoError::RngUnavailable(reason))) if policy != EncryptionPolicy::Always => {
+            // Locked-down browsers may lack a secure RNG; export in the clear rather than fail
+            warn!("Exporting without encryption: {}", reason);
+            (
+                serde_json::to_value(&app_data)
+                    .map_err(|err| DataError::Serialization(err.to_string()))?,
+                Some("Encryption is unavailable in this browser, so this backup is not encrypted".to_string()),
+            )
+        },
+        Some(Err(err)) => {
+            error!("Failed to encrypt export data: {:?}", err);
+            return Err(DataError::Crypto(err));
+        }
+    };
+    
+    // Passphrase backups can also be opened with the recovery code, if entered
+    let recovery = match (passphrase_key, recovery, key) {
+        (Some(_), Some(recovery), Some(key)) if is_encrypted => Some(recovery_block(&key, recovery)?),
+        _ => None,
+    };
+    
+    // Wrap it in an envelope whose metadata stays readable without the key
+    let envelope = BackupEnvelope {
+        version: EXPORT_VERSION.to_string(),
+        timestamp: clock::now().to_rfc3339(),
+        format: SPLIT_FORMAT.to_string(),
+        encrypted: is_encrypted,
+        salt,
+        hint: hint.map(str::to_string),
+        recovery,
+        data,
+    };
+    
+    let json_string = match serde_json::to_string(&envelope) {
+        Ok(json_string) => json_string,
+        Err(err) => {
+            error!("Failed to serialize export envelope: {:?}", err);
+            return Err(DataError::Serialization(err.to_string()));
+        }
+    };
+    
+    #[cfg(test)]
+    let json_string = EXPORT_TAMPER_HOOK.with(|hook| hook.get()).map_or(json_string.clone(), |tamper| tamper(&json_string));
+    
+    // Read the backup back before anyone downloads it
+    verify_backup(&json_string, &app_data, &key_options)?;
+    
+    info!("Data successfully exported");
+    Ok(ExportedBackup { json: json_string, encrypted: envelope.encrypted, warning })
+}
+
+#[cfg(test)]
+thread_local! {
+    static EXPORT_TAMPER_HOOK: std::cell::Cell<Option<fn(&str) -> String>> = std::cell::Cell::new(None);
+}
+
+// Test hook: rewrite the exported backup between producing and verifying it
+#[cfg(test)]
+pub fn set_export_tamper_hook(hook: Option<fn(&str) -> String>) {
+    EXPORT_TAMPER_HOOK.with(|current| current.set(hook));
+}
+
+// Check a freshly produced backup unwraps to the data it was made from
+fn verify_backup(json_data: &str, expected: &ExportedAppData, key_options: &KeyOptions) -> Result<(), DataError> {
+    let verification_error = |reason: String| {
+        error!("Export verification failed: {}", reason);
+        DataError::VerificationFailed(reason)
+    };
+    
+    let plaintext = unwrap_backup_with(json_data, key_options).map_err(|err| verification_error(err.to_string()))?;
+    let restored: ExportedData = serde_json::from_str(&plaintext)
+        .map_err(|err| verification_error(err.to_string()))?;
+    
+    if &restored.data != expected {
+        return Err(verification_error("restored data does not match".to_string()));
+    }
+    Ok(())
+}
+
+/// Snapshot of the state a backup can change
+pub type AppState = ExportedAppData;
+
+/// How a single field differs between the current and incoming state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One changed field, with display values for both sides
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub label: &'static str,
+    pub kind: ChangeKind,
+    pub current: Option<String>,
+    pub incoming: Option<String>,
+}
+
+impl FieldChange {
+    fn new(field: &'static str, label: &'static str, current: Option<String>, incoming: Option<String>) -> Option<Self> {
+        let kind = match (&current, &incoming) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(a), Some(b)) if a != b => ChangeKind::Modified,
+            _ => return None,
+        };
+        Some(FieldChange { field, label, kind, current, incoming })
+    }
+    
+    /// Human readable summary, e.g. "Theme: light → dark"
+    pub fn describe(&self) -> String {
+        match (&self.current, &self.incoming) {
+            (Some(current), Some(incoming)) => format!("{}: {} → {}", self.label, current, incoming),
+            (None, Some(incoming)) => format!("{}: {} (new)", self.label, incoming),
+            (Some(current), None) => format!("{}: {} (removed)", self.label, current),
+            (None, None) => format!("{}: unchanged", self.label),
+        }
+    }
+}
+
+// Read the current app state from storage without generating anything
+pub fn current_app_state() -> AppState {
+    AppState {
+        player_id: localStorage::get_storage_item("player_id").ok().flatten().unwrap_or_default(),
+        dark_mode: localStorage::get_storage_item("dark_mode").ok().flatten().map_or(false, |v| v == "true"),
+        locale: localStorage::get_storage_item("locale").ok().flatten(),
+        extra: collect_extra_backup_values(),
+    }
+}
+
+/// List the fields that differ between the current and incoming state
+pub fn diff_state(current: &AppState, incoming: &AppState) -> Vec<FieldChange> {
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    
+    [
+        FieldChange::new("player_id", "Player ID", non_empty(&current.player_id), non_empty(&incoming.player_id)),
+        FieldChange::new("theme", "Theme", Some(current.theme().to_string()), Some(incoming.theme().to_string())),
+        FieldChange::new("locale", "Language", current.locale.clone(), incoming.locale.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Parse and validate a backup without writing anything, returning the
+/// data importing it would bring in
+pub fn preview_import(json_data: &str) -> Result<ExportedAppData, DataError> {
+    preview_import_with(json_data, &KeyOptions::default())
+}
+
+/// `preview_import` for backups that need a custom key or another player's restore
+pub fn preview_import_with(json_data: &str, key_options: &KeyOptions) -> Result<ExportedAppData, DataError> {
+    let json_data = &normalize_import_text(json_data);
+    ensure_not_empty(json_data)?;
+    let decrypted = unwrap_backup_with(json_data, key_options)?;
+    Ok(parse_exported_data(&decrypted)?.data)
+}
+
+/// A backup waiting for the user to confirm its import
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportPreview {
+    pub text: String,
+    pub current: AppState,
+    pub incoming: AppState,
+    /// What importing under the chosen mode would change
+    pub changes: Vec<FieldChange>,
+}
+
+impl ImportPreview {
+    pub fn new(text: String, mode: ImportMode, key_options: &KeyOptions) -> Result<Self, DataError> {
+        let incoming = preview_import_with(&text, key_options)?;
+        let current = current_app_state();
+        let changes = diff_state(&current, &mode.apply(&current, &incoming));
+        Ok(ImportPreview { text, current, incoming, changes })
+    }
+}
+
+/// Renders the changes an import would make
+#[component]
+pub fn ImportChanges(
+    /// Changes produced by `diff_state`
+    changes: Vec<FieldChange>,
+) -> impl IntoView {
+    if changes.is_empty() {
+        return view! {
+            <p data-test-id="import-changes-none" class="text-sm italic">
+                "No changes"
+            </p>
+        }.into_any();
+    }
+    
+    view! {
+        <ul data-test-id="import-changes" class="text-sm space-y-1">
+            {changes.into_iter().map(|change| {
+                let class = match change.kind {
+                    ChangeKind::Added => "text-green-600 dark:text-green-400",
+                    ChangeKind::Removed => "text-red-600 dark:text-red-400 line-through",
+                    ChangeKind::Modified => "text-amber-600 dark:text-amber-400",
+                };
+                view! {
+                    <li data-test-id={format!("import-change-{}", change.field)} class={class}>
+                        {change.describe()}
+                    </li>
+                }
+            }).collect_view()}
+        </ul>
+    }.into_any()
+}
+
+/// Bring the theme and accent in line with the stored preferences after an import.
+/// Goes through the guarded programmatic path rather than dispatching
+/// `toggle_theme`, so repeated refreshes can't toggle the theme back and forth.
+pub fn sync_theme_from_storage(theme: ThemeState) {
+    theme.apply_theme(load_theme());
+    let accent = load_accent();
+    if theme.accent.get_untracked() != accent {
+        theme.accent.set(accent);
+    }
+}
+
+#[component]
+pub fn DataButton(
+    /// Start with the panel open, e.g. when deep-linked from the URL
+    #[prop(optional)] initially_open: bool,
+    /// Render the data controls directly instead of behind a button
+    #[prop(optional)] inline: bool,
+    /// Whether exports are encrypted; only `UserChoice` shows the toggle
+    #[prop(optional)] encryption: EncryptionPolicy,
+) -> impl IntoView {
+    // Only one panel is open at a time, so visibility lives in the shared nav
+    let nav = use_panel_nav();
+    if initially_open {
+        nav.open(PanelId::Data);
+    }
+    let status = use_data_status();
+    let service = use_data_service();
+    let unsaved_changes = use_unsaved_changes();
+    let announcer = use_announcer();
+    let bus = use_event_bus();
+    use_idle_auto_lock();
+    let (export_success, set_export_success) = create_signal(Option::<String>::None);
+    let (export_warning, set_export_warning) = signal(Option::<String>::None);
+    let (load_success, set_load_success) = create_signal(Option::<String>::None);
+    let (batch_results, set_batch_results) = signal(Vec::<FileImportResult>::new());
+
+    // Get the player ID when the component initializes
+    let id = get_player_id();
+    
+    // Log the player ID to the console for debugging
+    if !id.is_empty() {
+        log_event(LogCategory::PlayerId, Level::Info, &format!("Loaded {}", id));
+    } else {
+        let err_msg = "Failed to get or generate player ID".to_string();
+        error!("{}", err_msg);
+        status.report_error(DataError::Storage(StorageError::GetError(err_msg)));
+    }
+    
+    let theme = use_theme();
+    let dark_mode = theme.dark_mode;
+    let player_id = create_rw_signal(id);
+    let locale = RwSignal::new(get_locale());
+    let dark_mode_preference = RwSignal::new(dark_mode.get_untracked());
+    let dark_mode_signal = use_dark_mode();
+    create_effect(move |_| {
+        // Update our local reactive signal to match the global state
+        let current_theme_value = dark_mode_signal.get();
+        // Programmatic theme changes reconcile once the lock is released
+        if theme.transition_lock.get() {
+            return;
+        }
+        if dark_mode_preference.get() != current_theme_value {
+            dark_mode_preference.set(current_theme_value);
+        }
+    });
+
+    // Autosave is opt-in; snapshot whenever the displayed state changes
+    let autosave_on = RwSignal::new(autosave_enabled());
+    let had_autosave = has_autosave();
+    Effect::new(move |_| {
+        player_id.track();
+        dark_mode_signal.track();
+        locale.track();
+        if autosave_on.get_untracked() {
+            schedule_autosave();
+        }
+    });
+    
+    let toggle_autosave = move |_| {
+        let enabled = !autosave_on.get_untracked();
+        match set_autosave_enabled(enabled) {
+            Ok(()) => {
+                autosave_on.set(enabled);
+                schedule_autosave();
+            },
+            Err(err) => {
+                error!("Failed to save autosave setting: {:?}", err);
+                status.report_error(DataError::Storage(err));
+            }
+        }
+    };
+
+    // Click handler for the button to show the panel
+    let show_panel_click = move |_| {
+        nav.open(PanelId::Data);
+        
+        // Log the player ID again when the panel is shown
+        let current_id = player_id.get();
+        if !current_id.is_empty() {
+            log_event(LogCategory::PlayerId, Level::Info, &format!("Panel opened with {}", current_id));
+        }
+    };
+
+    // Clear any success/error messages when the panel closes, including
+    // when another panel replaces it
+    Effect::new(move |was_open: Option<bool>| {
+        let is_open = nav.is_open(PanelId::Data);
+        if was_open == Some(true) && !is_open {
+            set_export_success.set(None);
+            set_export_warning.set(None);
+            set_load_success.set(None);
+            status.clear_error();
+            set_batch_results.set(Vec::new());
+        }
+        is_open
+    });
+
+    // Close callback for the panel
+    let hide_panel = Callback::new(move |_: ()| nav.close());
+
+    let toggle_dark_mode = move |_| {
+        theme.toggle_theme.dispatch(());
+        
+        // Log the dark mode change
+        let new_preference = !dark_mode.get(); // Predict new value
+        log_event(LogCategory::Theme, Level::Info, &format!("Dark mode changed to {}", new_preference));
+    };
+
+    let change_locale = move |ev| {
+        let new_locale = event_target_value(&ev);
+        match set_locale(&new_locale) {
+            Ok(_) => {
+                log_event(LogCategory::Locale, Level::Info, &format!("Changed to {}", new_locale));
+                locale.set(new_locale);
+            },
+            Err(err) => {
+                error!("Failed to save language: {:?}", err);
+                status.report_error(DataError::Storage(err));
+            }
+        }
+    };
+
+    // With the passphrase setting on, export and import wait for the prompt
+    let passphrase_on = RwSignal::new(passphrase_required());
+    let passphrase_request = RwSignal::new(Option::<PassphraseRequest>::None);
+    // Files read while the passphrase setting is on, waiting for the prompt
+    let awaiting_passphrase = StoredValue::new(Option::<Vec<(String, Result<String, DataError>)>>::None);
+    
+    let toggle_passphrase = move |_| {
+        let required = !passphrase_on.get_untracked();
+        match set_passphrase_required(required) {
+            Ok(()) => passphrase_on.set(required),
+            Err(err) => {
+                error!("Failed to save passphrase setting: {:?}", err);
+                status.report_error(DataError::Storage(err));
+            }
+        }
+    };
+    
+    // Opt-in to keeping the derived key until the session idles out
+    let auto_lock_on = RwSignal::new(auto_lock_after().is_some());
+    let toggle_auto_lock = move |_| {
+        let on = !auto_lock_on.get_untracked();
+        match set_auto_lock_after(on.then_some(DEFAULT_AUTO_LOCK_AFTER)) {
+            Ok(()) => auto_lock_on.set(on),
+            Err(err) => {
+                error!("Failed to save auto-lock setting: {:?}", err);
+                status.report_error(DataError::Storage(err));
+            }
+        }
+    };
+    
+    // Recovery code just created, shown until dismissed and never again
+    let (recovery_code, set_recovery_code) = signal(Option::<String>::None);
+    let create_recovery = move |_| {
+        match create_recovery_code() {
+            Ok(code) => set_recovery_code.set(Some(code)),
+            Err(err) => {
+                error!("Failed to create recovery code: {}", err);
+                status.report_error(err);
+            }
+        }
+    };
+    
+    // What imports do with data that's already stored
+    let import_mode = RwSignal::new(ImportMode::default());
+    // Opt-in to restoring a backup exported by another player
+    let restore_other_player = RwSignal::new(false);
+    let preview_key_options = move || KeyOptions { restore_other_player: restore_other_player.get_untracked(), ..Default::default() };
+    let change_import_mode = move |ev| {
+        if let Ok(mode) = event_target_value(&ev).parse::<ImportMode>() {
+            import_mode.set(mode);
+        }
+    };
+    
+    // The user's encryption choice, only offered under `UserChoice`
+    let encrypt_on = RwSignal::new(true);
+    let show_encrypt_toggle = encryption == EncryptionPolicy::UserChoice && !is_safe_mode();
+    
+    let run_export = move |passphrase_key: Option<PassphraseKey>, recovery: Option<RecoveryKey>, hint: Option<String>| {
+        // Clear any previous messages
+        set_export_success.set(None);
+        set_export_warning.set(None);
+        set_load_success.set(None);
+        status.begin();
+        
+        // Get the data to export
+        let policy = match encryption {
+            EncryptionPolicy::UserChoice if !encrypt_on.get_untracked() => EncryptionPolicy::Never,
+            policy => policy,
+        };
+        let exported = match &passphrase_key {
+            Some(passphrase_key) => service.export_with_key(passphrase_key, recovery.as_ref(), hint.as_deref(), policy),
+            None => service.export_with_policy(None, None, policy),
+        };
+        let result = match exported {
+            Ok(ExportedBackup { json: export_json, encrypted, warning }) => {
+                set_export_warning.set(warning);
+                
+                // Name the file after its schema version, encryption and time
+                let filename = backup_filename(None, encrypted, EXPORT_VERSION);
+                
+                // Trigger the download
+                trigger_download(&export_json, &filename)
+                    .map_err(DataError::from)
+                    .map(|_| {
+                        // Set success message
+                        set_export_success.set(Some("Data exported successfully".to_string()));
+                        
+                        // Log export action
+                        log_event(LogCategory::DataExport, Level::Info, &format!("Export initiated: {}", filename));
+                    })
+                    .inspect_err(|err| error!("{}", err))
+            },
+            // Handle export error
+            Err(err) => Err(err),
+        };
+        status.finish(&result);
+    };
+    
+    // Export button click handler
+    let export_button_click = move |_| {
+        // A passphrase means encrypting, which `Never` rules out
+        if passphrase_on.get_untracked() && encryption != EncryptionPolicy::Never && encrypt_on.get_untracked() {
+            // An unlocked session reuses its key until it idles out
+            match session_key() {
+                Some(passphrase_key) => run_export(Some(passphrase_key), None, None),
+                None => passphrase_request.set(Some(PassphraseRequest::Export)),
+            }
+        } else {
+            run_export(None, None, None);
+        }
+    };
+    
+
+    // Re-read imported values from storage and sync the UI with them
+    let refresh_from_storage = move || {
+        // Refresh the player ID display
+        if let Ok(Some(id)) = localStorage::get_storage_item("player_id") {
+            player_id.set(id);
+        }
+        
+        // Refresh dark mode preference display
+        sync_theme_from_storage(theme);
+        
+        // Refresh the language selector
+        locale.set(get_locale());
+    };
+    
+    let restore_from_autosave = move || {
+        set_load_success.set(None);
+        status.begin();
+        let result = restore_autosave();
+        if let Ok(summary) = &result {
+            set_load_success.set(Some(summary.message.clone()));
+            announcer.announce(format!("Backup restored: {}", summary.message));
+            refresh_from_storage();
+        }
+        status.finish(&result);
+    };
+    let restore_autosave_click = move |_| unsaved_changes.guard(restore_from_autosave);
+    
+    // Apply every selected file, in selection order, once all reads have finished.
+    // Runs as a task that yields between files and import stages, so the
+    // progress bar paints while a large batch is applied.
+    let import_files = move |entries: Vec<(String, Result<String, DataError>)>, key_options: KeyOptions| spawn_local(async move {
+        let key_options = KeyOptions { restore_other_player: restore_other_player.get_untracked(), ..key_options };
+        let total = entries.len();
+        let mut results: Vec<FileImportResult> = Vec::with_capacity(total);
+        for (index, (file_name, read_result)) in entries.into_iter().enumerate() {
+            // This file's share of the batch
+            let on_progress = Callback::new(move |fraction: f32| {
+                status.report_progress((index as f32 + fraction) / total as f32);
+            });
+            let result = match read_result {
+                Ok(text) => service.import_with_progress(&text, &key_options, import_mode.get_untracked(), on_progress).await,
+                Err(err) => Err(err),
+            };
+            status.report_progress((index + 1) as f32 / total as f32);
+            results.push(FileImportResult { result, file_name });
+            TimeoutFuture::new(0).await;
+        }
+        
+        for file_result in &results {
+            match &file_result.result {
+                Ok(_) => {
+                    log_event(LogCategory::DataImport, Level::Info, &format!("File import successful: {}", file_result.file_name));
+                },
+                Err(err) => {
+                    log_event(LogCategory::DataImport, Level::Error, &format!("{}: {}", file_result.file_name, err));
+                }
+            }
+        }
+        
+        let summary = batch_summary(&results);
+        if results.iter().any(|r| r.result.is_ok()) {
+            bus.emit(AppEvent::ImportCompleted(summary.clone()));
+            set_load_success.set(Some(summary));
+            refresh_from_storage();
+            status.finish(&Ok::<(), DataError>(()));
+        } else {
+            // Report the first failure; the per-file list shows the rest
+            let first_error = results.iter().find_map(|r| r.result.clone().err());
+            status.finish(&first_error.map_or(Ok(()), Err));
+        }
+        
+        // Only list individual files when more than one was selected
+        set_batch_results.set(if results.len() > 1 { results } else { Vec::new() });
+    });
+    
+    // With the passphrase setting on, hold the files until the prompt is
+    // answered, showing the backup's hint to jog the user's memory
+    let import_or_ask_passphrase = move |entries: Vec<(String, Result<String, DataError>)>| {
+        // The session key only opens backups sharing its salt
+        let session_key = session_key().filter(|passphrase_key| {
+            passphrase_on.get_untracked() && entries.iter().all(|(_, read_result)| {
+                read_result.as_deref().ok().and_then(backup_salt).is_none_or(|salt| salt == passphrase_key.salt)
+            })
+        });
+        if let Some(passphrase_key) = session_key {
+            import_files(entries, KeyOptions::with_passphrase_key(passphrase_key));
+        } else if passphrase_on.get_untracked() {
+            let hint = entries.iter().find_map(|(_, read_result)| read_result.as_deref().ok().and_then(backup_hint));
+            awaiting_passphrase.set_value(Some(entries));
+            passphrase_request.set(Some(PassphraseRequest::Import { hint }));
+        } else {
+            import_files(entries, KeyOptions::default());
+        }
+    };
+    
+    // Large imports, and ones that may not fit in storage, wait for a typed
+    // confirmation that shows how much they'll write
+    let awaiting_large_import = RwSignal::new(Option::<(Vec<(String, Result<String, DataError>)>, ImportImpact)>::None);
+    let apply_loaded_files = move |entries: Vec<(String, Result<String, DataError>)>| {
+        let texts = || entries.iter().filter_map(|(_, read_result)| read_result.as_deref().ok());
+        let large = texts().any(|text| import_severity(text) == Severity::High);
+        // Passphrase backups can't be opened yet, so they aren't counted
+        let impacts: Vec<ImportImpact> = texts().filter_map(|text| estimate_import_impact(text).ok()).collect();
+        let impact = ImportImpact {
+            keys: impacts.iter().map(|impact| impact.keys).sum(),
+            bytes: impacts.iter().map(|impact| impact.bytes).sum(),
+            exceeds_quota: impacts.iter().any(|impact| impact.exceeds_quota),
+        };
+        if large || impact.exceeds_quota {
+            awaiting_large_import.set(Some((entries, impact)));
+        } else {
+            import_or_ask_passphrase(entries);
+        }
+    };
+    let confirm_large_import = Callback::new(move |_: ()| {
+        if let Some((entries, _)) = awaiting_large_import.get_untracked() {
+            awaiting_large_import.set(None);
+            import_or_ask_passphrase(entries);
+        }
+    });
+    let cancel_large_import = Callback::new(move |_: ()| {
+        awaiting_large_import.set(None);
+        status.finish(&Ok::<(), DataError>(()));
+    });
+    
+    // A loaded or dropped backup waits here while its changes are previewed
+    let pending_import = RwSignal::new(Option::<ImportPreview>::None);
+    let import_dropped = move |entries: Vec<(String, Result<String, DataError>)>| {
+        set_export_success.set(None);
+        set_load_success.set(None);
+        set_batch_results.set(Vec::new());
+        status.begin();
+        apply_loaded_files(entries);
+    };
+    // A single loaded file is previewed first; several files, partial backups
+    // and passphrase backups import straight away
+    let preview_loaded_files = move |entries: Vec<(String, Result<String, DataError>)>| {
+        let preview = match entries.as_slice() {
+            [(_, Ok(text))] => ImportPreview::new(text.clone(), import_mode.get_untracked(), &preview_key_options()).ok(),
+            _ => None,
+        };
+        match preview {
+            Some(preview) => {
+                pending_import.set(Some(preview));
+                status.finish(&Ok::<(), DataError>(()));
+            },
+            None => apply_loaded_files(entries),
+        }
+    };
+    
+    // Handlers of the latest load, replaced by the next one
+    let active_picker = StoredValue::new_local(Option::<FilePicker>::None);
+    let active_batch = StoredValue::new_local(Option::<FileReadBatch>::None);
+    
+    // Open the file picker and import the chosen files
+    let load_data = move || {
+        // Clear any previous messages
+        set_export_success.set(None);
+        set_load_success.set(None);
+        set_batch_results.set(Vec::new());
+        status.begin();
+        
+        // Drop the previous load's input and reads before starting another
+        active_picker.set_value(None);
+        active_batch.set_value(None);
+        
+        // Create a file input element
+        let window = web_sys::window().expect("No window found");
+        let document = window.document().expect("No document found");
+        
+        // Create a file input element
+        let file_input = document
+            .create_element("input")
+            .expect("Failed to create input element");
+        
+        // Set attributes for the file input
+        file_input
+            .set_attribute("type", "file")
+            .expect("Failed to set input type");
+        file_input
+            .set_attribute("accept", ".json")
+            .expect("Failed to set accept attribute");
+        file_input
+            .set_attribute("multiple", "")
+            .expect("Failed to set multiple attribute");
+        file_input
+            .set_attribute("style", "display: none;")
+            .expect("Failed to set style attribute");
+        
+        // Add the input to the document body
+        let body = document.body().expect("No body found");
+        body.append_child(&file_input)
+            .expect("Failed to append file input");
+        
+        // Create a reference to file_input that will be shared by the closure
+        let file_input_ref = file_input.clone();
+        
+        // Use FnMut instead of FnOnce
+        let onchange_callback = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            // Create a separate clone here to avoid moving file_input_ref
+            let input_elem = file_input_ref.clone();
+            let file_input = input_elem
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .expect("Failed to cast to HtmlInputElement");
+            
+            // Get the selected files - files is a property, not a method
+            let files = file_input.files();
+            if let Some(files) = files {
+                let file_count = files.length();
+                if file_count > 0 {
+                    let selected = (0..file_count).map(|index| files.get(index)).collect();
+                    let batch = FileReadBatch::start(selected, preview_loaded_files);
+                    
+                    // Give up on reads that stall so the busy state doesn't stick
+                    let limit = operation_timeout();
+                    let give_up_if_stalled = batch.stall_check();
+                    set_timeout(move || {
+                        if give_up_if_stalled() {
+                            error!("Reading backup files timed out after {:?}", limit);
+                            status.finish(&Err::<(), _>(DataError::Timeout(limit)));
+                        }
+                    }, limit);
+                    
+                    active_batch.set_value(Some(batch));
+                } else {
+                    // No file selected
+                    let error_msg = "No file selected".to_string();
+                    error!("{}", &error_msg);
+                    status.finish(&Err::<(), _>(DataError::FileRead(error_msg)));
+                }
+            } else {
+                // No files property
+                let error_msg = "Failed to access file input files".to_string();
+                error!("{}", &error_msg);
+                status.finish(&Err::<(), _>(DataError::FileRead(error_msg)));
+            }
+            
+            // The input has done its job
+            file_input_ref.remove();
+        }) as Box<dyn FnMut(_)>);
+        
+        // Set the onchange handler
+        file_input
+            .add_event_listener_with_callback("change", onchange_callback.as_ref().unchecked_ref())
+            .expect("Failed to add event listener");
+        
+        // Closing the file dialog without choosing a file ends the operation
+        let oncancel_callback = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+            status.finish(&Ok::<(), DataError>(()));
+        }) as Box<dyn FnMut(_)>);
+        file_input
+            .add_event_listener_with_callback("cancel", oncancel_callback.as_ref().unchecked_ref())
+            .expect("Failed to add event listener");
+        
+        // Keep the handlers alive until the next load replaces them
+        active_picker.set_value(Some(FilePicker {
+            input: file_input.clone(),
+            _onchange: onchange_callback,
+            _oncancel: oncancel_callback,
+        }));
+        
+        // Trigger click on the file input to open file dialog
+        let file_input_html = file_input
+            .dyn_into::<web_sys::HtmlElement>()
+            .expect("Failed to cast to HtmlElement");
+        file_input_html.click();
+        
+        // Log load action
+        log_event(LogCategory::DataLoad, Level::Info, "File picker dialog opened");
+    };
+    
+    let submit_passphrase = Callback::new(move |entry: PassphraseEntry| {
+        let request = passphrase_request.get_untracked();
+        passphrase_request.set(None);
+        // Only the derived key is kept for the session, never the passphrase;
+        // a recovery code opens one import and isn't kept at all
+        let keep = |passphrase_key: &PassphraseKey| {
+            if parse_recovery_code(&entry.passphrase).is_none() {
+                unlock_session(passphrase_key.clone());
+            }
+        };
+        match request {
+            Some(PassphraseRequest::Export) => {
+                // The recovery code is checked but, like the passphrase, never kept
+                let recovery = entry.recovery_code.as_deref().map(unlock_recovery_code).transpose();
+                match (new_passphrase_salt().map_err(DataError::from), recovery) {
+                    (Ok(salt), Ok(recovery)) => {
+                        let passphrase_key = PassphraseKey::derive(&entry.passphrase, &salt);
+                        keep(&passphrase_key);
+                        run_export(Some(passphrase_key), recovery, entry.hint.clone());
+                    },
+                    (Err(err), _) | (_, Err(err)) => status.report_error(err),
+                }
+            },
+            Some(PassphraseRequest::Import { .. }) => {
+                if let Some(entries) = awaiting_passphrase.get_value() {
+                    awaiting_passphrase.set_value(None);
+                    let salt = entries.iter().find_map(|(_, read_result)| read_result.as_deref().ok().and_then(backup_salt));
+                    let mut key_options = KeyOptions::with_passphrase(&entry.passphrase);
+                    if let Some(salt) = salt {
+                        let passphrase_key = PassphraseKey::derive(&entry.passphrase, &salt);
+                        keep(&passphrase_key);
+                        key_options.passphrase_key = Some(passphrase_key);
+                    }
+                    import_files(entries, key_options);
+                }
+            },
+            None => {},
+        }
+    });
+    let cancel_passphrase = Callback::new(move |_: ()| {
+        if matches!(passphrase_request.get_untracked(), Some(PassphraseRequest::Import { .. })) {
+            // The read files are dropped and the load ends without importing
+            awaiting_passphrase.set_value(None);
+            status.finish(&Ok::<(), DataError>(()));
+        }
+        passphrase_request.set(None);
+    });
+    
+    // Load button click handler; importing replaces state, so unsaved edits are confirmed first
+    let load_button_click = move |_| unsaved_changes.guard(load_data);
+
+    // Backups dropped onto the drop zone are previewed like loaded ones
+    let on_drop = move |ev: web_sys::DragEvent| {
+        ev.prevent_default();
+        let Some(transfer) = ev.data_transfer() else { return };
+        
+        // Dropped files go through the same reads as the file picker
+        if let Some(files) = transfer.files().filter(|files| files.length() > 0) {
+            let selected: Vec<Option<web_sys::File>> = (0..files.length()).map(|index| files.get(index)).collect();
+            unsaved_changes.guard(move || {
+                active_batch.set_value(None);
+                set_export_success.set(None);
+                set_load_success.set(None);
+                set_batch_results.set(Vec::new());
+                status.begin();
+                active_batch.set_value(Some(FileReadBatch::start(selected.clone(), preview_loaded_files)));
+            });
+            return;
+        }
+        
+        let text = transfer.get_data("text/plain").unwrap_or_default();
+        log_event(LogCategory::DataLoad, Level::Info, &format!("Text dropped for import ({} bytes)", text.len()));
+        match ImportPreview::new(text.clone(), import_mode.get_untracked(), &preview_key_options()) {
+            Ok(preview) => pending_import.set(Some(preview)),
+            // Passphrase backups can only be previewed once they're opened,
+            // so they go straight to the prompt
+            Err(DataError::PassphraseRequired) => {
+                unsaved_changes.guard(move || import_dropped(vec![("Dropped text".to_string(), Ok(text.clone()))]));
+            },
+            Err(err) => {
+                error!("Dropped text is not a backup: {}", err);
+                status.finish(&Err::<(), _>(err));
+            },
+        }
+    };
+    let confirm_import = move |_| {
+        if let Some(preview) = pending_import.get_untracked() {
+            pending_import.set(None);
+            unsaved_changes.guard(move || import_dropped(vec![("Previewed backup".to_string(), Ok(preview.text.clone()))]));
+        }
+    };
+
+    // Contents shared by the panel and the inline layout
+    let panel_body = move || view! {
+        <p>"Your locally stored data:"</p>
+        {move || {
+            if let Some(error) = status.last_error.get() {
+                view! {
+                    <p 
+                        data-test-id="storage-error"
+                        class={use_error_message_class}
+                    >
+                        {"Error: "}{error.to_string()}
+                    </p>
+                }.into_any()
+            } else {
+                view! {
+                    <div>
+                        <p 
+                            data-test-id="player-id"
+                            class={use_player_id_class}
+                        >
+                            {"Player ID: "}{player_id.get()}
+                        </p>
+                        <CopyButton
+                            text={Signal::derive(move || player_id.get())}
+                            label="Copy ID"
+                            test_id="copy-player-id"
+                        />
+                        <CopyInviteLink />
+                        <CopyButton
+                            text={Signal::derive(export_flat)}
+                            label="Copy diagnostics"
+                            test_id="copy-diagnostics"
+                        />
+                        <ContrastCheck vars={theme.vars} />
+                        <p>
+                            <span>{"Dark Mode: "}{if dark_mode.get() { "Enabled" } else { "Disabled" }}</span>
+                            <button
+                                data-test-id="dark-mode-toggle"
+                                class={use_dark_mode_toggle_button_class}
+                                on:click={toggle_dark_mode}
+                            >
+                                {if dark_mode.get() { "Disable" } else { "Enable" }}
+                            </button>
+                        </p>
+                        <div class="flex items-center space-x-2">
+                            <span>{"Accent: "}</span>
+                            <AccentPicker />
+                        </div>
+                        {show_encrypt_toggle.then(|| view! {
+                            <p>
+                                <label>
+                                    <input
+                                        type="checkbox"
+                                        data-test-id="encrypt-export-toggle"
+                                        prop:checked={move || encrypt_on.get()}
+                                        on:change={move |ev| encrypt_on.set(event_target_checked(&ev))}
+                                    />
+                                    {" Encrypt exported backups"}
+                                </label>
+                            </p>
+                        })}
+                        {(!is_safe_mode() && encryption != EncryptionPolicy::Never).then(|| view! {
+                            <p>
+                                <label>
+                                    <input
+                                        type="checkbox"
+                                        data-test-id="passphrase-toggle"
+                                        prop:checked={move || passphrase_on.get()}
+                                        on:change={toggle_passphrase}
+                                    />
+                                    {" Require a passphrase to export and import"}
+                                </label>
+                                {move || passphrase_on.get().then(|| view! {
+                                    <button
+                                        data-test-id="recovery-code-button"
+                                        class={use_button_class}
+                                        on:click={create_recovery}
+                                    >
+                                        "Create recovery code"
+                                    </button>
+                                })}
+                            </p>
+                            {move || passphrase_on.get().then(|| view! {
+                                <p>
+                                    <label>
+                                        <input
+                                            type="checkbox"
+                                            data-test-id="auto-lock-toggle"
+                                            prop:checked={move || auto_lock_on.get()}
+                                            on:change={toggle_auto_lock}
+                                        />
+                                        {" Stay unlocked until idle for 5 minutes"}
+                                    </label>
+                                </p>
+                            })}
+                            {move || recovery_code.get().map(|code| view! {
+                                <p data-test-id="recovery-code-panel">
+                                    "Store this code somewhere safe; it won't be shown again. Only a check of it is kept here, so enter it when exporting each passphrase backup it should open: "
+                                    <code data-test-id="recovery-code">{code}</code>
+                                    <button
+                                        data-test-id="recovery-code-dismiss"
+                                        class={use_button_class}
+                                        on:click={move |_| set_recovery_code.set(None)}
+                                    >
+                                        "I've saved it"
+                                    </button>
+                                </p>
+                            })}
+                        })}
+                        <p>
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    data-test-id="autosave-toggle"
+                                    prop:checked={move || autosave_on.get()}
+                                    on:change={toggle_autosave}
+                                />
+                                {" Autosave for crash recovery"}
+                            </label>
+                            <button
+                                data-test-id="restore-autosave-button"
+                                class={use_button_class}
+                                disabled={move || !autosave_on.get() && !had_autosave}
+                                on:click={restore_autosave_click}
+                            >
+                                "Restore from autosave"
+                            </button>
+                        </p>
+                        <p>
+                            <label for="locale-select">{"Language: "}</label>
+                            <select
+                                id="locale-select"
+                                data-test-id="locale-select"
+                                class="ml-2 rounded border px-2 py-1 text-sm text-gray-900"
+                                prop:value={move || locale.get()}
+                                on:change={change_locale}
+                            >
+                                {SUPPORTED_LOCALES.iter().map(|(code, name)| view! {
+                                    <option value={*code}>{*name}</option>
+                                }).collect_view()}
+                                // Keep an unlisted browser language selectable
+                                {move || {
+                                    let current = locale.get();
+                                    (!SUPPORTED_LOCALES.iter().any(|(code, _)| *code == current))
+                                        .then(|| view! { <option value={current.clone()}>{current.clone()}</option> })
+                                }}
+                            </select>
+                        </p>
+                        <p>
+                            <label for="import-mode-select">{"When loading: "}</label>
+                            <select
+                                id="import-mode-select"
+                                data-test-id="import-mode-select"
+                                class="ml-2 rounded border px-2 py-1 text-sm text-gray-900"
+                                prop:value={move || import_mode.get().as_str()}
+                                on:change={change_import_mode}
+                            >
+                                {ImportMode::ALL.iter().map(|mode| view! {
+                                    <option value={mode.as_str()}>{mode.label()}</option>
+                                }).collect_view()}
+                            </select>
+                        </p>
+                        <p>
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    data-test-id="restore-other-player-toggle"
+                                    prop:checked={move || restore_other_player.get()}
+                                    on:change={move |ev| restore_other_player.set(event_target_checked(&ev))}
+                                />
+                                {" Restore a backup from another player as a new profile"}
+                            </label>
+                        </p>
+
+                        <div class="mt-4 flex space-x-2">
+                            <button
+                                data-test-id="export-data-button"
+                                class={use_button_class}
+                                on:click={export_button_click}
+                            >
+                                "Export Data"
+                            </button>
+
+                            <button
+                                data-test-id="load-data-button"
+                                class={use_button_class}
+                                on:click={load_button_click}
+                            >
+                                "Load Data"
+                            </button>
+                        </div>
+                        <div
+                            data-test-id="data-drop-zone"
+                            class="mt-2 rounded border-2 border-dashed border-gray-400 p-2 text-sm text-center"
+                            on:dragover={move |ev: web_sys::DragEvent| ev.prevent_default()}
+                            on:drop={on_drop}
+                        >
+                            "Drop a backup file or its text here to import it"
+                        </div>
+                        {move || pending_import.get().map(|preview| {
+                            let or_none = |value: &str| if value.is_empty() { "none".to_string() } else { value.to_string() };
+                            view! {
+                                <div data-test-id="import-preview" class="mt-2">
+                                    <p>"This backup contains:"</p>
+                                    <ul class="text-sm">
+                                        <li data-test-id="import-preview-player-id">
+                                            {format!("Player ID: {} (now {})", or_none(&preview.incoming.player_id), or_none(&preview.current.player_id))}
+                                        </li>
+                                        <li data-test-id="import-preview-theme">
+                                            {format!("Theme: {} (now {})", preview.incoming.theme(), preview.current.theme())}
+                                        </li>
+                                    </ul>
+                                    <p>"Importing it will make these changes:"</p>
+                                    <ImportChanges changes={preview.changes} />
+                                    <button
+                                        data-test-id="import-preview-confirm"
+                                        class={use_button_class}
+                                        on:click={confirm_import}
+                                    >
+                                        "Import"
+                                    </button>
+                                    <button
+                                        data-test-id="import-preview-cancel"
+                                        class={use_button_class}
+                                        on:click={move |_| pending_import.set(None)}
+                                    >
+                                        "Cancel"
+                                    </button>
+                                </div>
+                            }
+                        })}
+                        {(!is_safe_mode()).then(|| view! { <QrExport /> })}
+
+                        <div class="mt-2">
+                            {move || status.is_busy.get().then(|| view! {
+                                <p data-test-id="data-busy" class="text-sm italic">
+                                    "Working…"
+                                    {move || status.progress.get().map(|fraction| view! {
+                                        <progress
+                                            data-test-id="data-progress"
+                                            class="ml-2 align-middle"
+                                            max="1"
+                                            value={fraction.to_string()}
+                                        />
+                                    })}
+                                </p>
+                            })}
+                            {move || export_warning.get().map(|warning| view! {
+                                <p
+                                    data-test-id="export-warning"
+                                    class="text-yellow-600 dark:text-yellow-400"
+                                >
+                                    {warning}
+                                </p>
+                            })}
+                            {move || {
+                                if let Some(success) = export_success.get() {
+                                    view! {
+                                        <p 
+                                            data-test-id="export-success-message"
+                                            class="text-green-600 dark:text-green-400"
+                                        >
+                                            {success}
+                                        </p>
+                                    }.into_any()
+                                } else if let Some(success) = load_success.get() {
+                                    view! {
+                                        <p 
+                                            data-test-id="load-success-message"
+                                            class="text-green-600 dark:text-green-400"
+                                        >
+                                            {success}
+                                        </p>
+                                    }.into_any()
+                                } else {
+                                    view! {}.into_any()
+                                }
+                            }}
+                            <ul data-test-id="import-file-results" class="mt-1 text-sm">
+                                // Keyed by position, since several files can share a name;
+                                // rows read their result so a new batch updates them in place
+                                <For
+                                    each={move || 0..batch_results.with(Vec::len)}
+                                    key={|index| *index}
+                                    let:index
+                                >
+                                    <li
+                                        data-test-id="import-file-result"
+                                        class={move || match batch_results.with(|results| results.get(index).map(|r| r.result.is_ok())) {
+                                            Some(true) => "text-green-600 dark:text-green-400",
+                                            _ => "text-red-600 dark:text-red-400",
+                                        }}
+                                    >
+                                        {move || batch_results.with(|results| results.get(index).map(|file_result| match &file_result.result {
+                                            Ok(summary) => format!("{}: {}", file_result.file_name, summary.message),
+                                            Err(err) => format!("{}: {}", file_result.file_name, err),
+                                        }))}
+                                    </li>
+                                </For>
+                            </ul>
+                        </div>
+                    </div>
+                }.into_any()
+            }
+        }}
+        {move || awaiting_large_import.with(|pending| pending.as_ref().map(|(_, impact)| *impact)).map(|impact| view! {
+            <ConfirmModal
+                message={format!("This backup is unusually large and will replace much of your data. {}. Import it anyway?", impact.summary())}
+                test_id="large-import"
+                severity=Severity::High
+                on_confirm={confirm_large_import}
+                on_cancel={cancel_large_import}
+            />
+        })}
+        {move || passphrase_request.get().map(|request| match request {
+            PassphraseRequest::Export => view! {
+                <PassphrasePrompt
+                    message="Enter a passphrase to protect this backup"
+                    test_id="passphrase"
+                    ask_hint=true
+                    ask_recovery_code={has_recovery_code()}
+                    on_submit={submit_passphrase}
+                    on_cancel={cancel_passphrase}
+                />
+            }.into_any(),
+            PassphraseRequest::Import { hint } => view! {
+                <PassphrasePrompt
+                    message="Enter the passphrase or recovery code for the backup"
+                    test_id="passphrase"
+                    hint={hint}
+                    on_submit={submit_passphrase}
+                    on_cancel={cancel_passphrase}
+                />
+            }.into_any(),
+        })}
+    };
+
+    if inline {
+        // Always-visible layout for dashboards
+        return view! {
+            <div
+                data-test-id="data-inline"
+                class={use_data_panel_class()}
+            >
+                <h2 class={use_data_header_class()}>"Locally Stored Data"</h2>
+                <div class={use_data_content_class()}>
+                    {panel_body()}
+                </div>
+            </div>
+        }.into_any();
+    }
+    
+    view! {
+        <div class="mt-6">
+            {move || {
+                if nav.is_open(PanelId::Data) {
+                    // Panel view
+                    view! {
+                        <Panel
+                            title="Locally Stored Data"
+                            test_id="data"
+                            on_close={hide_panel}
+                        >
+                            {panel_body()}
+                        </Panel>
+                    }.into_any()
+                } else {
+                    // Button view
+                    view! {
+                        <button
+                            data-test-id="data-button"
+                            class={use_button_class}
+                            on:click={show_panel_click}
+                        >
+                            "Locally Stored Data"
+                        </button>
+                    }.into_any()
+                }
+            }}
+        </div>
+    }.into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+    use gloo_timers::future::TimeoutFuture;
+    use crate::theme::ThemeProvider;
+    
+    wasm_bindgen_test_configure!(run_in_browser);
+    
+    #[wasm_bindgen_test]
+    fn test_import_batch_merges_fields_from_each_file() {
+        localStorage::reset_all_storage();
+        
+        // One backup only carries the identity, the other only the theme
+        let identity_backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "batch_player_1" }
+        }).to_string();
+        let theme_backup = crate::crypto::encrypt_data(&json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-10T12:00:00Z",
+            "data": { "dark_mode": true }
+        }).to_string()).expect("Encryption should succeed");
+        
+        let results = import_batch(&[
+            ("identity.json".to_string(), identity_backup),
+            ("theme.json".to_string(), theme_backup),
+        ], &KeyOptions::default(), ImportMode::Overwrite);
+        
+        assert!(results.iter().all(|r| r.result.is_ok()), "Both files should import: {:?}", results);
+        assert_eq!(batch_summary(&results), "Imported 2 of 2 files");
+        
+        // The merged result contains fields from both files
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("batch_player_1".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_old_backup_without_dark_mode_is_migrated() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        
+        let old_backup = json!({
+            "version": "0.0.1",
+            "timestamp": "2024-11-02T12:00:00Z",
+            "data": { "player_id": "old_player" }
+        }).to_string();
+        import_data(&old_backup).expect("A 0.0.1 backup should import");
+        
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("old_player".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("false".to_string()), "Missing theme defaults to light");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_migrate_only_fills_fields_for_older_versions() {
+        let without_theme = |version: &str| json!({
+            "version": version,
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "migrated_player" }
+        });
+        
+        let migrated = migrate(without_theme("0.0.3"), "0.0.3").expect("0.0.x lacked dark_mode");
+        assert!(!migrated.data.dark_mode);
+        assert_eq!(migrated.version, EXPORT_VERSION);
+        
+        assert!(migrate(without_theme(EXPORT_VERSION), EXPORT_VERSION).is_err(), "Current backups must carry dark_mode");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_modes_with_existing_data() {
+        let backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "imported_player", "dark_mode": true, "locale": "fr-FR" }
+        }).to_string();
+        let stored = |key: &str| localStorage::get_storage_item(key).unwrap();
+        let import_over_local = |mode: ImportMode| {
+            localStorage::reset_all_storage();
+            let _ = localStorage::set_storage_item("player_id", "local_player");
+            let _ = localStorage::set_storage_item("dark_mode", "false");
+            import_data_with_mode(&backup, mode).expect("Import should succeed")
+        };
+        
+        import_over_local(ImportMode::Overwrite);
+        assert_eq!(stored("player_id"), Some("imported_player".to_string()));
+        assert_eq!(stored("dark_mode"), Some("true".to_string()));
+        assert_eq!(stored("locale"), Some("fr-FR".to_string()));
+        
+        // Only the missing language is filled in
+        import_over_local(ImportMode::MergeKeepLocal);
+        assert_eq!(stored("player_id"), Some("local_player".to_string()));
+        assert_eq!(stored("dark_mode"), Some("false".to_string()));
+        assert_eq!(stored("locale"), Some("fr-FR".to_string()));
+        
+        // The identity is kept, everything else comes from the backup
+        let summary = import_over_local(ImportMode::MergePreferImported);
+        assert_eq!(stored("player_id"), Some("local_player".to_string()));
+        assert_eq!(stored("dark_mode"), Some("true".to_string()));
+        assert_eq!(stored("locale"), Some("fr-FR".to_string()));
+        assert!(summary.changes.iter().all(|change| change.field != "player_id"), "{:?}", summary.changes);
+        
+        // Nothing missing locally means nothing to merge
+        let summary = import_data_with_mode(&backup.replace("fr-FR", "de-DE"), ImportMode::MergeKeepLocal).unwrap();
+        assert!(summary.no_op);
+        assert_eq!(summary.message, KEPT_LOCAL_MESSAGE);
+        assert_eq!(stored("locale"), Some("fr-FR".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_preview_import_does_not_mutate_storage() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "previewing_player");
+        let _ = localStorage::set_storage_item("dark_mode", "false");
+        let before = current_app_state();
+        
+        let plain = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "previewed_player", "dark_mode": true }
+        }).to_string();
+        let encrypted = crate::crypto::encrypt_data(&plain).expect("Encryption should succeed");
+        
+        for backup in [&plain, &encrypted] {
+            let incoming = preview_import(backup).expect("Preview should succeed");
+            assert_eq!(incoming.player_id, "previewed_player");
+            assert!(incoming.dark_mode);
+        }
+        assert!(matches!(preview_import("not a backup"), Err(DataError::Parse(_))));
+        
+        assert_eq!(current_app_state(), before, "Previewing must not write anything");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("previewing_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_imported_theme_change_does_not_dispatch_toggle() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("dark_mode", "false");
+        
+        // Capture the theme context from inside the provider
+        let captured_theme = RwSignal::new(Option::<ThemeState>::None);
+        
+        #[component]
+        fn ThemeProbe(capture: RwSignal<Option<ThemeState>>) -> impl IntoView {
+            capture.set(Some(use_theme()));
+        }
+        
+        mount_to_body(move || view! {
+            <ThemeProvider>
+                <ThemeProbe capture={captured_theme} />
+                <DataButton />
+            </ThemeProvider>
+        });
+        
+        let theme = captured_theme.get_untracked().expect("Theme context should be captured");
+        let toggles_before = theme.toggle_theme.version().get_untracked();
+        
+        // Import a backup that switches to dark mode, then refresh twice as the UI may
+        let backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "theme_lock_player", "dark_mode": true }
+        }).to_string();
+        import_data(&backup).expect("Import should succeed");
+        sync_theme_from_storage(theme);
+        sync_theme_from_storage(theme);
+        
+        TimeoutFuture::new(100).await;
+        
+        assert!(theme.dark_mode.get_untracked(), "Imported dark mode should be applied");
+        assert!(!theme.transition_lock.get_untracked(), "Transition lock should be released after the tick");
+        assert_eq!(
+            theme.toggle_theme.version().get_untracked(), toggles_before,
+            "Programmatic theme changes should not dispatch the toggle action"
+        );
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_locale_round_trips_through_export() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "locale_player");
+        crate::utils::set_locale("de-DE").expect("Saving the locale should succeed");
+        
+        let exported = export_data().expect("Export should succeed");
+        
+        // Import into fresh storage
+        localStorage::reset_all_storage();
+        import_data(&exported).expect("Import should succeed");
+        
+        assert_eq!(get_locale(), "de-DE", "Non-default locale should survive the backup");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_without_locale_keeps_default() {
+        localStorage::reset_all_storage();
+        
+        // Backups made before locales existed have no locale field
+        let legacy_backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "legacy_player", "dark_mode": false }
+        }).to_string();
+        import_data(&legacy_backup).expect("Import without a locale should succeed");
+        
+        assert_eq!(localStorage::get_storage_item("locale").unwrap(), None, "No locale should be stored");
+        assert_eq!(get_locale(), crate::utils::browser_locale());
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_passphrase_backup_needs_the_right_passphrase() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "passphrase_player");
+        
+        let backup = export_backup_with(Some("hunter2"), None).expect("Passphrase export should succeed");
+        assert!(backup.encrypted);
+        localStorage::reset_all_storage();
+        
+        let result = merge_import_data(&backup.json);
+        assert!(matches!(result, Err(DataError::PassphraseRequired)), "{:?}", result);
+        
+        let result = merge_import_data_with(&backup.json, &KeyOptions::with_passphrase("wrong"));
+        assert!(matches!(result, Err(DataError::Crypto(CryptoError::DecryptionError(_)))), "{:?}", result);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None, "Nothing should be imported");
+        
+        merge_import_data_with(&backup.json, &KeyOptions::with_passphrase("hunter2")).expect("Right passphrase should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("passphrase_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_recovery_code_opens_passphrase_backup() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "recovery_player");
+        
+        let code = create_recovery_code().expect("Recovery code should be created");
+        assert!(!localStorage::get_storage_item(RECOVERY_KEY_KEY).unwrap().unwrap().contains(&code), "The code itself is never stored");
+        
+        // Without the code entered the backup only opens with the passphrase
+        let passphrase_key = PassphraseKey::derive("hunter2", &new_passphrase_salt().unwrap());
+        let without_code = export_backup_with_key(&passphrase_key, None, None, EncryptionPolicy::UserChoice).unwrap();
+        assert!(serde_json::from_str::<BackupEnvelope>(&without_code.json).unwrap().recovery.is_none());
+        
+        let other_code = format_recovery_code(&[0u8; RECOVERY_CODE_LEN]);
+        assert!(matches!(unlock_recovery_code(&other_code), Err(DataError::WrongRecoveryCode)));
+        let recovery = unlock_recovery_code(&code).expect("The device's code should unlock");
+        let backup = export_backup_with_key(&passphrase_key, Some(&recovery), None, EncryptionPolicy::UserChoice)
+            .expect("Passphrase export should succeed");
+        localStorage::reset_all_storage();
+        
+        // Any case and spacing of the code works in place of the passphrase
+        let typed = code.to_lowercase().replace('-', " ");
+        merge_import_data_with(&backup.json, &KeyOptions::with_passphrase(&typed)).expect("Recovery code should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("recovery_player".to_string()));
+        
+        localStorage::reset_all_storage();
+        let result = merge_import_data_with(&backup.json, &KeyOptions::with_passphrase(&other_code));
+        assert!(matches!(result, Err(DataError::Crypto(_))), "{:?}", result);
+        merge_import_data_with(&backup.json, &KeyOptions::with_passphrase("hunter2")).expect("The passphrase still works");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_legacy_recovery_escrow_is_removed() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item(RECOVERY_KEY_KEY, r#"{"salt":"AAAA","key":"BBBB"}"#);
+        
+        assert!(!has_recovery_code(), "An escrowed key isn't a usable recovery check");
+        assert_eq!(localStorage::get_storage_item(RECOVERY_KEY_KEY).unwrap(), None, "The escrowed key should be removed");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_password_hint_is_readable_without_the_key() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "hint_player");
+        
+        let backup = export_backup_with(Some("hunter2"), Some("  my old chat handle ")).expect("Export with a hint should succeed");
+        
+        // The hint is in the cleartext envelope, the player ID isn't
+        let envelope: BackupEnvelope = serde_json::from_str(&backup.json).unwrap();
+        assert_eq!(envelope.hint.as_deref(), Some("my old chat handle"));
+        assert_eq!(backup_hint(&backup.json).as_deref(), Some("my old chat handle"));
+        assert!(!backup.json.contains("hint_player"));
+        
+        localStorage::reset_all_storage();
+        merge_import_data_with(&backup.json, &KeyOptions::with_passphrase("hunter2")).expect("Backup with a hint should import");
+        
+        let result = export_backup_with(Some("hunter2"), Some("It's HUNTER2"));
+        assert!(matches!(result, Err(DataError::HintRevealsPassphrase)), "{:?}", result);
+        
+        let plain = export_backup_with(None, Some("ignored")).unwrap();
+        assert_eq!(backup_hint(&plain.json), None, "Hints only go with passphrase backups");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_corrupt_and_tampered_backups_read_differently() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "message_player");
+        let exported = export_data().expect("Export should succeed");
+        let envelope: Value = serde_json::from_str(&exported).unwrap();
+        localStorage::reset_all_storage();
+        
+        let mut corrupt = envelope.clone();
+        corrupt["data"]["iv"] = Value::String("AAAA".to_string());
+        let err = import_data(&corrupt.to_string()).unwrap_err();
+        assert!(err.to_string().starts_with("The file is corrupt"), "{}", err);
+        
+        let mut tampered = envelope;
+        let mut ciphertext = BASE64.decode(tampered["data"]["ciphertext"].as_str().unwrap()).unwrap();
+        ciphertext[0] ^= 0x01;
+        tampered["data"]["ciphertext"] = Value::String(BASE64.encode(&ciphertext));
+        let err = import_data(&tampered.to_string()).unwrap_err();
+        assert!(err.to_string().starts_with("The data was tampered with"), "{}", err);
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_merge_import_reports_corrupt_legacy_backup() {
+        localStorage::reset_all_storage();
+        let legacy = crate::crypto::encrypt_data(&json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "legacy_player" }
+        }).to_string()).expect("Encryption should succeed");
+        let mut corrupt: Value = serde_json::from_str(&legacy).unwrap();
+        let iv = BASE64.decode(corrupt["iv"].as_str().unwrap()).unwrap();
+        corrupt["iv"] = Value::String(BASE64.encode(&iv[..6]));
+
+        let err = merge_import_data(&corrupt.to_string()).unwrap_err();
+        assert!(err.to_string().starts_with("The file is corrupt"), "{}", err);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_merge_import_reports_tampered_legacy_backup() {
+        localStorage::reset_all_storage();
+        let legacy = crate::crypto::encrypt_data(&json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "legacy_player" }
+        }).to_string()).expect("Encryption should succeed");
+        let mut tampered: Value = serde_json::from_str(&legacy).unwrap();
+        let mut tag = BASE64.decode(tampered["tag"].as_str().unwrap()).unwrap();
+        tag[0] ^= 0x01;
+        tampered["tag"] = Value::String(BASE64.encode(&tag));
+
+        let err = merge_import_data(&tampered.to_string()).unwrap_err();
+        assert!(err.to_string().starts_with("The data was tampered with"), "{}", err);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_wrapped_errors_are_exposed_as_source() {
+        use std::error::Error;
+        
+        let err = DataError::from(CryptoError::DecryptionError("bad tag".to_string()));
+        let source = err.source().expect("Crypto errors should be chained");
+        assert!(matches!(source.downcast_ref::<CryptoError>(), Some(CryptoError::DecryptionError(msg)) if msg == "bad tag"));
+        
+        let err = DataError::from(StorageError::NotFound("player_id".to_string()));
+        let source = err.source().expect("Storage errors should be chained");
+        assert_eq!(source.to_string(), "Nothing stored under 'player_id'");
+        
+        assert!(DataError::MissingPlayerId.source().is_none());
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_impact_reflects_payload_size() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "impact_player");
+        
+        let backup_with = |notes: &str| json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-01-01T00:00:00Z",
+            "data": { "player_id": "impact_player", "dark_mode": true, "extra": { "notes": notes } },
+        }).to_string();
+        
+        let small = estimate_import_impact(&backup_with("hi")).unwrap();
+        assert_eq!(small.keys, 3);
+        assert_eq!(small.bytes, stored_size("player_id", "impact_player") + stored_size("dark_mode", "true") + stored_size("notes", "hi"));
+        assert!(!small.exceeds_quota);
+        
+        let notes = "n".repeat(10_000);
+        let large = estimate_import_impact(&backup_with(&notes)).unwrap();
+        assert_eq!(large.bytes - small.bytes, stored_size("", &notes) - stored_size("", "hi"));
+        assert_eq!(large.summary(), format!("This import will use ~{} KB", large.bytes.div_ceil(1024)));
+        
+        let too_big = estimate_import_impact(&backup_with(&"n".repeat(STORAGE_QUOTA_ESTIMATE / 2))).unwrap();
+        assert!(too_big.exceeds_quota, "A payload past the quota estimate should be flagged");
+        assert!(too_big.summary().contains("may not fit"));
+        assert_eq!(localStorage::get_storage_item("notes").unwrap(), None, "Estimating writes nothing");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_large_import_needs_typed_confirmation() {
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "small_player");
+        let small = export_backup().unwrap().json;
+        assert_eq!(import_severity(&small), Severity::Normal);
+        
+        // Synthetic backup with more friends than the threshold allows
+        let friends: Vec<Value> = (0..150)
+            .map(|index| json!({ "id": format!("friend-{}", index), "name": format!("Friend {}", index) }))
+            .collect();
+        let large = json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-01-01T00:00:00Z",
+            "data": { "player_id": "large_player", "extra": { "friends": Value::Array(friends).to_string() } },
+        }).to_string();
+        assert_eq!(import_severity(&large), Severity::High);
+        
+        // The threshold is configurable, and size alone is enough
+        set_large_import_threshold(LargeImportThreshold { max_bytes: 64, max_friends: 1000 });
+        assert_eq!(import_severity(&small), Severity::High);
+        reset_everything();
+        
+        let confirmed = RwSignal::new(false);
+        let on_confirm = Callback::new(move |_: ()| confirmed.set(true));
+        mount_to_body(move || view! {
+            <ThemeProvider>
+                <ConfirmModal
+                    message="Large import"
+                    test_id="large-import-test"
+                    severity=Severity::High
+                    on_confirm={on_confirm}
+                    on_cancel={Callback::new(|_: ()| {})}
+                />
+            </ThemeProvider>
+        });
+        
+        let confirm = get_by_test_id("large-import-test-confirm");
+        assert!(confirm.has_attribute("disabled"), "Confirming needs the phrase typed first");
+        click_and_wait(&confirm, 50).await;
+        assert!(!confirmed.get_untracked());
+        
+        let input = get_by_test_id("large-import-test-phrase-input").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        input.set_value(crate::confirm_modal::CONFIRM_PHRASE);
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        TimeoutFuture::new(50).await;
+        assert!(!confirm.has_attribute("disabled"));
+        click_and_wait(&confirm, 50).await;
+        assert!(confirmed.get_untracked(), "Typing the phrase should allow the import");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_flat_export_lists_keys_and_masks_player_id() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "secret_player_id");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let _ = localStorage::set_storage_item("locale", "fr-FR");
+        
+        let flat = export_flat();
+        let lines: Vec<&str> = flat.lines().collect();
+        
+        assert!(lines.contains(&"player_id=secr…"), "{}", flat);
+        assert!(!flat.contains("secret_player_id"), "Player ID should be masked");
+        assert!(lines.contains(&"dark_mode=true"), "{}", flat);
+        assert!(lines.contains(&"locale=fr-FR"), "{}", flat);
+        assert!(lines.contains(&"autosave=<unset>"), "Local-only keys should be listed too: {}", flat);
+        assert_eq!(lines.len(), list_storage_keys().len(), "One line per key");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_anonymized_export_replaces_identity_only() {
+        localStorage::reset_all_storage();
+        crate::profile::register_backup_keys();
+        crate::friends::register_backup_keys();
+        let _ = localStorage::set_storage_item("player_id", "real_player_id");
+        let _ = localStorage::set_storage_item("profile_name", "Ada Lovelace");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let _ = localStorage::set_storage_item("locale", "de-DE");
+        let _ = localStorage::set_storage_item("friends", "[]");
+        
+        let anonymized = export_data_with(true).expect("Anonymized export should succeed");
+        assert!(!anonymized.contains("real_player_id") && !anonymized.contains("Ada Lovelace"), "{}", anonymized);
+        
+        let read_data = |json: &str| {
+            let envelope: BackupEnvelope = serde_json::from_str(json).unwrap();
+            assert!(!envelope.encrypted, "Anonymized exports are readable in a bug report");
+            serde_json::from_value::<ExportedAppData>(envelope.data).unwrap()
+        };
+        let data = read_data(&anonymized);
+        let original = read_data(&export_backup_with_policy(None, None, EncryptionPolicy::Never).unwrap().json);
+        assert_eq!(data.player_id, ANONYMOUS_PLAYER_ID);
+        assert_eq!(data.extra.get("profile_name").map(String::as_str), Some(ANONYMOUS_NAME));
+        assert!(data.dark_mode);
+        assert_eq!(data.locale.as_deref(), Some("de-DE"));
+        assert_eq!(data.extra.keys().collect::<Vec<_>>(), original.extra.keys().collect::<Vec<_>>(), "Structure should be kept");
+        assert_eq!(data.extra.get("friends"), original.extra.get("friends"));
+        
+        // Imports into a throwaway state for reproducing the bug
+        localStorage::reset_all_storage();
+        import_data(&anonymized).expect("Anonymized export should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some(ANONYMOUS_PLAYER_ID.to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_split_export_metadata_is_readable_without_key() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "secret_player_id");
+        
+        let exported = export_data().expect("Export should succeed");
+        
+        // The envelope parses as plain JSON and exposes the version
+        let envelope: Value = serde_json::from_str(&exported).expect("Envelope should be plain JSON");
+        assert_eq!(envelope["version"], EXPORT_VERSION, "Version should be readable without decryption");
+        assert_eq!(envelope["format"], SPLIT_FORMAT);
+        assert_eq!(envelope["encrypted"], true);
+        assert!(envelope["data"].get("ciphertext").is_some(), "Data section should be encrypted");
+        
+        // ...but the player ID is not
+        assert!(!exported.contains("secret_player_id"), "Player ID should not appear in cleartext");
+        
+        // The backup still imports
+        localStorage::reset_all_storage();
+        import_data(&exported).expect("Split export should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("secret_player_id".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_export_timestamp_comes_from_clock() {
+        use chrono::TimeZone;
+        
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "clock_player");
+        clock::set_clock(clock::FixedClock(chrono::Utc.with_ymd_and_hms(2025, 4, 9, 12, 0, 0).unwrap()));
+        
+        let exported = export_data().expect("Export should succeed");
+        clock::reset_clock();
+        
+        let envelope: Value = serde_json::from_str(&exported).expect("Envelope should be plain JSON");
+        assert_eq!(envelope["timestamp"], "2025-04-09T12:00:00+00:00", "Timestamp should come from the fixed clock");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_registered_backup_key_is_exported() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "registry_player");
+        let _ = localStorage::set_storage_item("custom_setting", "custom_value");
+        let _ = localStorage::set_storage_item("scratch_setting", "scratch_value");
+        register_backup_key("custom_setting", true);
+        register_backup_key("scratch_setting", false);
+        
+        let exported = export_data().expect("Export should succeed");
+        let plain = unwrap_backup(&exported).expect("Backup should unwrap");
+        let data: ExportedData = serde_json::from_str(&plain).expect("Backup should parse");
+        assert_eq!(data.data.extra.get("custom_setting"), Some(&"custom_value".to_string()), "Registered key should be captured");
+        assert!(!data.data.extra.contains_key("scratch_setting"), "Keys not included by default should be skipped");
+        
+        // The captured key is restored on import
+        let _ = localStorage::reset_storage_item("custom_setting");
+        import_data(&exported).expect("Import should succeed");
+        assert_eq!(localStorage::get_storage_item("custom_setting").unwrap(), Some("custom_value".to_string()));
+        
+        let _ = localStorage::reset_storage_item("custom_setting");
+        let _ = localStorage::reset_storage_item("scratch_setting");
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_skips_unregistered_keys() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "guarded_player");
+        let _ = localStorage::set_storage_item(crate::friends::FRIENDS_KEY, "[]");
+        
+        // Module keys are known once their modules register them
+        crate::friends::register_backup_keys();
+        let exported = export_data().expect("Export should succeed");
+        let plain: ExportedData = serde_json::from_str(&unwrap_backup(&exported).unwrap()).unwrap();
+        assert_eq!(plain.data.extra.get(crate::friends::FRIENDS_KEY).map(String::as_str), Some("[]"));
+        
+        let crafted = json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": {
+                "player_id": "guarded_player",
+                "dark_mode": true,
+                "extra": {
+                    PASSPHRASE_REQUIRED_KEY: "false",
+                    crate::autosave::AUTOSAVE_KEY: "overwritten",
+                    "player_id": "smuggled_player",
+                    crate::friends::FRIENDS_KEY: "[]",
+                },
+            },
+        }).to_string();
+        import_data(&crafted).expect("Import should succeed");
+        merge_import_data(&crafted).expect("Merge should succeed");
+        
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()));
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("guarded_player".to_string()));
+        assert_eq!(localStorage::get_storage_item(PASSPHRASE_REQUIRED_KEY).unwrap(), None);
+        assert_eq!(localStorage::get_storage_item(crate::autosave::AUTOSAVE_KEY).unwrap(), None);
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_export_falls_back_to_plaintext_without_rng() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "no_rng_player");
+        crate::crypto::set_random_source(|_| Err("no RNG".to_string()));
+        
+        let backup = export_backup();
+        crate::crypto::reset_random_source();
+        let backup = backup.expect("Export should fall back instead of failing");
+        
+        assert!(backup.warning.is_some(), "Fallback export should carry a warning");
+        let envelope: Value = serde_json::from_str(&backup.json).expect("Envelope should be plain JSON");
+        assert_eq!(envelope["encrypted"], false);
+        assert_eq!(envelope["data"]["player_id"], "no_rng_player");
+        
+        // The plaintext backup still imports
+        localStorage::reset_all_storage();
+        import_data(&backup.json).expect("Plaintext backup should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("no_rng_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_encrypted_rejects_plaintext() {
+        localStorage::reset_all_storage();
+        let plain_backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "plain_player", "dark_mode": false }
+        }).to_string();
+        
+        let result = import_encrypted(&plain_backup, &KeyOptions::default());
+        assert!(matches!(result, Err(DataError::NotEncrypted)), "Plaintext should be rejected clearly: {:?}", result);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None, "Nothing should be imported");
+        
+        import_plain(&plain_backup).expect("import_plain should accept plaintext");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("plain_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_plain_rejects_encrypted() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "encrypted_player");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        let result = import_plain(&exported);
+        assert!(matches!(result, Err(DataError::EncryptedBackup)), "Encrypted backup should be rejected clearly: {:?}", result);
+        
+        // A wrong key is a decryption failure, not a format error
+        let wrong_key = KeyOptions { key: Some(Zeroizing::new([7u8; 32])), ..Default::default() };
+        let result = import_encrypted(&exported, &wrong_key);
+        assert!(matches!(result, Err(DataError::Crypto(_))), "Wrong key should fail to decrypt: {:?}", result);
+        
+        import_encrypted(&exported, &KeyOptions::default()).expect("The app key should decrypt");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("encrypted_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_export_is_bound_to_player_id() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "bound_player");
+        let exported = export_data().expect("Export should succeed");
+        
+        let envelope: Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(envelope["data"]["aad"], "bound_player", "The player ID should be the associated data");
+        
+        // Another player can't import it over their own data
+        let _ = localStorage::set_storage_item("player_id", "other_player");
+        let result = import_data(&exported);
+        assert!(matches!(&result, Err(DataError::OtherPlayer(bound)) if bound == "bound_player"), "{:?}", result);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("other_player".to_string()));
+        
+        // Unless they choose to restore it as a new profile
+        let restore = KeyOptions { restore_other_player: true, ..Default::default() };
+        import_encrypted(&exported, &restore).expect("Restoring as a new profile should be allowed");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("bound_player".to_string()));
+        
+        // Moving the data under another player's ID fails authentication
+        let mut relabelled = envelope.clone();
+        relabelled["data"]["aad"] = Value::String("other_player".to_string());
+        localStorage::reset_all_storage();
+        let result = import_data(&relabelled.to_string());
+        assert!(matches!(result, Err(DataError::Crypto(CryptoError::DecryptionError(_)))), "{:?}", result);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None, "Nothing should be imported");
+        
+        import_data(&exported).expect("The untouched backup should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("bound_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_corrupted_export_is_not_returned_for_download() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "verify_player");
+        
+        // Flip the ciphertext so it no longer decrypts
+        set_export_tamper_hook(Some(|json| {
+            let mut envelope: Value = serde_json::from_str(json).unwrap();
+            envelope["data"]["ciphertext"] = Value::String("AAAAAAAAAAAAAAAAAAAAAAAA".to_string());
+            envelope.to_string()
+        }));
+        let result = export_backup();
+        set_export_tamper_hook(None);
+        
+        assert!(
+            matches!(result, Err(DataError::VerificationFailed(_))),
+            "A backup that fails verification should abort the export: {:?}", result
+        );
+        assert!(export_backup().is_ok(), "Untampered exports should still verify");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_accepts_bom_and_crlf() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "bom_player");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        // As saved by an editor that adds a BOM and Windows line endings
+        let pretty: Value = serde_json::from_str(&exported).unwrap();
+        let edited = format!("\u{feff}{}", serde_json::to_string_pretty(&pretty).unwrap().replace('\n', "\r\n"));
+        
+        import_data(&edited).expect("Backup with a BOM should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("bom_player".to_string()));
+        
+        localStorage::reset_all_storage();
+        merge_import_data(&edited).expect("Merging a backup with a BOM should work too");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("bom_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_blank_import_is_empty_input() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "blank_player");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        for blank in ["", "   ", "\u{feff}\r\n\t"] {
+            let result = import_data(blank);
+            assert!(matches!(result, Err(DataError::EmptyInput)), "{:?}: {:?}", blank, result);
+            let result = merge_import_data(blank);
+            assert!(matches!(result, Err(DataError::EmptyInput)), "{:?}: {:?}", blank, result);
+        }
+        assert_eq!(DataError::EmptyInput.to_string(), "Nothing to import");
+        
+        import_data(&format!("\n  {}  \n", exported)).expect("Whitespace around a backup is fine");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("blank_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_reports_increasing_progress() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "progress_player");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        let reported = RwSignal::new(Vec::<f32>::new());
+        let on_progress = Callback::new(move |fraction: f32| reported.update(|values| values.push(fraction)));
+        
+        import_data_with_progress(&exported, Some(on_progress)).expect("Import should succeed");
+        
+        let values = reported.get_untracked();
+        assert!(values.len() >= 2, "Progress should be reported at several milestones: {:?}", values);
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "Progress should increase: {:?}", values);
+        assert_eq!(values.last(), Some(&1.0), "Progress should finish at 1.0");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_merge_import_reports_progress_between_stages() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "staged_player");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        let reported = RwSignal::new(Vec::<f32>::new());
+        let on_progress = Callback::new(move |fraction: f32| reported.update(|values| values.push(fraction)));
+        
+        merge_import_data_with_progress(&exported, &KeyOptions::default(), ImportMode::Overwrite, on_progress)
+            .await
+            .expect("Import should succeed");
+        
+        let values = reported.get_untracked();
+        assert_eq!(values.len(), 3, "Decrypt, validate and commit should each report: {:?}", values);
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "Progress should increase: {:?}", values);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("staged_player".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_importing_current_state_is_a_no_op() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "same_player");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let exported = export_data().expect("Export should succeed");
+        
+        let summary = import_data(&exported).expect("Import should succeed");
+        assert!(summary.no_op, "Importing the current state should be a no-op");
+        assert_eq!(summary.message, NO_OP_MESSAGE);
+        assert!(summary.changes.is_empty());
+        
+        // After a change the same backup does something again
+        let _ = localStorage::set_storage_item("dark_mode", "false");
+        let summary = import_data(&exported).expect("Import should succeed");
+        assert!(!summary.no_op);
+        assert_eq!(summary.changes.len(), 1, "Only the theme should change: {:?}", summary.changes);
+        
+        let merged = merge_import_data(&exported).unwrap();
+        assert!(merged.no_op, "Merging the same backup again changes nothing");
+        assert_eq!(merged.message, NO_OP_MESSAGE);
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_inline_data_controls_need_no_click() {
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton inline=true />
+            </ThemeProvider>
+        });
+        
+        let inline = crate::test_utils::get_by_test_id("data-inline");
+        let export_button = inline.query_selector("[data-test-id='export-data-button']").unwrap();
+        assert!(export_button.is_some(), "Export button should be present without opening anything");
+        assert!(
+            inline.query_selector("[data-test-id='data-close-button']").unwrap().is_none(),
+            "Inline layout should have nothing to close"
+        );
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_passphrase_setting_blocks_export_until_entered() {
+        use crate::test_utils::{click_and_wait, get_by_test_id};
+        
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "passphrase_player");
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        
+        click_and_wait(&get_by_test_id("passphrase-toggle"), 50).await;
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        assert!(document.query_selector("[data-test-id='passphrase-modal']").unwrap().is_some(), "Export should ask for a passphrase");
+        assert!(document.query_selector("[data-test-id='export-success-message']").unwrap().is_none(), "Nothing should be exported yet");
+        
+        let input = get_by_test_id("passphrase-input").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        input.set_value("shared machine secret");
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        click_and_wait(&get_by_test_id("passphrase-submit"), 50).await;
+        
+        assert!(document.query_selector("[data-test-id='passphrase-modal']").unwrap().is_none(), "Prompt should close");
+        assert!(get_by_test_id("export-success-message").inner_html().contains("Data exported"));
+        assert_eq!(
+            localStorage::get_storage_item("passphrase_required").unwrap(),
+            Some("true".to_string()),
+            "Only the setting is stored, never the passphrase"
+        );
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_always_encrypt_policy_hides_toggle_and_uses_host_key() {
+        use crate::crypto::{decrypt_payload_with_key, set_encryption_key, EncryptedData};
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "policy_player");
+        let host_key = [7u8; 32];
+        set_encryption_key(Some(host_key));
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true encryption=EncryptionPolicy::Always />
+            </ThemeProvider>
+        });
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        assert!(document.query_selector("[data-test-id='encrypt-export-toggle']").unwrap().is_none(), "The policy leaves nothing to choose");
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        assert!(get_by_test_id("export-success-message").inner_html().contains("Data exported"));
+        
+        let exported = export_backup_with_policy(None, None, EncryptionPolicy::Always).unwrap();
+        assert!(exported.encrypted);
+        assert!(!exported.json.contains("policy_player"));
+        let envelope: BackupEnvelope = serde_json::from_str(&exported.json).unwrap();
+        let payload: EncryptedData = serde_json::from_value(envelope.data).unwrap();
+        assert!(decrypt_payload_with_key(&payload, Some(&host_key)).unwrap().contains("policy_player"));
+        
+        set_encryption_key(None);
+        assert!(decrypt_payload_with_key(&payload, None).is_err(), "The built-in key must not open host-keyed backups");
+        
+        reset_everything();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_never_encrypt_policy_refuses_passphrase() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "plain_player");
+        
+        let exported = export_backup_with_policy(None, None, EncryptionPolicy::Never).unwrap();
+        assert!(!exported.encrypted);
+        assert!(exported.warning.is_none(), "Plaintext was asked for, so there's nothing to warn about");
+        assert!(matches!(
+            export_backup_with_policy(Some("secret"), None, EncryptionPolicy::Never),
+            Err(DataError::PolicyViolation(_))
+        ));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_idle_session_asks_for_passphrase_again() {
+        use crate::clock::{set_clock, FixedClock};
+        use crate::session_lock::{auto_lock_after, lock_if_idle, DEFAULT_AUTO_LOCK_AFTER};
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        use chrono::TimeZone;
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "locking_player");
+        let start = chrono::Utc.with_ymd_and_hms(2025, 4, 9, 12, 0, 0).unwrap();
+        set_clock(FixedClock(start));
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        click_and_wait(&get_by_test_id("passphrase-toggle"), 50).await;
+        click_and_wait(&get_by_test_id("auto-lock-toggle"), 50).await;
+        assert_eq!(auto_lock_after(), Some(DEFAULT_AUTO_LOCK_AFTER), "The toggle should store the opt-in");
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        let prompted = || document.query_selector("[data-test-id='passphrase-modal']").unwrap().is_some();
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        assert!(prompted(), "The first export should ask for a passphrase");
+        let input = get_by_test_id("passphrase-input").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        input.set_value("session secret");
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        click_and_wait(&get_by_test_id("passphrase-submit"), 50).await;
+        
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        assert!(!prompted(), "The unlocked session should reuse its key");
+        
+        set_clock(FixedClock(start + chrono::Duration::minutes(6)));
+        assert!(lock_if_idle(), "Six idle minutes should lock the session");
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        assert!(prompted(), "A locked session should ask again");
+        
+        reset_everything();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_dropped_text_is_previewed_before_import() {
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "dropped_player");
+        let exported = export_data().expect("Export should succeed");
+        let _ = localStorage::set_storage_item("player_id", "current_player");
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        
+        // The backup is another player's
+        click_and_wait(&get_by_test_id("restore-other-player-toggle"), 0).await;
+        let transfer = web_sys::DataTransfer::new().unwrap();
+        transfer.set_data("text/plain", &exported).unwrap();
+        let init = web_sys::DragEventInit::new();
+        init.set_bubbles(true);
+        init.set_cancelable(true);
+        init.set_data_transfer(Some(&transfer));
+        let drop = web_sys::DragEvent::new_with_event_init_dict("drop", &init).unwrap();
+        get_by_test_id("data-drop-zone").dispatch_event(&drop).unwrap();
+        TimeoutFuture::new(50).await;
+        
+        assert!(drop.default_prevented(), "The browser shouldn't open the dropped text itself");
+        let preview = get_by_test_id("import-preview");
+        assert!(preview.inner_html().contains("current_player → dropped_player"), "{}", preview.inner_html());
+        assert_eq!(
+            get_by_test_id("import-preview-player-id").text_content().unwrap().trim(),
+            "Player ID: dropped_player (now current_player)"
+        );
+        assert_eq!(
+            localStorage::get_storage_item("player_id").unwrap(),
+            Some("current_player".to_string()),
+            "Nothing is written until the preview is confirmed"
+        );
+        
+        click_and_wait(&get_by_test_id("import-preview-confirm"), 100).await;
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("dropped_player".to_string()));
+        
+        reset_everything();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_batch_results_list_files_sharing_a_name() {
+        use crate::test_utils::{get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "batch_name_player");
+        let exported = export_data().expect("Export should succeed");
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        
+        // Two files called the same, from different folders
+        let transfer = web_sys::DataTransfer::new().unwrap();
+        for contents in [exported.as_str(), "not a backup"] {
+            let file = text_file("backup.json", contents).unwrap();
+            transfer.items().add_with_file(&file).unwrap();
+        }
+        let init = web_sys::DragEventInit::new();
+        init.set_bubbles(true);
+        init.set_cancelable(true);
+        init.set_data_transfer(Some(&transfer));
+        let drop = web_sys::DragEvent::new_with_event_init_dict("drop", &init).unwrap();
+        get_by_test_id("data-drop-zone").dispatch_event(&drop).unwrap();
+        TimeoutFuture::new(200).await;
+        
+        let rows = get_by_test_id("import-file-results").query_selector_all("[data-test-id='import-file-result']").unwrap();
+        assert_eq!(rows.length(), 2, "Each file should get its own row even when names repeat");
+        
+        reset_everything();
+    }
+    
+    thread_local! {
+        static DOWNLOADED_BACKUP: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_encrypted_export_toggle_round_trips_through_ui() {
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "toggle_player");
+        // Capture what the export button hands to the download, unchanged
+        set_export_tamper_hook(Some(|json| {
+            DOWNLOADED_BACKUP.with(|downloaded| *downloaded.borrow_mut() = Some(json.to_string()));
+            json.to_string()
+        }));
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        
+        let toggle = get_by_test_id("encrypt-export-toggle").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        assert!(toggle.checked(), "Exports are encrypted unless the toggle is cleared");
+        click_and_wait(&get_by_test_id("export-data-button"), 50).await;
+        set_export_tamper_hook(None);
+        
+        let downloaded = DOWNLOADED_BACKUP.with(|downloaded| downloaded.borrow_mut().take()).expect("Export should produce a download");
+        let envelope: Value = serde_json::from_str(&downloaded).unwrap();
+        assert_eq!(envelope["encrypted"], true);
+        assert!(!downloaded.contains("toggle_player"), "The player ID shouldn't be readable in the file");
+        
+        // Import the downloaded file into a different profile
+        let _ = localStorage::set_storage_item("player_id", "other_player");
+        click_and_wait(&get_by_test_id("restore-other-player-toggle"), 0).await;
+        let transfer = web_sys::DataTransfer::new().unwrap();
+        transfer.set_data("text/plain", &downloaded).unwrap();
+        let init = web_sys::DragEventInit::new();
+        init.set_bubbles(true);
+        init.set_cancelable(true);
+        init.set_data_transfer(Some(&transfer));
+        let drop = web_sys::DragEvent::new_with_event_init_dict("drop", &init).unwrap();
+        get_by_test_id("data-drop-zone").dispatch_event(&drop).unwrap();
+        TimeoutFuture::new(50).await;
+        click_and_wait(&get_by_test_id("import-preview-confirm"), 100).await;
+        
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("toggle_player".to_string()));
+        
+        reset_everything();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_import_mode_select_keeps_local_player_id() {
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        
+        reset_everything();
+        let _ = localStorage::set_storage_item("player_id", "backup_player");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let exported = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "local_player");
+        
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <DataButton initially_open=true />
+            </ThemeProvider>
+        });
+        
+        let select = get_by_test_id("import-mode-select").dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+        assert_eq!(select.value(), ImportMode::Overwrite.as_str(), "Imports replace data unless told otherwise");
+        select.set_value(ImportMode::MergeKeepLocal.as_str());
+        select.dispatch_event(&web_sys::Event::new("change").unwrap()).unwrap();
+        
+        // The backup is another player's
+        click_and_wait(&get_by_test_id("restore-other-player-toggle"), 0).await;
+        let transfer = web_sys::DataTransfer::new().unwrap();
+        transfer.set_data("text/plain", &exported).unwrap();
+        let init = web_sys::DragEventInit::new();
+        init.set_bubbles(true);
+        init.set_cancelable(true);
+        init.set_data_transfer(Some(&transfer));
+        let drop = web_sys::DragEvent::new_with_event_init_dict("drop", &init).unwrap();
+        get_by_test_id("data-drop-zone").dispatch_event(&drop).unwrap();
+        TimeoutFuture::new(50).await;
+        
+        let changes = get_by_test_id("import-changes");
+        assert!(!changes.inner_html().contains("backup_player"), "The kept player ID isn't a change: {}", changes.inner_html());
+        click_and_wait(&get_by_test_id("import-preview-confirm"), 100).await;
+        
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("local_player".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()), "Missing keys are filled in");
+        
+        reset_everything();
+    }
+    
+    fn text_file(name: &str, contents: &str) -> Option<web_sys::File> {
+        let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+        web_sys::File::new_with_str_sequence(&parts, name).ok()
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_sequential_file_batches_complete_once_each() {
+        let completed: Rc<RefCell<Vec<Vec<String>>>> = Rc::new(RefCell::new(Vec::new()));
+        let record = |completed: &Rc<RefCell<Vec<Vec<String>>>>| {
+            let completed = completed.clone();
+            move |entries: Vec<(String, Result<String, DataError>)>| {
+                completed.borrow_mut().push(entries.into_iter().map(|(_, result)| result.unwrap()).collect());
+            }
+        };
+        
+        // Several loads in a row, each batch dropped before the next like the data panel does
+        for round in 0..3 {
+            let _batch = FileReadBatch::start(
+                vec![text_file("a.json", &format!("a{}", round)), text_file("b.json", &format!("b{}", round))],
+                record(&completed),
+            );
+            TimeoutFuture::new(50).await;
+        }
+        
+        assert_eq!(
+            *completed.borrow(),
+            vec![vec!["a0", "b0"], vec!["a1", "b1"], vec!["a2", "b2"]],
+            "Each batch should be applied exactly once, in selection order"
+        );
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_superseded_file_batch_never_completes() {
+        let completed = Rc::new(Cell::new(0));
+        let count = |completed: &Rc<Cell<u32>>| {
+            let completed = completed.clone();
+            move |_: Vec<(String, Result<String, DataError>)>| completed.set(completed.get() + 1)
+        };
+        
+        let first = FileReadBatch::start(vec![text_file("old.json", "old")], count(&completed));
+        // A new load starts before the first read has finished
+        drop(first);
+        let second = FileReadBatch::start(vec![text_file("new.json", "new")], count(&completed));
+        
+        TimeoutFuture::new(100).await;
+        assert_eq!(completed.get(), 1, "Only the latest batch should be applied");
+        drop(second);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_backup_format_detection() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "format_player");
+        let encrypted = export_data().expect("Export should succeed");
+        localStorage::reset_all_storage();
+        
+        let plain = json!({
+            "version": EXPORT_VERSION,
+            "timestamp": "2025-01-01T00:00:00Z",
+            "data": { "player_id": "format_player", "dark_mode": false }
+        }).to_string();
+        let legacy_encrypted = crate::crypto::encrypt_data(&plain).unwrap();
+        
+        assert_eq!(BackupFormat::detect(&plain), BackupFormat::Plain);
+        assert_eq!(BackupFormat::detect(&encrypted), BackupFormat::Encrypted);
+        assert_eq!(BackupFormat::detect(&legacy_encrypted), BackupFormat::Encrypted);
+        assert_eq!(BackupFormat::detect("just some random text"), BackupFormat::Unknown);
+        assert_eq!(BackupFormat::detect(r#"{"unrelated": true}"#), BackupFormat::Unknown);
+        assert_eq!(BackupFormat::detect("PK\u{3}\u{4}\u{14}\u{0}archive"), BackupFormat::Binary);
+        assert_eq!(BackupFormat::detect("\u{fffd}\u{fffd}PNG"), BackupFormat::Binary);
+        // serde_json leaves DEL and C1 controls unescaped inside strings
+        assert_eq!(BackupFormat::detect(&plain.replace("format_player", "format\u{7f}\u{85}\u{fffd}")), BackupFormat::Plain);
+        
+        assert_eq!(BackupFormat::try_from(plain.as_str()).ok(), Some(BackupFormat::Plain));
+        assert!(matches!(BackupFormat::try_from("random text"), Err(DataError::InvalidFormat(_))));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_backup_filename_format() {
+        use chrono::TimeZone;
+        let fixed = chrono::Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        clock::set_clock(clock::FixedClock(fixed));
+        let stamp = fixed.with_timezone(&chrono::Local).format("%Y%m%d_%H%M%S").to_string();
+        
+        assert_eq!(backup_filename(None, true, "1"), format!("fc_v1_enc_{}.enc.json", stamp));
+        assert_eq!(backup_filename(None, false, EXPORT_VERSION), format!("fc_v{}_plain_{}.json", EXPORT_VERSION, stamp));
+        assert_eq!(backup_filename(Some("friends"), true, "1"), format!("fc_friends_v1_enc_{}.enc.json", stamp));
+        
+        clock::reset_clock();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_crypto_errors_name_the_failing_operation() {
+        let encryption = DataError::from(CryptoError::EncryptionError("cipher unavailable".to_string())).to_string();
+        assert!(encryption.starts_with("Encryption error"), "Encryption failures aren't decryption failures: {}", encryption);
+        assert!(!encryption.contains("decrypt"), "{}", encryption);
+        
+        let key = DataError::from(CryptoError::KeyError("bad length".to_string())).to_string();
+        assert!(key.starts_with("Key error"), "{}", key);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_download_without_body_reports_no_body() {
+        // A bare document has no <body>, like a sandboxed iframe that hasn't loaded yet
+        let document = Document::new().expect("Document should be created");
+        assert!(document.body().is_none());
+        
+        let result = trigger_download_in(&document, "{}", "backup.json", "application/json");
+        assert_eq!(result, Err(DownloadError::NoBody), "Missing body should be reported specifically");
+        assert!(DataError::from(DownloadError::NoBody).to_string().contains("finished loading"));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_download_link_failure_is_reported_as_link() {
+        // In an XML document "a" isn't an HTML anchor, so the link can't be made
+        const XHTML: &str = "http://www.w3.org/1999/xhtml";
+        let document = Document::new().expect("Document should be created");
+        let html = document.create_element_ns(Some(XHTML), "html").unwrap();
+        html.append_child(&document.create_element_ns(Some(XHTML), "body").unwrap()).unwrap();
+        document.append_child(&html).unwrap();
+        assert!(document.body().is_some());
+        
+        let result = trigger_download_in(&document, "{}", "backup.json", "application/json");
+        assert!(matches!(result, Err(DownloadError::Link(_))), "{:?}", result);
+        assert!(result.unwrap_err().to_string().contains("blocked the download link"));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_download_errors_name_their_step() {
+        let messages = [
+            DownloadError::NoDocument,
+            DownloadError::Blob("x".to_string()),
+            DownloadError::ObjectUrl("x".to_string()),
+            DownloadError::Link("x".to_string()),
+            DownloadError::Revoke("x".to_string()),
+        ].map(|err| err.to_string());
+        assert!(messages[2].contains("blocked the download"));
+        for (i, message) in messages.iter().enumerate() {
+            assert!(messages[i + 1..].iter().all(|other| other != message), "Each step needs its own message: {}", message);
+        }
+        
+        let err = DataError::from(DownloadError::ObjectUrl("denied".to_string()));
+        assert!(std::error::Error::source(&err).is_some(), "The download step should be the source");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_stalled_operation_times_out_and_clears_busy() {
+        let owner = Owner::new();
+        let status = owner.with(provide_data_status);
+        
+        status.begin();
+        let stalled = async {
+            TimeoutFuture::new(500).await;
+            "finished"
+        };
+        let result = with_timeout(stalled, Duration::from_millis(50)).await;
+        status.finish(&result);
+        
+        assert!(matches!(result, Err(DataError::Timeout(_))), "Stalled operation should time out: {:?}", result);
+        assert!(!status.is_busy.get_untracked(), "Busy state should clear after a timeout");
+        assert!(matches!(status.last_error.get_untracked(), Some(DataError::Timeout(_))));
+        
+        // Operations that finish in time pass their output through
+        let quick = with_timeout(async { 42 }, Duration::from_millis(50)).await;
+        assert_eq!(quick.ok(), Some(42));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_diff_state_lists_changed_fields() {
+        let current = AppState {
+            player_id: "current_player".to_string(),
+            dark_mode: false,
+            locale: Some("en-US".to_string()),
+            extra: BTreeMap::new(),
+        };
+        let incoming = AppState {
+            player_id: "incoming_player".to_string(),
+            dark_mode: true,
+            locale: None,
+            extra: BTreeMap::new(),
+        };
+        
+        let changes = diff_state(&current, &incoming);
+        
+        assert_eq!(changes.len(), 3, "All three fields differ: {:?}", changes);
+        assert_eq!(changes[0].field, "player_id");
+        assert_eq!(changes[0].kind, ChangeKind::Modified);
+        assert_eq!(changes[0].describe(), "Player ID: current_player → incoming_player");
+        assert_eq!(changes[1].field, "theme");
+        assert_eq!(changes[1].describe(), "Theme: light → dark");
+        assert_eq!(changes[2].field, "locale");
+        assert_eq!(changes[2].kind, ChangeKind::Removed);
+        
+        // Identical states produce no changes
+        assert!(diff_state(&current, &current).is_empty(), "Identical states should not differ");
+        
+        // A locale only present in the incoming state is an addition
+        let with_locale = AppState { locale: Some("fr-FR".to_string()), ..incoming.clone() };
+        let changes = diff_state(&incoming, &with_locale);
+        assert_eq!(changes, vec![FieldChange {
+            field: "locale",
+            label: "Language",
+            kind: ChangeKind::Added,
+            current: None,
+            incoming: Some("fr-FR".to_string()),
+        }]);
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_import_changes_renders_each_change() {
+        let current = AppState { player_id: "a".to_string(), dark_mode: false, locale: None, extra: BTreeMap::new() };
+        let incoming = AppState { player_id: "b".to_string(), dark_mode: true, locale: None, extra: BTreeMap::new() };
+        let changes = diff_state(&current, &incoming);
+        
+        mount_to_body(move || view! { <ImportChanges changes={changes} /> });
+        
+        let player_change = crate::test_utils::get_by_test_id("import-change-player_id");
+        assert_eq!(player_change.text_content().unwrap(), "Player ID: a → b");
+        let theme_change = crate::test_utils::get_by_test_id("import-change-theme");
+        assert_eq!(theme_change.text_content().unwrap(), "Theme: light → dark");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_data_status_tracks_in_flight_operation() {
+        let captured_status: Rc<RefCell<Option<DataStatus>>> = Rc::new(RefCell::new(None));
+        let capture = captured_status.clone();
+        
+        // A host provides the status above the data controls and observes it
+        #[component]
+        fn StatusHost(capture: Rc<RefCell<Option<DataStatus>>>) -> impl IntoView {
+            let status = provide_data_status();
+            *capture.borrow_mut() = Some(status);
+            view! {
+                <p data-test-id="host-busy-indicator">
+                    {move || if status.is_busy.get() { "busy" } else { "idle" }}
+                </p>
+            }
+        }
+        
+        mount_to_body(move || view! { <StatusHost capture={capture} /> });
+        
+        let status = (*captured_status.borrow()).expect("Status should be captured");
+        assert!(!status.is_busy.get_untracked(), "Status should start idle");
+        
+        // Start an operation and observe it while it's in flight
+        status.begin();
+        TimeoutFuture::new(50).await;
+        assert!(status.is_busy.get_untracked(), "Status should be busy during the operation");
+        let indicator = crate::test_utils::get_by_test_id("host-busy-indicator");
+        assert_eq!(indicator.text_content().unwrap(), "busy", "Host UI should follow the busy signal");
+        
+        // Finishing with an error clears busy and records the error
+        status.finish(&Err::<(), _>(DataError::MissingPlayerId));
+        TimeoutFuture::new(50).await;
+        assert!(!status.is_busy.get_untracked(), "Status should be idle after the operation");
+        assert!(
+            matches!(status.last_error.get_untracked(), Some(DataError::MissingPlayerId)),
+            "The operation's error should be exposed"
+        );
+        
+        // The next operation clears the previous error
+        status.begin();
+        assert!(status.last_error.get_untracked().is_none(), "Starting an operation should clear the last error");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_mode_values_round_trip() {
+        for mode in ImportMode::ALL {
+            assert_eq!(mode.as_str().parse::<ImportMode>(), Ok(mode), "{} should parse back", mode.as_str());
+        }
+        assert_eq!("merge".parse::<ImportMode>(), Err(ImportModeParseError("merge".to_string())));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_batch_keeps_local_values_when_asked() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "local_player");
+        
+        let first = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "imported_player_1" }
+        }).to_string();
+        let second = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-10T12:00:00Z",
+            "data": { "player_id": "imported_player_2", "dark_mode": true }
+        }).to_string();
+        
+        let results = import_batch(&[
+            ("first.json".to_string(), first),
+            ("second.json".to_string(), second),
+        ], &KeyOptions::default(), ImportMode::MergeKeepLocal);
+        
+        assert!(results.iter().all(|r| r.result.is_ok()), "Both files should import: {:?}", results);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("local_player".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()), "Missing values should still be filled in");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_batch_reports_failures_per_file() {
+        localStorage::reset_all_storage();
+        
+        let valid_backup = json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "batch_player_2", "dark_mode": false }
+        }).to_string();
+        
+        let results = import_batch(&[
+            ("broken.json".to_string(), "not a backup".to_string()),
+            ("valid.json".to_string(), valid_backup),
+        ], &KeyOptions::default(), ImportMode::Overwrite);
+        
+        assert!(results[0].result.is_err(), "Broken file should fail");
+        assert!(results[1].result.is_ok(), "A failed file should not stop the rest of the batch");
+        assert_eq!(batch_summary(&results), "Imported 1 of 2 files");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("batch_player_2".to_string()));
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_import_validates_encrypted_backups() {
+        localStorage::reset_all_storage();
+        let encrypted = crate::crypto::encrypt_data(&json!({
+            "version": "0.1.0",
+            "timestamp": "2025-04-09T12:00:00Z",
+            "data": { "player_id": "test_crypto_id_456", "dark_mode": true }
+        }).to_string()).expect("Encryption should succeed");
+        
+        import_data(&encrypted).expect("Import should succeed with properly encrypted data");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("test_crypto_id_456".to_string()));
+        
+        // Tampered and forged payloads should fail with an error, not a panic or garbage data
+        let tampered = import_data(&encrypted.replace('A', "B")).expect_err("Tampered data should be rejected");
+        let forged = import_data(&json!({
+            "ciphertext": "ABCDEF1234567890",
+            "iv": "0123456789ABCDEF",
+            "tag": "INVALID0987654321"
+        }).to_string()).expect_err("Forged data should be rejected");
+        for err in [tampered, forged] {
+            assert!(
+                matches!(err, DataError::Crypto(_) | DataError::Parse(_) | DataError::InvalidFormat(_)),
+                "Should fail validation: {:?}", err
+            );
+        }
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_export_is_an_envelope_around_the_app_data() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "envelope_player");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        
+        let exported = export_data().expect("Export should succeed");
+        let envelope: BackupEnvelope = serde_json::from_str(&exported).expect("Export should be an envelope");
+        assert_eq!(envelope.format, SPLIT_FORMAT);
+        assert!(!envelope.version.is_empty() && !envelope.timestamp.is_empty(), "Metadata should be readable without the key");
+        if envelope.encrypted {
+            assert!(envelope.data.get("ciphertext").is_some(), "Encrypted section should include ciphertext");
+            assert!(envelope.data.get("iv").is_some(), "Encrypted section should include iv");
+        }
+        
+        let unwrapped: Value = serde_json::from_str(&unwrap_backup(&exported).expect("Backup should unwrap")).unwrap();
+        assert!(unwrapped["version"].as_str().is_some_and(|version| version.contains('.')), "Version should be semantic");
+        assert_eq!(unwrapped["data"]["player_id"], "envelope_player", "Exported data should match storage");
+        assert_eq!(unwrapped["data"]["dark_mode"], true);
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_export_without_player_id_is_a_data_error() {
+        localStorage::reset_all_storage();
+        
+        let err = export_data().expect_err("Export needs a player ID");
+        assert!(matches!(err, DataError::MissingPlayerId), "{:?}", err);
+        assert!(err.to_string().contains("player ID"), "{}", err);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_profile_bundle_restores_each_profile() {
+        localStorage::reset_all_storage();
+        crate::profile::register_backup_keys();
+        let _ = localStorage::set_storage_item("player_id", "bundle_player_1");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let _ = localStorage::set_storage_item(crate::profile::PROFILE_NAME_KEY, "First");
+
+        // A second profile alongside the device's own
+        let mut bundle: ProfileBundle = serde_json::from_str(&export_all_profiles().expect("Export should succeed")).unwrap();
+        assert_eq!(bundle.profiles.len(), 1, "The device keeps one profile");
+        let mut second = bundle.profiles[0].clone();
+        second.player_id = "bundle_player_2".to_string();
+        second.extra.insert(crate::profile::PROFILE_NAME_KEY.to_string(), "Second".to_string());
+        bundle.profiles.push(second);
+        let bundle_json = serde_json::to_string(&bundle).unwrap();
+
+        localStorage::reset_all_storage();
+        let results = import_all_profiles(&bundle_json, ImportMode::Overwrite).expect("Bundle should parse");
+        assert_eq!(results.len(), 2, "One result per profile");
+        assert!(results[0].result.is_ok(), "{:?}", results[0].result);
+        assert!(matches!(&results[1].result, Err(DataError::OtherPlayer(id)) if id == "bundle_player_2"), "{:?}", results[1].result);
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("bundle_player_1".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()));
+        assert_eq!(localStorage::get_storage_item(crate::profile::PROFILE_NAME_KEY).unwrap(), Some("First".to_string()));
+
+        // With the second player stored, its profile is the one restored
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "bundle_player_2");
+        let _ = localStorage::set_storage_item(crate::profile::PROFILE_NAME_KEY, "Renamed");
+        let results = import_all_profiles(&bundle_json, ImportMode::MergeKeepLocal).expect("Bundle should parse");
+        assert!(results[0].result.is_err() && results[1].result.is_ok(), "{:?}", results);
+        assert_eq!(localStorage::get_storage_item(crate::profile::PROFILE_NAME_KEY).unwrap(), Some("Renamed".to_string()), "The mode applies per profile");
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()), "Missing values are filled in");
+
+        localStorage::reset_all_storage();
+    }
+}
@@ -0,0 +1,178 @@
+use leptos::prelude::*;
+use log::info;
+use crate::data::{
+    DataError,
+    ExportedBackup,
+    RecoveryKey,
+    AppState,
+    KeyOptions,
+    export_backup,
+    export_backup_with_policy,
+    export_backup_with_key,
+    EncryptionPolicy,
+    merge_import_data,
+    merge_import_data_with_progress,
+    ImportMode,
+    ImportSummary,
+    current_app_state,
+    registered_backup_keys,
+};
+use crate::crypto::PassphraseKey;
+use crate::profile::{save_profile_name, validate_profile_name};
+use crate::utils::{generate_player_id, remove_storage_item, set_storage_item};
+
+/// Data operations shared by the data panel and any other trigger
+/// (menu items, keyboard shortcuts), independent of the UI.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DataService;
+
+impl DataService {
+    /// Produce a verified backup of everything the app stores
+    pub fn export(&self) -> Result<ExportedBackup, DataError> {
+        export_backup()
+    }
+
+    /// Produce a verified backup under an encryption policy, protected by
+    /// `passphrase` when one is given
+    pub fn export_with_policy(&self, passphrase: Option<&str>, hint: Option<&str>, policy: EncryptionPolicy) -> Result<ExportedBackup, DataError> {
+        export_backup_with_policy(passphrase, hint, policy)
+    }
+
+    /// Export with a passphrase key derived earlier in the session
+    pub fn export_with_key(&self, passphrase_key: &PassphraseKey, recovery: Option<&RecoveryKey>, hint: Option<&str>, policy: EncryptionPolicy) -> Result<ExportedBackup, DataError> {
+        export_backup_with_key(passphrase_key, recovery, hint, policy)
+    }
+
+    /// Merge a backup into storage
+    pub fn import(&self, json_data: &str) -> Result<ImportSummary, DataError> {
+        merge_import_data(json_data)
+    }
+
+    /// Merge a backup under `mode`, reporting progress and yielding between stages
+    pub async fn import_with_progress(&self, json_data: &str, key_options: &KeyOptions, mode: ImportMode, on_progress: Callback<f32>) -> Result<ImportSummary, DataError> {
+        merge_import_data_with_progress(json_data, key_options, mode, on_progress).await
+    }
+
+    /// Remove every registered backup key from storage
+    pub fn clear(&self) -> Result<(), DataError> {
+        for backup_key in registered_backup_keys() {
+            remove_storage_item(&backup_key.key)?;
+        }
+        info!("Cleared all stored data");
+        Ok(())
+    }
+
+    /// Replace the player ID with a new one, returning it
+    pub fn regenerate_id(&self) -> Result<String, DataError> {
+        let new_id = generate_player_id();
+        set_storage_item("player_id", &new_id)?;
+        info!("Generated new player ID: {}", new_id);
+        Ok(new_id)
+    }
+
+    /// Rename the player, keeping the player ID. Saving notifies storage
+    /// subscribers, so everything showing the name picks it up.
+    pub fn set_name(&self, name: &str) -> Result<(), DataError> {
+        let name = validate_profile_name(name).map_err(DataError::InvalidName)?;
+        save_profile_name(&name)?;
+        info!("Renamed player to {}", name);
+        Ok(())
+    }
+
+    /// Snapshot of the stored state
+    pub fn current_state(&self) -> AppState {
+        current_app_state()
+    }
+}
+
+// Provide the data service to this part of the tree
+pub fn provide_data_service() -> DataService {
+    let service = DataService;
+    provide_context(service);
+    service
+}
+
+// Helper to get the data service, falling back to the default one
+pub fn use_data_service() -> DataService {
+    use_context::<DataService>().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::localStorage;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_export_then_import_restores_state() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "service_player");
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        let service = DataService;
+
+        let backup = service.export().expect("Export should succeed");
+        service.clear().expect("Clear should succeed");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), None, "Clear should remove the player ID");
+
+        service.import(&backup.json).expect("Import should succeed");
+        let state = service.current_state();
+        assert_eq!(state.player_id, "service_player");
+        assert!(state.dark_mode);
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_regenerate_id_replaces_player_id() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "old_player");
+
+        let new_id = DataService.regenerate_id().expect("Regenerating should succeed");
+
+        assert_ne!(new_id, "old_player");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some(new_id));
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_set_name_updates_editor_and_keeps_id() {
+        use crate::profile::{load_profile_name, ProfileNameEditor, ProfileNameError};
+        use crate::theme::ThemeProvider;
+        use crate::test_utils::get_by_test_id;
+        use gloo_timers::future::TimeoutFuture;
+        use wasm_bindgen::JsCast;
+
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "named_player");
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <ProfileNameEditor />
+            </ThemeProvider>
+        });
+
+        DataService.set_name("  Ada ").expect("Renaming should succeed");
+        TimeoutFuture::new(50).await;
+
+        let input = get_by_test_id("profile-name-input").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        assert_eq!(input.value(), "Ada", "The editor should show the new name");
+        assert_eq!(load_profile_name(), "Ada");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("named_player".to_string()), "Renaming keeps the ID");
+
+        let result = DataService.set_name("   ");
+        assert!(matches!(result, Err(DataError::InvalidName(ProfileNameError::Empty))), "{:?}", result);
+        assert_eq!(load_profile_name(), "Ada");
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_export_without_player_id_fails() {
+        localStorage::reset_all_storage();
+
+        let result = DataService.export();
+        assert!(matches!(result, Err(DataError::MissingPlayerId)), "Export should need a player ID: {:?}", result);
+    }
+}
@@ -0,0 +1,98 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::theme::Theme;
+
+/// Significant things that happen in the app, for components that react
+/// to them without holding references to the component that caused them
+#[derive(Clone, Debug, PartialEq)]
+pub enum AppEvent {
+    /// A backup was imported; carries the import summary
+    ImportCompleted(String),
+    /// The active theme changed
+    ThemeChanged(Theme),
+    /// A friend was added; carries their player ID
+    FriendAdded(String),
+    /// An operation failed; carries the message shown to the user
+    ErrorReported(String),
+}
+
+/// Passes `AppEvent`s from the code that emits them to every subscriber
+#[derive(Copy, Clone)]
+pub struct EventBus {
+    subscribers: StoredValue<Vec<(usize, Callback<AppEvent>)>>,
+    next_id: StoredValue<usize>,
+}
+
+impl EventBus {
+    /// Deliver `event` to every subscriber, in the order they subscribed
+    pub fn emit(&self, event: AppEvent) {
+        // Copy the list so subscribers can subscribe or unsubscribe while handling
+        let subscribers = self.subscribers.get_value();
+        for (_, callback) in subscribers {
+            callback.run(event.clone());
+        }
+    }
+
+    /// Call `callback` with every event emitted from now on.
+    /// Returns an id for `unsubscribe`.
+    pub fn subscribe(&self, callback: Callback<AppEvent>) -> usize {
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        self.subscribers.update_value(|subscribers| subscribers.push((id, callback)));
+        id
+    }
+
+    /// Stop delivering events to the subscriber `id`
+    pub fn unsubscribe(&self, id: usize) {
+        self.subscribers.update_value(|subscribers| subscribers.retain(|(subscriber, _)| *subscriber != id));
+    }
+}
+
+// Provide an event bus to this part of the tree
+pub fn provide_event_bus() -> EventBus {
+    let bus = EventBus {
+        subscribers: StoredValue::new(Vec::new()),
+        next_id: StoredValue::new(0),
+    };
+    provide_context(bus);
+    bus
+}
+
+// Helper to get the event bus, creating one if none is provided
+pub fn use_event_bus() -> EventBus {
+    use_context::<EventBus>().unwrap_or_else(provide_event_bus)
+}
+
+/// Subscribe to the bus for as long as the current component is mounted
+pub fn use_app_events(handler: impl Fn(AppEvent) + Send + Sync + 'static) {
+    let bus = use_event_bus();
+    let id = bus.subscribe(Callback::new(handler));
+    on_cleanup(move || bus.unsubscribe(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_subscribers_receive_emitted_events() {
+        let owner = Owner::new();
+        let bus = owner.with(provide_event_bus);
+        let received = RwSignal::new(Vec::<AppEvent>::new());
+
+        let id = bus.subscribe(Callback::new(move |event| received.update(|events| events.push(event))));
+        bus.emit(AppEvent::ImportCompleted("1 file".to_string()));
+        bus.emit(AppEvent::ThemeChanged(Theme::Dark));
+        assert_eq!(
+            received.get_untracked(),
+            vec![AppEvent::ImportCompleted("1 file".to_string()), AppEvent::ThemeChanged(Theme::Dark)]
+        );
+
+        bus.unsubscribe(id);
+        bus.emit(AppEvent::ErrorReported("ignored".to_string()));
+        assert_eq!(received.get_untracked().len(), 2, "Unsubscribed handlers get nothing more");
+    }
+}
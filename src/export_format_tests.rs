@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod export_format_tests {
+    use std::collections::BTreeMap;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use wasm_bindgen_test::*;
+    use crate::data::{
+        ExportedData, ExportedAppData, BackupEnvelope, EXPORT_VERSION, SPLIT_FORMAT, unwrap_backup,
+        current_app_state, import_data,
+    };
+    use crate::utils::localStorage;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    // Number of generated cases per property
+    const CASES: u64 = 64;
+    // Largest size passed to the generators
+    const MAX_SIZE: usize = 24;
+
+    /// Small deterministic RNG so failures reproduce from their seed
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn new(seed: u64) -> Self {
+            XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+        }
+
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            if bound == 0 { 0 } else { (self.next() % bound as u64) as usize }
+        }
+
+        fn chance(&mut self) -> bool {
+            self.next() & 1 == 1
+        }
+    }
+
+    // Characters that have tripped up serializers: quotes, escapes, controls,
+    // BOM, combining marks, astral-plane emoji and right-to-left text
+    const TRICKY_CHARS: &[char] = &[
+        'a', 'Z', '0', ' ', '"', '\\', '/', '\n', '\r', '\t', '\u{0}', '\u{1f}',
+        '\u{feff}', 'é', 'ß', '\u{301}', '中', '😀', '🏳', '\u{200d}', 'א', '\u{7f}',
+    ];
+
+    // Generators take a `size`: size 0 gives the simplest value, so a failing
+    // seed can be shrunk by retrying it at smaller sizes
+    fn gen_string(rng: &mut XorShift, size: usize) -> String {
+        let len = rng.below(size + 1);
+        (0..len).map(|_| TRICKY_CHARS[rng.below(TRICKY_CHARS.len())]).collect()
+    }
+
+    fn gen_timestamp(rng: &mut XorShift, size: usize) -> String {
+        if size == 0 {
+            return Utc.timestamp_opt(0, 0).unwrap().to_rfc3339();
+        }
+        // Anywhere from year 1 to 9999, including both ends
+        let year = match rng.below(4) {
+            0 => 1,
+            1 => 9999,
+            _ => 1 + rng.below(9999) as i32,
+        };
+        let date = NaiveDate::from_ymd_opt(year, 1 + rng.below(12) as u32, 1 + rng.below(28) as u32).unwrap();
+        let time = date.and_hms_nano_opt(rng.below(24) as u32, rng.below(60) as u32, rng.below(60) as u32, rng.below(1_000_000_000) as u32).unwrap();
+        Utc.from_utc_datetime(&time).to_rfc3339()
+    }
+
+    fn gen_app_data(rng: &mut XorShift, size: usize) -> ExportedAppData {
+        let extra_count = rng.below(size.min(4) + 1);
+        let extra: BTreeMap<String, String> = (0..extra_count)
+            .map(|i| (format!("extra_{}_{}", i, gen_string(rng, size)), gen_string(rng, size)))
+            .collect();
+        ExportedAppData {
+            player_id: gen_string(rng, size),
+            dark_mode: rng.chance(),
+            locale: if rng.chance() { Some(gen_string(rng, size)) } else { None },
+            extra,
+        }
+    }
+
+    fn gen_exported_data(seed: u64, size: usize) -> ExportedData {
+        let mut rng = XorShift::new(seed);
+        ExportedData {
+            // An empty version is rejected on import, so always set one
+            version: format!("{}{}", EXPORT_VERSION, gen_string(&mut rng, size.min(4))),
+            timestamp: gen_timestamp(&mut rng, size),
+            data: gen_app_data(&mut rng, size),
+        }
+    }
+
+    /// Check `property` for generated values; on failure, report the
+    /// smallest size at which the failing seed still fails
+    fn check_property(name: &str, property: impl Fn(&ExportedData) -> Result<(), String>) {
+        for seed in 0..CASES {
+            let size = (seed as usize * MAX_SIZE / CASES as usize).max(1);
+            if let Err(err) = property(&gen_exported_data(seed, size)) {
+                let (smallest_size, smallest_value, smallest_err) = (0..=size)
+                    .find_map(|candidate| {
+                        let value = gen_exported_data(seed, candidate);
+                        property(&value).err().map(|err| (candidate, value, err))
+                    })
+                    .unwrap_or((size, gen_exported_data(seed, size), err));
+                panic!(
+                    "{} failed for seed {} (shrunk to size {}): {}\n{:?}",
+                    name, seed, smallest_size, smallest_err, smallest_value
+                );
+            }
+        }
+    }
+
+    fn recover(backup_json: &str) -> Result<ExportedData, String> {
+        let plaintext = unwrap_backup(backup_json).map_err(|err| err.to_string())?;
+        serde_json::from_str(&plaintext).map_err(|err| err.to_string())
+    }
+
+    fn expect_equal(original: &ExportedData, recovered: ExportedData) -> Result<(), String> {
+        if recovered == *original {
+            Ok(())
+        } else {
+            Err(format!("recovered {:?}", recovered))
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_plain_export_round_trips() {
+        check_property("plain round trip", |original| {
+            let json = serde_json::to_string(original).map_err(|err| err.to_string())?;
+            expect_equal(original, recover(&json)?)
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_split_envelope_round_trips() {
+        check_property("unencrypted envelope round trip", |original| {
+            let envelope = BackupEnvelope {
+                version: original.version.clone(),
+                timestamp: original.timestamp.clone(),
+                format: SPLIT_FORMAT.to_string(),
+                encrypted: false,
+                salt: None,
+                hint: None,
+                recovery: None,
+                data: serde_json::to_value(&original.data).map_err(|err| err.to_string())?,
+            };
+            let json = serde_json::to_string(&envelope).map_err(|err| err.to_string())?;
+            expect_equal(original, recover(&json)?)
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_encrypted_envelope_round_trips() {
+        check_property("encrypted envelope round trip", |original| {
+            let app_json = serde_json::to_string(&original.data).map_err(|err| err.to_string())?;
+            let encrypted = crate::crypto::encrypt_payload(&app_json).map_err(|err| err.to_string())?;
+            let envelope = BackupEnvelope {
+                version: original.version.clone(),
+                timestamp: original.timestamp.clone(),
+                format: SPLIT_FORMAT.to_string(),
+                encrypted: true,
+                salt: None,
+                hint: None,
+                recovery: None,
+                data: serde_json::to_value(&encrypted).map_err(|err| err.to_string())?,
+            };
+            let json = serde_json::to_string(&envelope).map_err(|err| err.to_string())?;
+            expect_equal(original, recover(&json)?)
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_plain_backup_imports() {
+        // Control characters inside values mustn't make the backup look binary
+        check_property("plain import", |original| {
+            localStorage::reset_all_storage();
+            let json = serde_json::to_string(original).map_err(|err| err.to_string())?;
+            import_data(&json).map_err(|err| err.to_string())?;
+            let state = current_app_state();
+            let expected = &original.data;
+            if (&state.player_id, state.dark_mode, &state.locale) == (&expected.player_id, expected.dark_mode, &expected.locale) {
+                Ok(())
+            } else {
+                Err(format!("imported {:?}", state))
+            }
+        });
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_generator_is_deterministic_and_shrinks() {
+        assert_eq!(gen_exported_data(7, 10), gen_exported_data(7, 10), "Same seed and size should give the same value");
+
+        let simplest = gen_exported_data(7, 0);
+        assert!(simplest.data.player_id.is_empty(), "Size 0 should give empty strings");
+        assert!(simplest.data.extra.is_empty(), "Size 0 should give no extra keys");
+    }
+}
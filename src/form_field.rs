@@ -0,0 +1,39 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::theme::use_paragraph_class;
+
+/// Labelled form control with an inline validation message.
+///
+/// The message is rendered under the control, with id and test id
+/// `{input_id}-error`, while `error` holds one. It's an alert, so screen
+/// readers announce it as it appears.
+#[component]
+pub fn FormField(
+    /// Text of the `<label>`
+    label: &'static str,
+    /// `id` of the control inside, which the label points at
+    input_id: &'static str,
+    /// Validation message, or `None` while the value is fine
+    #[prop(into)] error: Signal<Option<String>>,
+    /// The control, and any buttons that go with it
+    children: Children,
+) -> impl IntoView {
+    let error_id = format!("{}-error", input_id);
+
+    view! {
+        <p class={use_paragraph_class()}>
+            <label for={input_id}>{label}</label>
+            {children()}
+            {move || error.get().map(|message| view! {
+                <span
+                    id={error_id.clone()}
+                    data-test-id={error_id.clone()}
+                    role="alert"
+                    class="block text-sm text-red-600 dark:text-red-400"
+                >
+                    {message}
+                </span>
+            })}
+        </p>
+    }
+}
@@ -0,0 +1,637 @@
+use leptos::*;
+use leptos::prelude::*;
+use std::cell::Cell;
+use serde::{Deserialize, Serialize};
+use log::{error, info};
+use crate::utils::{
+    get_storage_item, normalize_player_id, set_storage_item, subscribe_storage_changes,
+    unsubscribe_storage_changes, StorageError,
+};
+use crate::theme::{use_paragraph_class, use_button_class};
+use crate::data::register_backup_key;
+use crate::confirm_modal::ConfirmModal;
+use crate::data::{use_data_status, DataError, DataStatus};
+use crate::panel::{Panel, PanelId, use_panel_nav};
+
+// Storage key for the friends list
+pub const FRIENDS_KEY: &str = "friends";
+
+// Default cap on the friends list, keeping it well inside localStorage quotas
+pub const DEFAULT_MAX_FRIENDS: usize = 500;
+
+thread_local! {
+    static MAX_FRIENDS: Cell<usize> = const { Cell::new(DEFAULT_MAX_FRIENDS) };
+}
+
+// Change how many friends can be stored
+pub fn set_max_friends(max: usize) {
+    MAX_FRIENDS.with(|current| current.set(max));
+}
+
+pub fn max_friends() -> usize {
+    MAX_FRIENDS.with(|current| current.get())
+}
+
+/// Where a friendship is in the invite handshake
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FriendStatus {
+    /// We sent an invite and are waiting for the other player
+    PendingOutgoing,
+    /// The other player invited us and we haven't answered yet
+    PendingIncoming,
+    Accepted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Friend {
+    pub player_id: String,
+    pub name: String,
+    pub status: FriendStatus,
+}
+
+// Error type for friend operations
+#[derive(Debug, Clone)]
+pub enum FriendError {
+    NotFound(String),
+    InvalidTransition(FriendStatus),
+    LimitReached(usize),
+    Storage(StorageError),
+    Parse(String),
+}
+
+impl std::fmt::Display for FriendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FriendError::NotFound(id) => write!(f, "No friend with player ID {}", id),
+            FriendError::InvalidTransition(status) => write!(f, "Friend request can't be answered while {:?}", status),
+            FriendError::LimitReached(max) => write!(f, "Friends list is full ({} friends); remove someone to add more", max),
+            FriendError::Storage(err) => write!(f, "Storage error: {:?}", err),
+            FriendError::Parse(msg) => write!(f, "Failed to parse friends list: {}", msg),
+        }
+    }
+}
+
+impl From<StorageError> for FriendError {
+    fn from(err: StorageError) -> Self {
+        FriendError::Storage(err)
+    }
+}
+
+// Include the friends list in backups
+pub fn register_backup_keys() {
+    register_backup_key(FRIENDS_KEY, true);
+}
+
+// Load the friends list, treating a missing key as empty
+pub fn load_friends() -> Result<Vec<Friend>, FriendError> {
+    match get_storage_item(FRIENDS_KEY)? {
+        Some(json) => serde_json::from_str(&json).map_err(|err| FriendError::Parse(err.to_string())),
+        None => Ok(Vec::new()),
+    }
+}
+
+// Save the friends list
+pub fn save_friends(friends: &[Friend]) -> Result<(), FriendError> {
+    let json = serde_json::to_string(friends).map_err(|err| FriendError::Parse(err.to_string()))?;
+    set_storage_item(FRIENDS_KEY, &json)?;
+    Ok(())
+}
+
+/// Add a friend, or update them if already listed.
+/// New friends past `max_friends()` are rejected with `FriendError::LimitReached`.
+pub fn add_friend(friend: Friend) -> Result<Friend, FriendError> {
+    let mut friends = load_friends()?;
+    match friends.iter_mut().find(|f| f.player_id == friend.player_id) {
+        Some(existing) => *existing = friend.clone(),
+        None => {
+            let max = max_friends();
+            if friends.len() >= max {
+                error!("Friends list is full, not adding {}", friend.player_id);
+                return Err(FriendError::LimitReached(max));
+            }
+            friends.push(friend.clone());
+        }
+    }
+    save_friends(&friends)?;
+    Ok(friend)
+}
+
+// Add or update a friend in the stored list
+fn upsert_friend(player_id: &str, name: &str, status: FriendStatus) -> Result<Friend, FriendError> {
+    add_friend(Friend {
+        player_id: player_id.to_string(),
+        name: name.to_string(),
+        status,
+    })
+}
+
+/// Send a friend request. If they already invited us, this accepts it.
+pub fn request_friend(player_id: &str, name: &str) -> Result<Friend, FriendError> {
+    let existing = load_friends()?.into_iter().find(|f| f.player_id == player_id);
+    let status = match existing.map(|f| f.status) {
+        Some(FriendStatus::PendingIncoming) | Some(FriendStatus::Accepted) => FriendStatus::Accepted,
+        _ => FriendStatus::PendingOutgoing,
+    };
+    info!("Friend request to {}: {:?}", player_id, status);
+    upsert_friend(player_id, name, status)
+}
+
+/// Record a request received through an invite link
+pub fn receive_friend_request(player_id: &str, name: &str) -> Result<Friend, FriendError> {
+    let existing = load_friends()?.into_iter().find(|f| f.player_id == player_id);
+    let status = match existing.map(|f| f.status) {
+        Some(FriendStatus::PendingOutgoing) | Some(FriendStatus::Accepted) => FriendStatus::Accepted,
+        _ => FriendStatus::PendingIncoming,
+    };
+    info!("Friend request from {}: {:?}", player_id, status);
+    upsert_friend(player_id, name, status)
+}
+
+/// Accept an incoming friend request
+pub fn accept_friend(player_id: &str) -> Result<Friend, FriendError> {
+    let mut friends = load_friends()?;
+    let friend = friends
+        .iter_mut()
+        .find(|f| f.player_id == player_id)
+        .ok_or_else(|| FriendError::NotFound(player_id.to_string()))?;
+    if friend.status != FriendStatus::PendingIncoming {
+        return Err(FriendError::InvalidTransition(friend.status));
+    }
+    friend.status = FriendStatus::Accepted;
+    let accepted = friend.clone();
+    save_friends(&friends)?;
+    Ok(accepted)
+}
+
+/// Decline an incoming request (or cancel an outgoing one), removing the friend
+pub fn decline_friend(player_id: &str) -> Result<(), FriendError> {
+    let mut friends = load_friends()?;
+    let before = friends.len();
+    friends.retain(|f| f.player_id != player_id);
+    if friends.len() == before {
+        return Err(FriendError::NotFound(player_id.to_string()));
+    }
+    save_friends(&friends)
+}
+
+// Number of accepted friends in a list
+fn accepted_count(friends: &[Friend]) -> usize {
+    friends.iter().filter(|f| f.status == FriendStatus::Accepted).count()
+}
+
+/// Number of accepted friends, kept up to date as the stored list changes,
+/// whether from this panel, an invite or an import
+pub fn use_friends_count() -> Signal<usize> {
+    let count = RwSignal::new(accepted_count(&load_friends().unwrap_or_default()));
+    let subscription = subscribe_storage_changes(move |keys| {
+        if keys.iter().any(|key| key == FRIENDS_KEY) {
+            count.set(accepted_count(&load_friends().unwrap_or_default()));
+        }
+    });
+    on_cleanup(move || unsubscribe_storage_changes(subscription));
+    count.into()
+}
+
+/// Outcome of `bulk_add_friends`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BulkAddReport {
+    pub added: usize,
+    /// Repeats, people already listed, our own ID, and IDs past the limit
+    pub skipped: usize,
+    /// Entries that aren't player IDs
+    pub invalid: usize,
+    /// The cap, when the friends list filled up before every ID was added
+    pub limit_reached: Option<usize>,
+}
+
+impl BulkAddReport {
+    pub fn summary(&self) -> String {
+        format!("Added {}, skipped {}, invalid {}", self.added, self.skipped, self.invalid)
+    }
+}
+
+/// Send friend requests to every player ID in `text`, separated by
+/// newlines, commas or spaces. Each ID is named after its first eight
+/// characters until the friend's own invite brings their name.
+pub fn bulk_add_friends(text: &str) -> Result<BulkAddReport, FriendError> {
+    let own_id = get_storage_item("player_id")?;
+    let mut known: Vec<String> = load_friends()?.into_iter().map(|f| f.player_id).collect();
+    let mut report = BulkAddReport::default();
+    for entry in text.split(|c: char| c == ',' || c.is_whitespace()).filter(|entry| !entry.is_empty()) {
+        let Some(player_id) = normalize_player_id(entry) else {
+            report.invalid += 1;
+            continue;
+        };
+        if own_id.as_deref() == Some(player_id.as_str()) || known.contains(&player_id) {
+            report.skipped += 1;
+            continue;
+        }
+        let name = player_id.chars().take(8).collect();
+        match add_friend(Friend { player_id: player_id.clone(), name, status: FriendStatus::PendingOutgoing }) {
+            Ok(_) => report.added += 1,
+            Err(FriendError::LimitReached(max)) => {
+                report.skipped += 1;
+                report.limit_reached = Some(max);
+            },
+            Err(err) => return Err(err),
+        }
+        known.push(player_id);
+    }
+    info!("Bulk friend add: {}", report.summary());
+    Ok(report)
+}
+
+/// Show a full friends list in the data panel's status area, where storage
+/// problems are reported
+pub fn report_friend_limit(status: DataStatus, err: &FriendError) {
+    if let FriendError::LimitReached(max) = err {
+        status.report_error(DataError::FriendLimit(*max));
+    }
+}
+
+/// Textarea for pasting a list of player IDs to send friend requests to
+#[component]
+pub fn BulkAddFriends(
+    /// Called after friends were added, so lists can reload
+    #[prop(into)] on_added: Callback<()>,
+) -> impl IntoView {
+    let text = RwSignal::new(String::new());
+    let (result, set_result) = signal(Option::<String>::None);
+    let status = use_data_status();
+
+    let add = move |_| {
+        match bulk_add_friends(&text.get_untracked()) {
+            Ok(report) => {
+                if report.added > 0 {
+                    text.set(String::new());
+                    on_added.run(());
+                }
+                if let Some(max) = report.limit_reached {
+                    report_friend_limit(status, &FriendError::LimitReached(max));
+                }
+                set_result.set(Some(report.summary()));
+            },
+            Err(err) => {
+                report_friend_limit(status, &err);
+                error!("{}", err);
+                set_result.set(Some(err.to_string()));
+            }
+        }
+    };
+
+    view! {
+        <div class="mt-4">
+            <label class={use_paragraph_class()} for="bulk-friends-input">"Bulk add friends"</label>
+            <textarea
+                id="bulk-friends-input"
+                data-test-id="bulk-friends-input"
+                class="w-full rounded border px-2 py-1 text-gray-900"
+                rows="4"
+                placeholder="One player ID per line, or separated by commas"
+                prop:value={move || text.get()}
+                on:input={move |ev| text.set(event_target_value(&ev))}
+            />
+            <button
+                data-test-id="bulk-friends-add"
+                class={use_button_class()}
+                disabled={move || text.get().trim().is_empty()}
+                on:click={add}
+            >
+                "Add all"
+            </button>
+            {move || result.get().map(|summary| view! {
+                <p data-test-id="bulk-friends-result" class="text-sm">{summary}</p>
+            })}
+        </div>
+    }
+}
+
+#[component]
+pub fn FriendsList() -> impl IntoView {
+    let (friends, set_friends) = signal(load_friends().unwrap_or_default());
+    let (friend_error, set_friend_error) = signal(Option::<String>::None);
+    // Keyboard selection, as an index into `ordered`
+    let selected = RwSignal::new(0usize);
+    let (opened, set_opened) = signal(Option::<Friend>::None);
+    let (pending_removal, set_pending_removal) = signal(Option::<Friend>::None);
+
+    // Apply a change and reload the list from storage
+    let update = move |result: Result<(), FriendError>| {
+        match result {
+            Ok(()) => set_friend_error.set(None),
+            Err(err) => {
+                error!("{}", err);
+                set_friend_error.set(Some(err.to_string()));
+            }
+        }
+        let reloaded = load_friends().unwrap_or_default();
+        selected.update(|index| *index = (*index).min(reloaded.len().saturating_sub(1)));
+        set_friends.set(reloaded);
+    };
+
+    let pending = move || {
+        friends.get().into_iter().filter(|f| f.status != FriendStatus::Accepted).collect::<Vec<_>>()
+    };
+    let accepted = move || {
+        friends.get().into_iter().filter(|f| f.status == FriendStatus::Accepted).collect::<Vec<_>>()
+    };
+    // Entries in display order: pending first, then accepted
+    let ordered = move || {
+        let mut entries = pending();
+        entries.extend(accepted());
+        entries
+    };
+    let is_selected = move |player_id: &str| {
+        ordered().iter().position(|f| f.player_id == player_id) == Some(selected.get())
+    };
+
+    let on_keydown = move |ev: web_sys::KeyboardEvent| {
+        let entries = ordered();
+        if entries.is_empty() {
+            return;
+        }
+        match ev.key().as_str() {
+            "ArrowDown" => {
+                ev.prevent_default();
+                selected.update(|index| *index = (*index + 1).min(entries.len() - 1));
+            },
+            "ArrowUp" => {
+                ev.prevent_default();
+                selected.update(|index| *index = index.saturating_sub(1));
+            },
+            "Enter" => {
+                set_opened.set(entries.get(selected.get_untracked()).cloned());
+            },
+            "Delete" => {
+                set_pending_removal.set(entries.get(selected.get_untracked()).cloned());
+            },
+            _ => {}
+        }
+    };
+
+    let confirm_removal = Callback::new(move |_: ()| {
+        if let Some(friend) = pending_removal.get_untracked() {
+            if opened.get_untracked().is_some_and(|f| f.player_id == friend.player_id) {
+                set_opened.set(None);
+            }
+            update(decline_friend(&friend.player_id));
+        }
+        set_pending_removal.set(None);
+    });
+    let cancel_removal = Callback::new(move |_: ()| set_pending_removal.set(None));
+
+    view! {
+        <div
+            data-test-id="friends-list"
+            class="mt-4"
+            role="listbox"
+            tabindex="0"
+            on:keydown={on_keydown}
+        >
+            <h3 class={use_paragraph_class()}>"Pending"</h3>
+            <ul data-test-id="friends-pending">
+                <For
+                    each=pending
+                    key=|friend| (friend.player_id.clone(), friend.status)
+                    let:friend
+                >
+                    {
+                        let id = friend.player_id.clone();
+                        let decline_id = friend.player_id.clone();
+                        let selected_id = friend.player_id.clone();
+                        let incoming = friend.status == FriendStatus::PendingIncoming;
+                        view! {
+                            <li
+                                data-test-id={format!("friend-{}", friend.player_id)}
+                                role="option"
+                                tabindex="-1"
+                                aria-selected={move || is_selected(&selected_id).to_string()}
+                            >
+                                {friend.name.clone()}
+                                {if incoming { " wants to connect" } else { " (invite sent)" }}
+                                {incoming.then(|| view! {
+                                    <button
+                                        data-test-id={format!("friend-accept-{}", id)}
+                                        class={use_button_class()}
+                                        on:click={
+                                            let id = id.clone();
+                                            move |_| update(accept_friend(&id).map(|_| ()))
+                                        }
+                                    >
+                                        "Accept"
+                                    </button>
+                                })}
+                                <button
+                                    data-test-id={format!("friend-decline-{}", decline_id)}
+                                    class={use_button_class()}
+                                    on:click={move |_| update(decline_friend(&decline_id))}
+                                >
+                                    {if incoming { "Decline" } else { "Cancel" }}
+                                </button>
+                            </li>
+                        }
+                    }
+                </For>
+            </ul>
+            <h3 class={use_paragraph_class()}>"Friends"</h3>
+            <ul data-test-id="friends-accepted">
+                <For
+                    each=accepted
+                    key=|friend| friend.player_id.clone()
+                    let:friend
+                >
+                    {
+                        let selected_id = friend.player_id.clone();
+                        view! {
+                            <li
+                                data-test-id={format!("friend-{}", friend.player_id)}
+                                role="option"
+                                tabindex="-1"
+                                aria-selected={move || is_selected(&selected_id).to_string()}
+                            >
+                                {friend.name}
+                            </li>
+                        }
+                    }
+                </For>
+            </ul>
+            {move || opened.get().map(|friend| view! {
+                <div data-test-id="friend-detail" class={use_paragraph_class()}>
+                    <p>{friend.name}</p>
+                    <p>{"Player ID: "}{friend.player_id}</p>
+                    <p>{format!("Status: {:?}", friend.status)}</p>
+                </div>
+            })}
+            {move || friend_error.get().map(|err| view! {
+                <p data-test-id="friends-error" class="text-red-600 text-sm">{err}</p>
+            })}
+            {move || pending_removal.get().map(|friend| view! {
+                <ConfirmModal
+                    message={format!("Remove {} from your friends?", friend.name)}
+                    test_id="remove-friend"
+                    on_confirm={confirm_removal}
+                    on_cancel={cancel_removal}
+                />
+            })}
+        </div>
+        // Outside the listbox, so typing doesn't trigger its shortcuts
+        <BulkAddFriends on_added={move || update(Ok(()))} />
+    }
+}
+
+/// Button that opens the friends list in its own panel
+#[component]
+pub fn FriendsButton() -> impl IntoView {
+    let nav = use_panel_nav();
+    let friends_count = use_friends_count();
+
+    view! {
+        <div class="mt-6">
+            {move || {
+                if nav.is_open(PanelId::Friends) {
+                    view! {
+                        <Panel title="Friends" test_id="friends" on_close={move || nav.close()}>
+                            <FriendsList />
+                        </Panel>
+                    }.into_any()
+                } else {
+                    view! {
+                        <button
+                            data-test-id="friends-button"
+                            class={use_button_class()}
+                            on:click={move |_| nav.open(PanelId::Friends)}
+                        >
+                            "Friends "
+                            <span
+                                data-test-id="friends-count"
+                                class="ml-1 rounded-full bg-indigo-600 px-2 text-xs text-white"
+                                aria-label={move || format!("{} friends", friends_count.get())}
+                            >
+                                {move || friends_count.get()}
+                            </span>
+                        </button>
+                    }.into_any()
+                }
+            }}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::localStorage;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn reset_friends() {
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+    }
+
+    fn status_of(player_id: &str) -> Option<FriendStatus> {
+        load_friends().unwrap().into_iter().find(|f| f.player_id == player_id).map(|f| f.status)
+    }
+
+    #[wasm_bindgen_test]
+    fn test_incoming_request_can_be_accepted() {
+        reset_friends();
+
+        receive_friend_request("p1", "Alice").unwrap();
+        assert_eq!(status_of("p1"), Some(FriendStatus::PendingIncoming));
+
+        accept_friend("p1").unwrap();
+        assert_eq!(status_of("p1"), Some(FriendStatus::Accepted), "Accepted status should be persisted");
+
+        reset_friends();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_outgoing_request_is_pending_until_answered() {
+        reset_friends();
+
+        request_friend("p2", "Bob").unwrap();
+        assert_eq!(status_of("p2"), Some(FriendStatus::PendingOutgoing));
+
+        // We can't accept our own request
+        assert!(matches!(accept_friend("p2"), Err(FriendError::InvalidTransition(FriendStatus::PendingOutgoing))));
+
+        // Their invite arriving completes the handshake
+        receive_friend_request("p2", "Bob").unwrap();
+        assert_eq!(status_of("p2"), Some(FriendStatus::Accepted));
+
+        reset_friends();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_add_friend_enforces_limit() {
+        reset_friends();
+        set_max_friends(2);
+
+        request_friend("cap1", "One").unwrap();
+        receive_friend_request("cap2", "Two").unwrap();
+        let result = request_friend("cap3", "Three");
+
+        // Updating someone already listed doesn't count against the cap
+        let update = receive_friend_request("cap1", "One");
+        set_max_friends(DEFAULT_MAX_FRIENDS);
+
+        assert!(matches!(result, Err(FriendError::LimitReached(2))), "Third friend should hit the cap: {:?}", result);
+        assert_eq!(status_of("cap3"), None, "Rejected friend should not be stored");
+        assert!(update.is_ok(), "Existing friends can still be updated at the cap");
+
+        reset_friends();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_full_friends_list_shows_in_data_status() {
+        use crate::data::DataButton;
+        use crate::theme::ThemeProvider;
+        use crate::test_utils::click_and_wait;
+        use wasm_bindgen::JsCast;
+
+        reset_friends();
+        set_max_friends(1);
+
+        // A root of its own, so status areas other tests left behind don't count
+        let document = web_sys::window().unwrap().document().unwrap();
+        let root = document.create_element("div").unwrap().dyn_into::<web_sys::HtmlElement>().unwrap();
+        document.body().unwrap().append_child(&root).unwrap();
+        let handle = leptos::mount::mount_to(root.clone(), || view! {
+            <ThemeProvider>
+                <DataButton inline=true />
+                <BulkAddFriends on_added={|| ()} />
+            </ThemeProvider>
+        });
+        let find = |test_id: &str| root.query_selector(&format!("[data-test-id='{}']", test_id)).unwrap();
+
+        let input = find("bulk-friends-input").unwrap().dyn_into::<web_sys::HtmlTextAreaElement>().unwrap();
+        input.set_value("2f1c4b1e-7f3a-4e2b-9a57-1d2c3b4a5f60 8e9d0c1b-2a3f-4e5d-8c7b-6a5f4e3d2c1b");
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        click_and_wait(&find("bulk-friends-add").unwrap(), 100).await;
+
+        let error = find("storage-error").map(|el| el.text_content().unwrap_or_default());
+        set_max_friends(DEFAULT_MAX_FRIENDS);
+        drop(handle);
+        root.remove();
+        reset_friends();
+
+        let error = error.expect("The full friends list should show in the data status area");
+        assert!(error.contains("Friends list is full (1 friends)"), "{}", error);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_declined_friend_is_removed() {
+        reset_friends();
+
+        receive_friend_request("p3", "Carol").unwrap();
+        receive_friend_request("p4", "Dave").unwrap();
+        decline_friend("p3").unwrap();
+
+        assert_eq!(status_of("p3"), None, "Declined friend should be removed");
+        assert_eq!(status_of("p4"), Some(FriendStatus::PendingIncoming), "Other requests should be kept");
+        assert!(matches!(decline_friend("p3"), Err(FriendError::NotFound(_))));
+
+        reset_friends();
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod friends_tests {
+    use leptos::*;
+    use leptos::prelude::*;
+    use wasm_bindgen_test::*;
+    use gloo_timers::future::TimeoutFuture;
+    use crate::test_utils::test::*;
+    use crate::theme::ThemeProvider;
+    use crate::utils::localStorage;
+    use crate::friends::{FriendsList, FriendStatus, FRIENDS_KEY, receive_friend_request, request_friend, load_friends};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    async fn press_key(element: &web_sys::Element, key: &str) {
+        let init = web_sys::KeyboardEventInit::new();
+        init.set_key(key);
+        init.set_bubbles(true);
+        let event = web_sys::KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap();
+        element.dispatch_event(&event).unwrap();
+        TimeoutFuture::new(50).await;
+    }
+
+    fn is_selected(test_id: &str) -> bool {
+        get_by_test_id(test_id).get_attribute("aria-selected").as_deref() == Some("true")
+    }
+
+    fn seed_friends() {
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+        receive_friend_request("kb1", "Alice").unwrap();
+        request_friend("kb2", "Bob").unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_arrow_down_advances_selection() {
+        seed_friends();
+        mount_to_body(|| view! { <ThemeProvider><FriendsList /></ThemeProvider> });
+
+        assert!(is_selected("friend-kb1"), "First entry should start selected");
+
+        let list = get_by_test_id("friends-list");
+        press_key(&list, "ArrowDown").await;
+        assert!(is_selected("friend-kb2"), "ArrowDown should select the next entry");
+        assert!(!is_selected("friend-kb1"));
+
+        // Selection stops at the last entry
+        press_key(&list, "ArrowDown").await;
+        assert!(is_selected("friend-kb2"), "Selection should not move past the end");
+
+        press_key(&list, "ArrowUp").await;
+        assert!(is_selected("friend-kb1"), "ArrowUp should select the previous entry");
+
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_enter_opens_detail_and_delete_confirms_removal() {
+        seed_friends();
+        mount_to_body(|| view! { <ThemeProvider><FriendsList /></ThemeProvider> });
+
+        let list = get_by_test_id("friends-list");
+        press_key(&list, "Enter").await;
+        let detail = get_by_test_id("friend-detail");
+        assert!(detail.text_content().unwrap().contains("kb1"), "Enter should open the selected friend");
+
+        // Delete asks first; cancelling keeps the friend
+        press_key(&list, "Delete").await;
+        click_and_wait(&get_by_test_id("remove-friend-cancel"), 50).await;
+        assert_eq!(load_friends().unwrap().len(), 2, "Cancelling should not remove anyone");
+
+        press_key(&list, "Delete").await;
+        click_and_wait(&get_by_test_id("remove-friend-confirm"), 50).await;
+        let remaining = load_friends().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].player_id, "kb2", "Confirming should remove the selected friend");
+
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_bulk_add_reports_added_skipped_and_invalid() {
+        use wasm_bindgen::JsCast;
+
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+        let listed = "6f1c2b9e-3d4a-4c5b-8e7f-0a1b2c3d4e5f";
+        request_friend(listed, "Already").unwrap();
+        mount_to_body(|| view! { <ThemeProvider><FriendsList /></ThemeProvider> });
+
+        // Two new IDs (one spelled without hyphens and repeated), one already
+        // listed, and two that aren't IDs at all
+        let pasted = format!(
+            "11111111-2222-4333-8444-555555555555, not-an-id\n{}\n\nAAAAAAAABBBBCCCCDDDDEEEEEEEEEEEE\naaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee 12345",
+            listed,
+        );
+        let input = get_by_test_id("bulk-friends-input").dyn_into::<web_sys::HtmlTextAreaElement>().unwrap();
+        input.set_value(&pasted);
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        TimeoutFuture::new(50).await;
+        click_and_wait(&get_by_test_id("bulk-friends-add"), 50).await;
+
+        assert_eq!(get_by_test_id("bulk-friends-result").text_content().unwrap(), "Added 2, skipped 2, invalid 2");
+        let friends = load_friends().unwrap();
+        assert_eq!(friends.len(), 3);
+        assert!(friends.iter().any(|f| f.player_id == "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee" && f.status == FriendStatus::PendingOutgoing));
+        assert!(get_by_test_id("friend-11111111-2222-4333-8444-555555555555").is_connected(), "The list should show new friends");
+
+        let _ = localStorage::reset_storage_item(FRIENDS_KEY);
+    }
+}
@@ -0,0 +1,366 @@
+use leptos::*;
+use leptos::prelude::*;
+use log::{error, info, Level};
+use serde::{Serialize, Deserialize};
+use std::cell::Cell;
+use crate::announcer::use_announcer;
+use crate::copy_button::CopyButton;
+use crate::event_bus::{use_event_bus, AppEvent};
+use crate::crypto::{CryptoError, encrypt_url_safe, decrypt_url_safe};
+use crate::data::use_data_status;
+use crate::friends::{accept_friend, report_friend_limit, Friend, FriendError, FriendStatus, receive_friend_request};
+use crate::logging::{log_event, LogCategory};
+use crate::profile::load_profile_name;
+use crate::theme::{use_button_class, use_paragraph_class};
+use crate::utils::{get_storage_item_required, query_param, StorageError};
+
+// Query parameter carrying an encrypted profile invite
+pub const PROFILE_PARAM: &str = "profile";
+
+// Longest link we'll produce; older browsers and chat apps truncate past ~2000 characters
+pub const MAX_PROFILE_LINK_LEN: usize = 2000;
+
+// Key shared by every copy of the app so any player can open an invite.
+// It keeps invite contents out of server logs and link previews, not secret
+// from other players.
+const INVITE_KEY: [u8; 32] = [
+    0x7a, 0x1e, 0x93, 0x4c, 0xd2, 0x08, 0x6f, 0xb1,
+    0x35, 0xe7, 0x5a, 0x29, 0xc4, 0x90, 0x1b, 0x6d,
+    0xf3, 0x42, 0x87, 0x0e, 0xab, 0x5c, 0x16, 0xd9,
+    0x61, 0x3f, 0xc8, 0x24, 0x9e, 0x73, 0x0a, 0xb5,
+];
+
+/// Who an invite is from. Field names are shortened to keep links small.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProfileInvite {
+    #[serde(rename = "p")]
+    pub player_id: String,
+    #[serde(rename = "n", default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Token the receiver echoes back to complete the handshake
+    #[serde(rename = "t", default, skip_serializing_if = "Option::is_none")]
+    pub handshake_token: Option<String>,
+}
+
+// Error type for invite operations
+#[derive(Debug, Clone)]
+pub enum InviteError {
+    Storage(StorageError),
+    Crypto(CryptoError),
+    Parse(String),
+    TooLong(usize),
+    MissingParam,
+}
+
+impl std::fmt::Display for InviteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InviteError::Storage(err) => write!(f, "Storage error: {:?}", err),
+            InviteError::Crypto(err) => write!(f, "Invite couldn't be read: {}", err),
+            InviteError::Parse(msg) => write!(f, "Invalid invite: {}", msg),
+            InviteError::TooLong(len) => write!(f, "Invite link is too long ({} characters)", len),
+            InviteError::MissingParam => write!(f, "Link has no `{}` parameter", PROFILE_PARAM),
+        }
+    }
+}
+
+impl From<StorageError> for InviteError {
+    fn from(err: StorageError) -> Self {
+        InviteError::Storage(err)
+    }
+}
+
+impl From<CryptoError> for InviteError {
+    fn from(err: CryptoError) -> Self {
+        InviteError::Crypto(err)
+    }
+}
+
+impl ProfileInvite {
+    /// Invite for the local player, from their stored ID and profile name
+    pub fn current() -> Result<Self, InviteError> {
+        Ok(ProfileInvite {
+            player_id: get_storage_item_required("player_id")?,
+            name: load_profile_name(),
+            handshake_token: None,
+        })
+    }
+
+    /// Encrypt into the URL-safe blob used as the `profile` parameter
+    pub fn encode(&self) -> Result<String, InviteError> {
+        let json = serde_json::to_string(self).map_err(|e| InviteError::Parse(e.to_string()))?;
+        Ok(encrypt_url_safe(&json, &INVITE_KEY)?)
+    }
+
+    pub fn decode(blob: &str) -> Result<Self, InviteError> {
+        let json = decrypt_url_safe(blob, &INVITE_KEY)?;
+        let invite: ProfileInvite = serde_json::from_str(&json).map_err(|e| InviteError::Parse(e.to_string()))?;
+        if invite.player_id.is_empty() {
+            return Err(InviteError::Parse("Invite has no player ID".to_string()));
+        }
+        Ok(invite)
+    }
+}
+
+/// Link to `base_url` carrying the invite, e.g. "https://example.com/?profile=..."
+pub fn build_profile_link(base_url: &str, invite: &ProfileInvite) -> Result<String, InviteError> {
+    let base = base_url.split(['?', '#']).next().unwrap_or_default();
+    // base64url needs no percent-encoding
+    let link = format!("{}?{}={}", base, PROFILE_PARAM, invite.encode()?);
+    if link.len() > MAX_PROFILE_LINK_LEN {
+        return Err(InviteError::TooLong(link.len()));
+    }
+    Ok(link)
+}
+
+/// Invite link for the local player, pointing at the current page
+pub fn current_invite_link() -> Result<String, InviteError> {
+    let href = web_sys::window().and_then(|window| window.location().href().ok()).unwrap_or_default();
+    build_profile_link(&href, &ProfileInvite::current()?)
+}
+
+/// Read the invite from a full link or just its query string
+pub fn parse_profile_link(link: &str) -> Result<ProfileInvite, InviteError> {
+    let without_fragment = link.split('#').next().unwrap_or_default();
+    let search = without_fragment.split_once('?').map_or(without_fragment, |(_, query)| query);
+    let blob = query_param(search, PROFILE_PARAM).ok_or(InviteError::MissingParam)?;
+    ProfileInvite::decode(&blob)
+}
+
+// Helper function to get the invite carried by the page URL, if any
+pub fn requested_invite() -> Option<Result<ProfileInvite, InviteError>> {
+    let search = web_sys::window()?.location().search().ok()?;
+    query_param(&search, PROFILE_PARAM)?;
+    Some(parse_profile_link(&search))
+}
+
+thread_local! {
+    static SIMULATED_CONNECTIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Complete invites locally instead of waiting for the other player, so
+/// connecting can be tried end to end in tests and demos without a server
+pub fn set_simulated_connections(on: bool) {
+    SIMULATED_CONNECTIONS.with(|current| current.set(on));
+}
+
+pub fn simulated_connections() -> bool {
+    SIMULATED_CONNECTIONS.with(|current| current.get())
+}
+
+/// Accept an invite and play the inviter's side of the handshake too,
+/// leaving a mutual friend. Each step is logged as it would happen over
+/// the network.
+pub fn simulate_connection(invite: &ProfileInvite) -> Result<Friend, FriendError> {
+    let log = |step: &str| log_event(LogCategory::Invite, Level::Info, &format!("Simulated handshake with {}: {}", invite.player_id, step));
+    log("invite received");
+    let friend = receive_friend_request(&invite.player_id, &invite.name)?;
+    if friend.status == FriendStatus::Accepted {
+        // We had already invited them, so their invite completes it
+        log("already mutual");
+        return Ok(friend);
+    }
+    log("acceptance sent");
+    let friend = accept_friend(&invite.player_id)?;
+    log("acceptance confirmed");
+    Ok(friend)
+}
+
+/// Preview of an invite opened from a link, offering to save it as an
+/// incoming friend request, or to connect at once with simulated connections
+#[component]
+pub fn IncomingInvite(invite: ProfileInvite) -> impl IntoView {
+    let (saved, set_saved) = signal(Option::<Result<(), FriendError>>::None);
+    let display_name = if invite.name.is_empty() { invite.player_id.clone() } else { invite.name.clone() };
+    let announcer = use_announcer();
+    let bus = use_event_bus();
+    let status = use_data_status();
+    let connected_message = format!("You're now connected with {}", display_name);
+    let invite = StoredValue::new(invite);
+
+    let add_friend = Callback::new(move |_: ()| {
+        let invite = invite.get_value();
+        let result = if simulated_connections() {
+            simulate_connection(&invite).map(|_| ())
+        } else {
+            receive_friend_request(&invite.player_id, &invite.name).map(|_| ())
+        };
+        match &result {
+            Ok(()) if simulated_connections() => {
+                announcer.announce(connected_message.clone());
+                bus.emit(AppEvent::FriendAdded(invite.player_id.clone()));
+            },
+            Ok(()) => info!("Saved invite from {}", invite.player_id),
+            Err(err) => {
+                error!("Failed to save invite: {}", err);
+                report_friend_limit(status, err);
+            },
+        }
+        set_saved.set(Some(result));
+    });
+
+    view! {
+        <div data-test-id="incoming-invite" class={use_paragraph_class()}>
+            <p>{display_name}" invited you to connect"</p>
+            {move || match saved.get() {
+                None => view! {
+                    <button data-test-id="accept-invite" class={use_button_class()} on:click={move |_| add_friend.run(())}>
+                        "Add friend"
+                    </button>
+                }.into_any(),
+                Some(Ok(())) if simulated_connections() => view! { <p data-test-id="invite-saved">"Connected"</p> }.into_any(),
+                Some(Ok(())) => view! { <p data-test-id="invite-saved">"Friend request saved"</p> }.into_any(),
+                Some(Err(err)) => view! { <p data-test-id="invite-error">{err.to_string()}</p> }.into_any(),
+            }}
+        </div>
+    }
+}
+
+/// Copies an invite link for the local player, or says why there is none,
+/// e.g. when the link would be too long to share
+#[component]
+pub fn CopyInviteLink() -> impl IntoView {
+    match current_invite_link() {
+        Ok(link) => view! {
+            <CopyButton text={Signal::derive(move || link.clone())} label="Copy invite link" test_id="copy-invite-link" />
+        }.into_any(),
+        Err(err) => {
+            error!("Failed to build invite link: {}", err);
+            view! { <p data-test-id="invite-link-error" class={use_paragraph_class()}>{err.to_string()}</p> }.into_any()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_profile_link_round_trip() {
+        let invite = ProfileInvite {
+            player_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            name: "Zoë 🎲".to_string(),
+            handshake_token: Some("tok_123".to_string()),
+        };
+
+        let link = build_profile_link("https://example.com/app/?panel=data#top", &invite).expect("Link should build");
+        assert!(link.starts_with("https://example.com/app/?profile="), "{}", link);
+        assert!(!link.contains("Zo"), "Name should not appear in cleartext");
+        assert!(link.len() < 300, "Link should stay short: {} characters", link.len());
+
+        assert_eq!(parse_profile_link(&link).expect("Link should parse"), invite);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_tampered_or_missing_invite_is_rejected() {
+        let invite = ProfileInvite { player_id: "p1".to_string(), name: String::new(), handshake_token: None };
+        let link = build_profile_link("https://example.com/", &invite).unwrap();
+
+        let mut tampered = link.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(matches!(parse_profile_link(&tampered), Err(InviteError::Crypto(_))));
+
+        assert!(matches!(parse_profile_link("https://example.com/?panel=data"), Err(InviteError::MissingParam)));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_simulated_connection_adds_and_announces_friend() {
+        use crate::announcer::{provide_announcer, LiveRegion};
+        use crate::friends::load_friends;
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        use crate::theme::ThemeProvider;
+
+        reset_everything();
+        set_simulated_connections(true);
+        let inviter = ProfileInvite {
+            player_id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            name: "Grace".to_string(),
+            handshake_token: None,
+        };
+        let link = build_profile_link("https://example.com/", &inviter).unwrap();
+        let invite = parse_profile_link(&link).expect("Link should parse");
+
+        mount_to_body(move || {
+            provide_announcer();
+            view! {
+                <ThemeProvider>
+                    <LiveRegion />
+                    <IncomingInvite invite={invite.clone()} />
+                </ThemeProvider>
+            }
+        });
+        click_and_wait(&get_by_test_id("accept-invite"), 50).await;
+
+        let friend = load_friends().unwrap().into_iter().find(|f| f.player_id == inviter.player_id);
+        assert_eq!(friend.map(|f| (f.name, f.status)), Some(("Grace".to_string(), FriendStatus::Accepted)));
+        assert!(get_by_test_id("live-region").text_content().unwrap().contains("connected with Grace"));
+        assert_eq!(get_by_test_id("invite-saved").text_content().unwrap(), "Connected");
+
+        set_simulated_connections(false);
+        reset_everything();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_copy_invite_link_copies_the_local_invite() {
+        use crate::copy_button::{reset_clipboard_writer, set_clipboard_writer};
+        use crate::test_utils::{click_and_wait, get_by_test_id, reset_everything};
+        use crate::theme::ThemeProvider;
+        use crate::utils::set_storage_item;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        reset_everything();
+        set_storage_item("player_id", "inviting_player").unwrap();
+        let copied: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let copied_writer = copied.clone();
+        set_clipboard_writer(move |text| {
+            *copied_writer.borrow_mut() = Some(text);
+            Box::pin(async { Ok(()) })
+        });
+
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <CopyInviteLink />
+            </ThemeProvider>
+        });
+        click_and_wait(&get_by_test_id("copy-invite-link"), 50).await;
+
+        let link = copied.borrow().clone().expect("The link should be copied");
+        assert_eq!(parse_profile_link(&link).expect("Link should parse").player_id, "inviting_player");
+
+        reset_clipboard_writer();
+        reset_everything();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_copy_invite_link_explains_a_link_too_long_to_share() {
+        use crate::test_utils::reset_everything;
+        use crate::theme::ThemeProvider;
+        use crate::utils::set_storage_item;
+        use gloo_timers::future::TimeoutFuture;
+
+        reset_everything();
+        set_storage_item("player_id", &"x".repeat(MAX_PROFILE_LINK_LEN)).unwrap();
+
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <CopyInviteLink />
+            </ThemeProvider>
+        });
+        TimeoutFuture::new(50).await;
+
+        let document = web_sys::window().unwrap().document().unwrap();
+        assert!(document.query_selector("[data-test-id='copy-invite-link']").unwrap().is_none());
+        let message = document.query_selector("[data-test-id='invite-link-error']").unwrap()
+            .expect("The reason should be shown")
+            .text_content()
+            .unwrap();
+        assert!(message.starts_with("Invite link is too long"), "{}", message);
+
+        reset_everything();
+    }
+}
@@ -0,0 +1,75 @@
+use log::Level;
+use wasm_bindgen::JsValue;
+
+/// Area of the app an event comes from; each has a fixed prefix so logs
+/// can be filtered, e.g. everything starting with `DATA_IMPORT`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogCategory {
+    DataImport,
+    DataExport,
+    DataLoad,
+    PlayerId,
+    Theme,
+    Locale,
+    Invite,
+}
+
+impl LogCategory {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            LogCategory::DataImport => "DATA_IMPORT",
+            LogCategory::DataExport => "DATA_EXPORT",
+            LogCategory::DataLoad => "DATA_LOAD",
+            LogCategory::PlayerId => "PLAYER_ID",
+            LogCategory::Theme => "THEME",
+            LogCategory::Locale => "LOCALE",
+            LogCategory::Invite => "INVITE",
+        }
+    }
+}
+
+/// The line an event is logged as: `CATEGORY: message`, or
+/// `CATEGORY_ERROR: message` for errors
+pub fn format_event(category: LogCategory, level: Level, message: &str) -> String {
+    let suffix = if level == Level::Error { "_ERROR" } else { "" };
+    format!("{}{}: {}", category.prefix(), suffix, message)
+}
+
+/// Log an event through the `log` crate and the browser console, and (in
+/// tests) record it in the log collector
+pub fn log_event(category: LogCategory, level: Level, message: &str) {
+    let line = format_event(category, level, message);
+    log::log!(level, "{}", line);
+    web_sys::console::log_1(&JsValue::from_str(&line));
+
+    #[cfg(test)]
+    if let Some(collector) = crate::mock_logger::mock::get_log_collector() {
+        match level {
+            Level::Error => collector.record_error(&line),
+            Level::Warn => collector.record_warn(&line),
+            _ => collector.record_info(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_logger::mock::init_log_collector;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_events_are_recorded_with_category_prefix() {
+        let collector = init_log_collector();
+
+        log_event(LogCategory::DataImport, Level::Info, "Merged fields: locale");
+        log_event(LogCategory::DataImport, Level::Error, "backup.json: bad data");
+
+        assert!(collector.contains_info("DATA_IMPORT: Merged fields: locale"));
+        assert!(collector.contains_error("DATA_IMPORT_ERROR: backup.json: bad data"));
+        assert_eq!(collector.info_count(), 1);
+        assert_eq!(collector.error_count(), 1);
+    }
+}
@@ -5,6 +5,29 @@ mod data;
 mod utils;
 mod theme;  
 mod crypto;
+mod panel;
+mod clock;
+mod friends;
+mod copy_button;
+mod confirm_modal;
+mod data_service;
+mod autosave;
+mod unsaved_changes;
+mod profile;
+mod capabilities;
+mod invite;
+mod shortcuts;
+mod announcer;
+mod panic_fallback;
+mod passphrase_prompt;
+mod logging;
+mod qr_export;
+mod safe_mode;
+mod form_field;
+mod service_worker;
+mod quota_warning;
+mod session_lock;
+mod event_bus;
 
 #[cfg(test)]
 mod app_tests;
@@ -21,6 +44,16 @@ mod theme_tests;
 
 #[cfg(test)]
 mod theme_provider_tests;  
+#[cfg(test)]
+mod panel_tests;
+#[cfg(test)]
+mod copy_button_tests;
+#[cfg(test)]
+mod friends_tests;
+#[cfg(test)]
+mod export_format_tests;
+#[cfg(test)]
+mod shortcuts_tests;
 
 use leptos::*;
 use leptos::prelude::*;
@@ -33,12 +66,24 @@ fn main() {
     // This uses wasm_logger which outputs to the browser console
     wasm_logger::init(wasm_logger::Config::default());
     
+    // Show a recovery message instead of a blank page if anything panics
+    panic_fallback::install_panic_hook();
+    
     // Log application startup
     log::info!("Leptos CSR application starting...");
     
+    // Let feature modules register the storage keys they persist, so
+    // backups see them before anything mounts
+    friends::register_backup_keys();
+    profile::register_backup_keys();
+    theme::register_backup_keys();
+    
     mount_to_body(|| view! { <App /> });
     
     log::info!("Application mounted successfully");
+    
+    // Cache the app for offline use where the browser allows it
+    service_worker::register_service_worker("./sw.js");
 }
 
 #[cfg(test)]
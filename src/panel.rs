@@ -0,0 +1,112 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::theme::{
+    use_data_panel_class,
+    use_data_header_class,
+    use_data_close_button_class,
+    use_data_content_class,
+};
+
+/// Themed panel shell shared by the data panel and any future panels.
+///
+/// Test ids are derived from `test_id`, so `test_id="data"` renders
+/// `data-panel`, `data-header`, `data-close-button` and `data-content`.
+#[component]
+pub fn Panel(
+    /// Heading shown at the top of the panel
+    #[prop(into)] title: String,
+    /// Prefix used for the `data-test-id` attributes of the panel parts
+    test_id: &'static str,
+    /// Called when the close button is clicked
+    #[prop(into)] on_close: Callback<()>,
+    /// Panel content, rendered inside the themed content wrapper
+    children: Children,
+) -> impl IntoView {
+    view! {
+        <div
+            class={use_data_panel_class()}
+            data-test-id={format!("{}-panel", test_id)}
+        >
+            <div class="flex justify-between items-center mb-4">
+                <h2
+                    data-test-id={format!("{}-header", test_id)}
+                    class={use_data_header_class()}
+                >
+                    {title}
+                </h2>
+                <button
+                    data-test-id={format!("{}-close-button", test_id)}
+                    class={use_data_close_button_class()}
+                    on:click={move |_| on_close.run(())}
+                >
+                    "×"
+                </button>
+            </div>
+            <div
+                data-test-id={format!("{}-content", test_id)}
+                class={use_data_content_class()}
+            >
+                {children()}
+            </div>
+        </div>
+    }
+}
+
+/// Panels that `PanelNav` can open
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelId {
+    Data,
+    Friends,
+}
+
+impl PanelId {
+    pub const ALL: [PanelId; 2] = [PanelId::Data, PanelId::Friends];
+
+    /// Name used in deep links, e.g. "?panel=friends"
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanelId::Data => "data",
+            PanelId::Friends => "friends",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        PanelId::ALL.into_iter().find(|panel| panel.name() == name)
+    }
+}
+
+/// Which panel is open. Opening one closes any other, so buttons,
+/// deep links and shortcuts all go through here.
+#[derive(Copy, Clone)]
+pub struct PanelNav {
+    pub active: ReadSignal<Option<PanelId>>,
+    set_active: WriteSignal<Option<PanelId>>,
+}
+
+impl PanelNav {
+    pub fn open(&self, panel: PanelId) {
+        self.set_active.set(Some(panel));
+    }
+
+    pub fn close(&self) {
+        self.set_active.set(None);
+    }
+
+    /// Whether `panel` is the open one (tracked)
+    pub fn is_open(&self, panel: PanelId) -> bool {
+        self.active.get() == Some(panel)
+    }
+}
+
+// Provide panel navigation to this part of the tree
+pub fn provide_panel_nav() -> PanelNav {
+    let (active, set_active) = signal(None);
+    let nav = PanelNav { active, set_active };
+    provide_context(nav);
+    nav
+}
+
+// Helper to get panel navigation, creating it if none is provided
+pub fn use_panel_nav() -> PanelNav {
+    use_context::<PanelNav>().unwrap_or_else(provide_panel_nav)
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod panel_tests {
+    use leptos::*;
+    use leptos::prelude::*;
+    use wasm_bindgen_test::*;
+    use crate::test_utils::test::*;
+    use crate::theme::ThemeProvider;
+    use crate::panel::{Panel, PanelId, provide_panel_nav};
+    use crate::data::DataButton;
+    use crate::friends::FriendsButton;
+    
+    wasm_bindgen_test_configure!(run_in_browser);
+    
+    #[wasm_bindgen_test]
+    async fn test_panel_renders_title_and_children() {
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <Panel title="Test Panel" test_id="render-test" on_close={move || {}}>
+                    <p data-test-id="render-test-child">"Panel body"</p>
+                </Panel>
+            </ThemeProvider>
+        });
+        
+        let header = get_by_test_id("render-test-header");
+        assert_eq!(header.text_content().unwrap(), "Test Panel", "Panel should render its title");
+        
+        // Children should be rendered inside the content wrapper
+        let content = get_by_test_id("render-test-content");
+        let child = get_by_test_id("render-test-child");
+        assert!(content.contains(Some(child.as_ref())), "Children should be rendered inside the panel content");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_panel_close_callback_fires() {
+        let (closed, set_closed) = signal(false);
+        
+        mount_to_body(move || view! {
+            <ThemeProvider>
+                <Panel title="Closable" test_id="close-test" on_close={move || set_closed.set(true)}>
+                    <p>"Content"</p>
+                </Panel>
+            </ThemeProvider>
+        });
+        
+        assert!(!closed.get_untracked(), "Close callback should not fire before clicking");
+        
+        let close_button = get_by_test_id("close-test-close-button");
+        click_and_wait(&close_button, 50).await;
+        
+        assert!(closed.get_untracked(), "Close callback should fire when the close button is clicked");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_opening_friends_panel_closes_data_panel() {
+        reset_everything();
+        mount_to_body(|| {
+            let nav = provide_panel_nav();
+            nav.open(PanelId::Data);
+            view! {
+                <ThemeProvider>
+                    <FriendsButton />
+                    <DataButton />
+                </ThemeProvider>
+            }
+        });
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        assert!(document.query_selector("[data-test-id='data-panel']").unwrap().is_some(), "Data panel should start open");
+        
+        let friends_button = get_by_test_id("friends-button");
+        click_and_wait(&friends_button, 50).await;
+        
+        assert!(document.query_selector("[data-test-id='friends-panel']").unwrap().is_some(), "Friends panel should open");
+        assert!(document.query_selector("[data-test-id='data-panel']").unwrap().is_none(), "Only one panel should be open at a time");
+        assert!(document.query_selector("[data-test-id='data-button']").unwrap().is_some(), "Data panel should collapse back to its button");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_friends_badge_counts_friends() {
+        use crate::friends::{add_friend, Friend, FriendStatus};
+        use gloo_timers::future::TimeoutFuture;
+        
+        reset_everything();
+        mount_to_body(|| {
+            provide_panel_nav();
+            view! {
+                <ThemeProvider>
+                    <FriendsButton />
+                </ThemeProvider>
+            }
+        });
+        assert_eq!(get_by_test_id("friends-count").text_content().unwrap(), "0");
+        
+        for (player_id, name) in [("badge1", "Alice"), ("badge2", "Bob")] {
+            add_friend(Friend { player_id: player_id.to_string(), name: name.to_string(), status: FriendStatus::Accepted }).unwrap();
+        }
+        TimeoutFuture::new(50).await;
+        assert_eq!(get_by_test_id("friends-count").text_content().unwrap(), "2", "The badge should follow the stored list");
+        
+        reset_everything();
+    }
+}
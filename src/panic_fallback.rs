@@ -0,0 +1,96 @@
+use std::cell::Cell;
+use wasm_bindgen::JsValue;
+
+// `data-test-id` of the recovery message
+pub const RECOVERY_TEST_ID: &str = "panic-recovery";
+
+thread_local! {
+    // Set while the hook runs so a failure inside it can't recurse
+    static HANDLING_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Log panics to the console and replace the blank page a panic leaves
+/// behind with a message asking the user to reload
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        web_sys::console::error_1(&JsValue::from_str(&format!("Application panicked: {}", info)));
+        show_recovery_message();
+    }));
+}
+
+/// Render the recovery message into the body with plain DOM calls, since
+/// the app's reactive state can't be trusted after a panic. Does nothing if
+/// the message is already showing or the hook is already running.
+pub fn show_recovery_message() {
+    if HANDLING_PANIC.with(|handling| handling.replace(true)) {
+        return;
+    }
+    // Every step is fallible and ignored: panicking here would abort outright
+    let _ = render_recovery_message();
+    HANDLING_PANIC.with(|handling| handling.set(false));
+}
+
+fn render_recovery_message() -> Option<()> {
+    let document = web_sys::window()?.document()?;
+    let selector = format!("[data-test-id='{}']", RECOVERY_TEST_ID);
+    if document.query_selector(&selector).ok()?.is_some() {
+        return Some(());
+    }
+
+    let message = document.create_element("div").ok()?;
+    message.set_attribute("data-test-id", RECOVERY_TEST_ID).ok()?;
+    message.set_attribute("role", "alert").ok()?;
+    message.set_attribute(
+        "style",
+        "position: fixed; inset: 0; display: flex; flex-direction: column; align-items: center; \
+         justify-content: center; gap: 1rem; background: #ffffff; color: #1e1b4b; font-family: sans-serif;",
+    ).ok()?;
+    message.set_text_content(Some("Something went wrong — reload the page to continue."));
+
+    // An empty href reloads the current page without needing script
+    let reload = document.create_element("a").ok()?;
+    reload.set_attribute("href", "").ok()?;
+    reload.set_text_content(Some("Reload"));
+    message.append_child(&reload).ok()?;
+
+    document.body()?.append_child(&message).ok()?;
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_recovery_message_is_rendered_once() {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let selector = format!("[data-test-id='{}']", RECOVERY_TEST_ID);
+
+        // What the hook does after logging a panic
+        show_recovery_message();
+        show_recovery_message();
+
+        let messages = document.query_selector_all(&selector).unwrap();
+        assert_eq!(messages.length(), 1, "Repeated panics should not stack messages");
+        let message = document.query_selector(&selector).unwrap().unwrap();
+        assert!(message.text_content().unwrap().contains("Something went wrong"));
+        assert_eq!(message.get_attribute("role").as_deref(), Some("alert"));
+
+        message.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_recovery_message_skipped_while_hook_running() {
+        let document = web_sys::window().unwrap().document().unwrap();
+
+        HANDLING_PANIC.with(|handling| handling.set(true));
+        show_recovery_message();
+        HANDLING_PANIC.with(|handling| handling.set(false));
+
+        let selector = format!("[data-test-id='{}']", RECOVERY_TEST_ID);
+        assert!(document.query_selector(&selector).unwrap().is_none(), "A re-entrant call should do nothing");
+    }
+}
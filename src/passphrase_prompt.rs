@@ -0,0 +1,142 @@
+use leptos::*;
+use leptos::prelude::*;
+use crate::data::hint_reveals_passphrase;
+use crate::theme::{use_card_class, use_paragraph_class, use_button_class};
+
+/// What the user entered in a `PassphrasePrompt`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassphraseEntry {
+    pub passphrase: String,
+    /// Reminder to store with the backup; `None` when left blank
+    pub hint: Option<String>,
+    /// Recovery code that should also open the backup; `None` when left blank
+    pub recovery_code: Option<String>,
+}
+
+/// Modal asking for a passphrase before an action continues.
+///
+/// The passphrase is handed to `on_submit` and not kept anywhere else.
+/// Test ids are derived from `test_id`, so `test_id="passphrase"` renders
+/// `passphrase-modal`, `passphrase-input`, `passphrase-submit` and
+/// `passphrase-cancel`, plus `passphrase-hint` when showing a hint,
+/// `passphrase-hint-input` when asking for one and
+/// `passphrase-recovery-input` when asking for a recovery code.
+#[component]
+pub fn PassphrasePrompt(
+    /// Explanation shown above the input
+    #[prop(into)] message: String,
+    /// Prefix used for the `data-test-id` attributes of the modal parts
+    test_id: &'static str,
+    /// Also ask for an optional hint, e.g. when protecting a new backup
+    #[prop(optional)] ask_hint: bool,
+    /// Also ask for an optional recovery code that should open the backup too
+    #[prop(optional)] ask_recovery_code: bool,
+    /// Hint to show, e.g. the one saved with the backup being opened
+    #[prop(default = None)] hint: Option<String>,
+    /// Called with the entered passphrase and hint
+    #[prop(into)] on_submit: Callback<PassphraseEntry>,
+    /// Called when the user cancels
+    #[prop(into)] on_cancel: Callback<()>,
+) -> impl IntoView {
+    let passphrase = RwSignal::new(String::new());
+    let new_hint = RwSignal::new(String::new());
+    let recovery_code = RwSignal::new(String::new());
+    // A hint that gives the passphrase away can't be submitted
+    let hint_reveals = move || hint_reveals_passphrase(&passphrase.get(), &new_hint.get());
+    let can_submit = move || !passphrase.get().is_empty() && !hint_reveals();
+
+    let submit = move || {
+        if !can_submit() {
+            return;
+        }
+        let entered_hint = new_hint.get_untracked().trim().to_string();
+        let entered_code = recovery_code.get_untracked().trim().to_string();
+        let entry = PassphraseEntry {
+            passphrase: passphrase.get_untracked(),
+            hint: (!entered_hint.is_empty()).then_some(entered_hint),
+            recovery_code: (!entered_code.is_empty()).then_some(entered_code),
+        };
+        passphrase.set(String::new());
+        new_hint.set(String::new());
+        recovery_code.set(String::new());
+        on_submit.run(entry);
+    };
+
+    view! {
+        <div
+            class="fixed inset-0 flex items-center justify-center bg-black/50"
+            role="dialog"
+            aria-modal="true"
+            data-test-id={format!("{}-modal", test_id)}
+        >
+            <div class={use_card_class()}>
+                <label class={use_paragraph_class()} for={format!("{}-input", test_id)}>{message}</label>
+                {hint.map(|hint| view! {
+                    <p data-test-id={format!("{}-hint", test_id)} class="text-sm italic mb-2">
+                        {"Hint: "}{hint}
+                    </p>
+                })}
+                <input
+                    id={format!("{}-input", test_id)}
+                    data-test-id={format!("{}-input", test_id)}
+                    type="password"
+                    autocomplete="off"
+                    class="w-full rounded border px-2 py-1 mb-4 text-gray-900"
+                    prop:value={move || passphrase.get()}
+                    on:input={move |ev| passphrase.set(event_target_value(&ev))}
+                    on:keydown={move |ev: web_sys::KeyboardEvent| if ev.key() == "Enter" { submit() }}
+                />
+                {ask_hint.then(|| view! {
+                    <label class="text-sm" for={format!("{}-hint-input", test_id)}>
+                        "Hint (optional, stored unencrypted)"
+                    </label>
+                    <input
+                        id={format!("{}-hint-input", test_id)}
+                        data-test-id={format!("{}-hint-input", test_id)}
+                        type="text"
+                        autocomplete="off"
+                        class="w-full rounded border px-2 py-1 mb-2 text-gray-900"
+                        prop:value={move || new_hint.get()}
+                        on:input={move |ev| new_hint.set(event_target_value(&ev))}
+                    />
+                    {move || hint_reveals().then(|| view! {
+                        <p data-test-id={format!("{}-hint-warning", test_id)} class="text-sm text-red-600 dark:text-red-400 mb-2">
+                            "The hint can't contain the passphrase"
+                        </p>
+                    })}
+                })}
+                {ask_recovery_code.then(|| view! {
+                    <label class="text-sm" for={format!("{}-recovery-input", test_id)}>
+                        "Recovery code (optional, also opens this backup)"
+                    </label>
+                    <input
+                        id={format!("{}-recovery-input", test_id)}
+                        data-test-id={format!("{}-recovery-input", test_id)}
+                        type="password"
+                        autocomplete="off"
+                        class="w-full rounded border px-2 py-1 mb-2 text-gray-900"
+                        prop:value={move || recovery_code.get()}
+                        on:input={move |ev| recovery_code.set(event_target_value(&ev))}
+                    />
+                })}
+                <div class="flex justify-end space-x-2">
+                    <button
+                        data-test-id={format!("{}-cancel", test_id)}
+                        class={use_button_class()}
+                        on:click={move |_| on_cancel.run(())}
+                    >
+                        "Cancel"
+                    </button>
+                    <button
+                        data-test-id={format!("{}-submit", test_id)}
+                        class={use_button_class()}
+                        disabled={move || !can_submit()}
+                        on:click={move |_| submit()}
+                    >
+                        "Continue"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,301 @@
+use leptos::*;
+use leptos::prelude::*;
+use log::error;
+use chrono::{DateTime, Duration, Utc};
+use crate::clock;
+use crate::confirm_modal::ConfirmModal;
+use crate::form_field::FormField;
+use crate::data::register_backup_key;
+use crate::theme::use_button_class;
+use crate::unsaved_changes::use_unsaved_changes;
+use crate::utils::{
+    get_storage_item, remove_storage_by_prefix, set_storage_item, subscribe_storage_changes,
+    unsubscribe_storage_changes, StorageError,
+};
+
+// Every profile key starts with this, so a profile can be deleted as a whole
+pub const PROFILE_KEY_PREFIX: &str = "profile_";
+
+// Storage key for the name shown to friends
+pub const PROFILE_NAME_KEY: &str = "profile_name";
+
+// When this browser's identity was last used, as RFC 3339
+pub const LAST_ACTIVE_KEY: &str = "profile_last_active";
+
+// Activity closer together than this doesn't rewrite `last_active`
+pub const LAST_ACTIVE_THROTTLE: Duration = Duration::minutes(1);
+
+// Include the profile name and last activity in backups
+pub fn register_backup_keys() {
+    register_backup_key(PROFILE_NAME_KEY, true);
+    register_backup_key(LAST_ACTIVE_KEY, true);
+}
+
+// Longest profile name, in characters
+pub const MAX_PROFILE_NAME_LEN: usize = 64;
+
+/// Why a profile name can't be saved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileNameError {
+    Empty,
+    TooLong(usize),
+    ControlCharacter,
+}
+
+impl std::fmt::Display for ProfileNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProfileNameError::Empty => write!(f, "Name can't be empty"),
+            ProfileNameError::TooLong(len) => write!(f, "Name is too long ({} of {} characters)", len, MAX_PROFILE_NAME_LEN),
+            ProfileNameError::ControlCharacter => write!(f, "Name can't contain control characters"),
+        }
+    }
+}
+
+/// The trimmed name, if it's 1 to `MAX_PROFILE_NAME_LEN` characters
+/// without control characters
+pub fn validate_profile_name(name: &str) -> Result<String, ProfileNameError> {
+    let name = name.trim();
+    let len = name.chars().count();
+    if len == 0 {
+        return Err(ProfileNameError::Empty);
+    }
+    if len > MAX_PROFILE_NAME_LEN {
+        return Err(ProfileNameError::TooLong(len));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(ProfileNameError::ControlCharacter);
+    }
+    Ok(name.to_string())
+}
+
+// Load the profile name, treating a missing key as empty
+pub fn load_profile_name() -> String {
+    get_storage_item(PROFILE_NAME_KEY).ok().flatten().unwrap_or_default()
+}
+
+pub fn save_profile_name(name: &str) -> Result<(), StorageError> {
+    set_storage_item(PROFILE_NAME_KEY, name.trim())
+}
+
+/// When the local identity was last active, if it ever was
+pub fn load_last_active() -> Option<DateTime<Utc>> {
+    let stored = get_storage_item(LAST_ACTIVE_KEY).ok().flatten()?;
+    DateTime::parse_from_rfc3339(&stored).ok().map(|time| time.with_timezone(&Utc))
+}
+
+/// Record activity now, unless it was already recorded within
+/// `LAST_ACTIVE_THROTTLE`. Returns whether anything was written.
+pub fn touch_last_active() -> Result<bool, StorageError> {
+    let now = clock::now();
+    if load_last_active().is_some_and(|last| now - last < LAST_ACTIVE_THROTTLE) {
+        return Ok(false);
+    }
+    set_storage_item(LAST_ACTIVE_KEY, &now.to_rfc3339())?;
+    Ok(true)
+}
+
+/// Remove everything stored for the profile, returning how many keys went
+pub fn delete_profile() -> Result<usize, StorageError> {
+    remove_storage_by_prefix(PROFILE_KEY_PREFIX)
+}
+
+/// Editable profile name; counts as unsaved until the user saves it
+#[component]
+pub fn ProfileNameEditor() -> impl IntoView {
+    let unsaved_changes = use_unsaved_changes();
+    let saved_name = RwSignal::new(load_profile_name());
+    let draft = RwSignal::new(saved_name.get_untracked());
+
+    Effect::new(move |_| {
+        unsaved_changes.set_unsaved(draft.get() != saved_name.get());
+    });
+
+    // A rename from elsewhere replaces the draft too, unless it's being edited
+    let subscription = subscribe_storage_changes(move |keys| {
+        if !keys.iter().any(|key| key == PROFILE_NAME_KEY) {
+            return;
+        }
+        let name = load_profile_name();
+        if draft.get_untracked() == saved_name.get_untracked() {
+            draft.set(name.clone());
+        }
+        saved_name.set(name);
+    });
+    on_cleanup(move || unsubscribe_storage_changes(subscription));
+
+    // Only complain once the user has changed something
+    let validation = move || validate_profile_name(&draft.get());
+    let name_error = Signal::derive(move || {
+        (draft.get() != saved_name.get())
+            .then(|| validation().err().map(|err| err.to_string()))
+            .flatten()
+    });
+    let can_save = move || draft.get() != saved_name.get() && validation().is_ok();
+
+    let (confirming_delete, set_confirming_delete) = signal(false);
+    let confirm_delete = Callback::new(move |_: ()| {
+        set_confirming_delete.set(false);
+        match delete_profile() {
+            Ok(_) => {
+                draft.set(String::new());
+                saved_name.set(String::new());
+            },
+            Err(err) => error!("Failed to delete profile: {:?}", err),
+        }
+    });
+    let cancel_delete = Callback::new(move |_: ()| set_confirming_delete.set(false));
+
+    let save = move |_| {
+        let Ok(name) = validate_profile_name(&draft.get_untracked()) else { return };
+        match save_profile_name(&name) {
+            Ok(()) => {
+                draft.set(name.clone());
+                saved_name.set(name);
+            },
+            Err(err) => error!("Failed to save profile name: {:?}", err),
+        }
+    };
+
+    view! {
+        <FormField label="Name: " input_id="profile-name-input" error={name_error}>
+            <input
+                id="profile-name-input"
+                data-test-id="profile-name-input"
+                type="text"
+                aria-invalid={move || name_error.get().is_some().to_string()}
+                prop:value={move || draft.get()}
+                on:input={move |ev| draft.set(event_target_value(&ev))}
+            />
+            <button
+                data-test-id="save-profile-name"
+                class={use_button_class()}
+                disabled={move || !can_save()}
+                on:click={save}
+            >
+                "Save"
+            </button>
+            <button
+                data-test-id="delete-profile"
+                class={use_button_class()}
+                on:click={move |_| set_confirming_delete.set(true)}
+            >
+                "Delete profile"
+            </button>
+            {move || confirming_delete.get().then(|| view! {
+                <ConfirmModal
+                    message="Delete your profile from this browser?"
+                    test_id="delete-profile"
+                    on_confirm={confirm_delete}
+                    on_cancel={cancel_delete}
+                />
+            })}
+        </FormField>
+    }
+}
+
+/// "Last active" line for the status area; `at` is the activity recorded
+/// before this visit, or `None` on a first visit
+#[component]
+pub fn LastActive(at: Option<DateTime<Utc>>) -> impl IntoView {
+    let text = match at {
+        Some(at) => format!("You were last active {}", at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")),
+        None => "This is your first visit".to_string(),
+    };
+    view! {
+        <p data-test-id="last-active" class="text-sm italic">{text}</p>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::localStorage;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_delete_profile_keeps_other_data() {
+        localStorage::reset_all_storage();
+        register_backup_keys();
+        save_profile_name("Ada").unwrap();
+        let _ = localStorage::set_storage_item("player_id", "profile_player");
+        // Another app on the origin using the same prefix
+        let _ = localStorage::set_storage_item("profile_other_app", "theirs");
+
+        assert_eq!(delete_profile().unwrap(), 1);
+        assert_eq!(load_profile_name(), "");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("profile_player".to_string()));
+        assert_eq!(localStorage::get_storage_item("profile_other_app").unwrap(), Some("theirs".to_string()), "Foreign keys should survive");
+        let _ = localStorage::reset_storage_item("profile_other_app");
+
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_last_active_writes_are_throttled() {
+        use crate::clock::{reset_clock, set_clock, FixedClock};
+        use chrono::TimeZone;
+
+        localStorage::reset_all_storage();
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        set_clock(FixedClock(start));
+        assert!(touch_last_active().unwrap(), "The first activity is recorded");
+
+        set_clock(FixedClock(start + Duration::seconds(30)));
+        assert!(!touch_last_active().unwrap(), "Activity within a minute isn't written again");
+        assert_eq!(load_last_active(), Some(start));
+
+        set_clock(FixedClock(start + Duration::minutes(2)));
+        assert!(touch_last_active().unwrap());
+        assert_eq!(load_last_active(), Some(start + Duration::minutes(2)));
+
+        reset_clock();
+        localStorage::reset_all_storage();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_profile_name_validation() {
+        assert_eq!(validate_profile_name("  Ada  "), Ok("Ada".to_string()));
+        assert_eq!(validate_profile_name("   "), Err(ProfileNameError::Empty));
+        assert_eq!(validate_profile_name(&"é".repeat(MAX_PROFILE_NAME_LEN)).map(|name| name.chars().count()), Ok(MAX_PROFILE_NAME_LEN));
+        assert_eq!(validate_profile_name(&"a".repeat(MAX_PROFILE_NAME_LEN + 1)), Err(ProfileNameError::TooLong(MAX_PROFILE_NAME_LEN + 1)));
+        assert_eq!(validate_profile_name("Ada\u{7}"), Err(ProfileNameError::ControlCharacter));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_over_long_name_blocks_save() {
+        use crate::test_utils::{click_and_wait, get_by_test_id};
+        use crate::theme::ThemeProvider;
+        use gloo_timers::future::TimeoutFuture;
+        use wasm_bindgen::JsCast;
+
+        localStorage::reset_all_storage();
+        mount_to_body(|| view! {
+            <ThemeProvider>
+                <ProfileNameEditor />
+            </ThemeProvider>
+        });
+
+        let input = get_by_test_id("profile-name-input").dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        input.set_value(&"x".repeat(MAX_PROFILE_NAME_LEN + 1));
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        TimeoutFuture::new(50).await;
+
+        let save = get_by_test_id("save-profile-name");
+        assert!(save.has_attribute("disabled"), "Save should be blocked while the name is invalid");
+        assert!(get_by_test_id("profile-name-input-error").text_content().unwrap().contains("too long"));
+        click_and_wait(&save, 50).await;
+        assert_eq!(load_profile_name(), "", "Nothing should be saved");
+
+        input.set_value("  Grace  ");
+        input.dispatch_event(&web_sys::Event::new("input").unwrap()).unwrap();
+        TimeoutFuture::new(50).await;
+        assert!(!save.has_attribute("disabled"));
+        click_and_wait(&save, 50).await;
+        assert_eq!(load_profile_name(), "Grace", "The name is trimmed when saved");
+
+        localStorage::reset_all_storage();
+    }
+}
@@ -0,0 +1,188 @@
+use leptos::*;
+use leptos::prelude::*;
+use log::error;
+use qrcode::QrCode;
+use qrcode::render::svg;
+use serde_json::json;
+use crate::clock;
+use crate::crypto::encrypt_payload;
+use crate::data::{
+    trigger_download_as, BackupEnvelope, DataError, EXPORT_VERSION, SPLIT_FORMAT,
+};
+use crate::theme::use_button_class;
+use crate::utils::{get_storage_item_required, StorageError};
+
+// Longest payload we'll put in a QR code. Version 40 holds ~2300 bytes at
+// medium error correction, but codes that dense are hard for phone cameras.
+pub const MAX_QR_PAYLOAD_LEN: usize = 1200;
+
+/// Encrypted backup holding only the player ID, small enough for a QR code.
+///
+/// It's an ordinary split backup with every other field left out, so the
+/// scanned text imports like any partial backup and leaves the rest of the
+/// receiving device's data alone.
+pub fn identity_backup() -> Result<String, DataError> {
+    let player_id = get_storage_item_required("player_id").map_err(|err| match err {
+        StorageError::NotFound(_) => DataError::MissingPlayerId,
+        err => DataError::Storage(err),
+    })?;
+
+    let app_json = json!({ "player_id": player_id }).to_string();
+    let encrypted = encrypt_payload(&app_json)?;
+    let envelope = BackupEnvelope {
+        version: EXPORT_VERSION.to_string(),
+        timestamp: clock::now().to_rfc3339(),
+        format: SPLIT_FORMAT.to_string(),
+        encrypted: true,
+        salt: None,
+        hint: None,
+        recovery: None,
+        data: serde_json::to_value(&encrypted)
+            .map_err(|err| DataError::Serialization(err.to_string()))?,
+    };
+
+    let payload = serde_json::to_string(&envelope)
+        .map_err(|err| DataError::Serialization(err.to_string()))?;
+    if payload.len() > MAX_QR_PAYLOAD_LEN {
+        return Err(DataError::TooLarge { len: payload.len(), max: MAX_QR_PAYLOAD_LEN });
+    }
+    Ok(payload)
+}
+
+/// Encode `payload` as a QR code
+pub fn qr_code(payload: &str) -> Result<QrCode, DataError> {
+    if payload.len() > MAX_QR_PAYLOAD_LEN {
+        return Err(DataError::TooLarge { len: payload.len(), max: MAX_QR_PAYLOAD_LEN });
+    }
+    QrCode::new(payload.as_bytes()).map_err(|err| DataError::Serialization(err.to_string()))
+}
+
+/// `payload` as a standalone SVG image
+pub fn qr_svg(payload: &str) -> Result<String, DataError> {
+    Ok(qr_code(payload)?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build())
+}
+
+/// Button that shows the identity backup as a QR code, with a download of
+/// the image for moving the ID to another device without a network.
+#[component]
+pub fn QrExport() -> impl IntoView {
+    let (qr_image, set_qr_image) = signal(Option::<String>::None);
+    let (qr_error, set_qr_error) = signal(Option::<String>::None);
+
+    let show_qr = move |_| {
+        match identity_backup().and_then(|payload| qr_svg(&payload)) {
+            Ok(image) => {
+                set_qr_error.set(None);
+                set_qr_image.set(Some(image));
+            },
+            Err(err) => {
+                error!("QR export failed: {}", err);
+                set_qr_image.set(None);
+                set_qr_error.set(Some(err.to_string()));
+            },
+        }
+    };
+
+    let download_qr = move |_| {
+        if let Some(image) = qr_image.get_untracked() {
+            if let Err(err) = trigger_download_as(&image, "fc_identity_qr.svg", "image/svg+xml") {
+                set_qr_error.set(Some(err.to_string()));
+            }
+        }
+    };
+
+    view! {
+        <div class="mt-2">
+            <button
+                data-test-id="qr-export-button"
+                class={use_button_class()}
+                on:click={show_qr}
+            >
+                "Show ID as QR code"
+            </button>
+            {move || qr_error.get().map(|message| view! {
+                <p data-test-id="qr-export-warning" class="text-yellow-600 dark:text-yellow-400">
+                    {message}
+                </p>
+            })}
+            {move || qr_image.get().map(|image| view! {
+                <div class="mt-2">
+                    <div data-test-id="qr-code" class="inline-block bg-white p-2" inner_html={image} />
+                    <p class="text-sm italic">
+                        "Scan on your other device and load the text as a backup"
+                    </p>
+                    <button
+                        data-test-id="qr-download-button"
+                        class={use_button_class()}
+                        on:click={download_qr}
+                    >
+                        "Download QR image"
+                    </button>
+                </div>
+            })}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::merge_import_data;
+    use crate::test_utils::test::reset_everything;
+    use crate::utils::localStorage;
+    use qrcode::Color;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    // Read a QR code back the way a scanner would, from its pixels
+    fn scan(code: &QrCode) -> String {
+        const QUIET_ZONE: usize = 4;
+        const SCALE: usize = 4;
+        let width = code.width();
+        let colors = code.to_colors();
+        let size = (width + 2 * QUIET_ZONE) * SCALE;
+        let mut image = rqrr::PreparedImage::prepare_from_greyscale(size, size, |x, y| {
+            let (x, y) = (x / SCALE, y / SCALE);
+            let inside = (QUIET_ZONE..QUIET_ZONE + width).contains(&x)
+                && (QUIET_ZONE..QUIET_ZONE + width).contains(&y);
+            if inside && colors[(y - QUIET_ZONE) * width + (x - QUIET_ZONE)] == Color::Dark { 0 } else { 255 }
+        });
+        let grids = image.detect_grids();
+        assert_eq!(grids.len(), 1, "Expected one QR code in the image");
+        grids[0].decode().expect("QR code should decode").1
+    }
+
+    #[wasm_bindgen_test]
+    fn test_identity_qr_round_trips_through_import() {
+        reset_everything();
+        localStorage::set_storage_item("player_id", "qr-player-1").unwrap();
+
+        let payload = identity_backup().expect("Identity backup should fit in a QR code");
+        assert!(payload.len() <= MAX_QR_PAYLOAD_LEN);
+        assert!(!payload.contains("qr-player-1"), "The player ID must be encrypted");
+        assert!(qr_svg(&payload).unwrap().starts_with("<?xml"));
+
+        let scanned = scan(&qr_code(&payload).unwrap());
+        assert_eq!(scanned, payload);
+
+        localStorage::set_storage_item("player_id", "someone-else").unwrap();
+        merge_import_data(&scanned).expect("Scanned payload should import");
+        assert_eq!(localStorage::get_storage_item("player_id").unwrap(), Some("qr-player-1".to_string()));
+
+        reset_everything();
+    }
+
+    #[wasm_bindgen_test]
+    fn test_oversized_payload_is_refused() {
+        let payload = "x".repeat(MAX_QR_PAYLOAD_LEN + 1);
+        let result = qr_svg(&payload);
+        assert!(
+            matches!(result, Err(DataError::TooLarge { len, max }) if len == MAX_QR_PAYLOAD_LEN + 1 && max == MAX_QR_PAYLOAD_LEN),
+            "{:?}", result
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use leptos::*;
+use leptos::prelude::*;
+use log::error;
+use std::time::Duration;
+use crate::utils::{storage_estimate, subscribe_storage_changes, unsubscribe_storage_changes, StorageEstimate};
+
+// Share of the quota in use past which the warning shows
+pub const QUOTA_WARNING_THRESHOLD: f64 = 0.8;
+
+// How often usage is rechecked, for writes that bypass the storage helpers
+// such as other tabs or other apps on the origin
+pub const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Warning text for `estimate`, or `None` while usage is under the threshold
+pub fn quota_warning(estimate: &StorageEstimate) -> Option<String> {
+    (estimate.fraction() >= QUOTA_WARNING_THRESHOLD).then(|| format!(
+        "Storage is {}% full. Export a backup and clear old data before saves start failing.",
+        (estimate.fraction() * 100.0).round().min(100.0) as u32,
+    ))
+}
+
+/// Banner shown as storage nears its quota, so a full store is caught
+/// before an operation fails on it. Checked on mount, after every write,
+/// and every `QUOTA_CHECK_INTERVAL`.
+#[component]
+pub fn QuotaWarning() -> impl IntoView {
+    let (warning, set_warning) = signal(Option::<String>::None);
+    let check = move || match storage_estimate() {
+        Ok(estimate) => set_warning.set(quota_warning(&estimate)),
+        Err(err) => error!("Failed to estimate storage use: {:?}", err),
+    };
+    check();
+
+    let subscription = subscribe_storage_changes(move |_| check());
+    let interval = set_interval_with_handle(check, QUOTA_CHECK_INTERVAL)
+        .inspect_err(|err| error!("Failed to schedule storage checks: {:?}", err))
+        .ok();
+    on_cleanup(move || {
+        unsubscribe_storage_changes(subscription);
+        if let Some(interval) = interval {
+            interval.clear();
+        }
+    });
+
+    view! {
+        {move || warning.get().map(|message| view! {
+            <div
+                data-test-id="quota-warning"
+                role="alert"
+                class="mt-4 rounded border border-yellow-500 p-2 text-yellow-700 dark:text-yellow-300"
+            >
+                {message}
+            </div>
+        })}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_by_test_id, reset_everything};
+    use crate::utils::set_mock_storage_estimate;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_warning_appears_past_threshold() {
+        use gloo_timers::future::TimeoutFuture;
+
+        reset_everything();
+        set_mock_storage_estimate(Some(StorageEstimate { usage: 100, quota: 1000 }));
+        mount_to_body(|| view! { <QuotaWarning /> });
+        let document = web_sys::window().unwrap().document().unwrap();
+        assert!(document.query_selector("[data-test-id='quota-warning']").unwrap().is_none(), "10% full needs no warning");
+
+        // The next write rechecks usage
+        set_mock_storage_estimate(Some(StorageEstimate { usage: 850, quota: 1000 }));
+        crate::utils::set_storage_item("quota_test_key", "x").unwrap();
+        TimeoutFuture::new(50).await;
+        let banner = get_by_test_id("quota-warning");
+        assert!(banner.text_content().unwrap().starts_with("Storage is 85% full"), "{:?}", banner.text_content());
+
+        reset_everything();
+        let _ = crate::utils::localStorage::reset_storage_item("quota_test_key");
+    }
+}
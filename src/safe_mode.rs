@@ -0,0 +1,48 @@
+use std::cell::Cell;
+use crate::utils::query_param;
+
+// Query parameter that turns safe mode on, e.g. "?safe=1"
+pub const SAFE_MODE_PARAM: &str = "safe";
+
+thread_local! {
+    static SAFE_MODE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run with optional subsystems off: exports are plaintext, the clipboard,
+/// invite links and cross-tab sync are unused. Meant for narrowing down
+/// bugs, so it's set before the app mounts and left alone after.
+pub fn set_safe_mode(on: bool) {
+    SAFE_MODE.with(|current| current.set(on));
+}
+
+pub fn is_safe_mode() -> bool {
+    SAFE_MODE.with(|current| current.get())
+}
+
+// Whether a query string like "?safe=1" asks for safe mode
+pub fn parse_safe_mode_param(search: &str) -> bool {
+    matches!(query_param(search, SAFE_MODE_PARAM).as_deref(), Some("1" | "true"))
+}
+
+// Helper function to check the page URL for the safe mode parameter
+pub fn safe_mode_requested() -> bool {
+    web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .is_some_and(|search| parse_safe_mode_param(&search))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_safe_mode_param_parsing() {
+        assert!(parse_safe_mode_param("?safe=1"));
+        assert!(parse_safe_mode_param("?panel=data&safe=true"));
+        assert!(!parse_safe_mode_param("?safe=0"));
+        assert!(!parse_safe_mode_param("?panel=data"));
+    }
+}
@@ -0,0 +1,174 @@
+use leptos::task::spawn_local;
+use log::{error, info};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+// The cache sw.js serves from; keep in step with CACHE there
+const CACHE_NAME: &str = "friends-connect-v1";
+
+/// Register the service worker at `path` so the app and its WASM bundle
+/// keep working offline. Does nothing where service workers aren't
+/// supported, e.g. on insecure origins. Returns whether registration
+/// started; its outcome is logged.
+pub fn register_service_worker(path: &str) -> bool {
+    let Some(window) = web_sys::window() else { return false };
+    let started = register_with(&window.navigator(), path);
+    if started {
+        cache_bundle();
+    }
+    started
+}
+
+/// Add the WASM bundle and JS glue this page loaded to the offline cache.
+/// They are fetched before the worker is installed, so without this the
+/// first visit would leave only the app shell cached.
+pub fn cache_bundle() {
+    let urls = bundle_urls();
+    if urls.is_empty() {
+        return;
+    }
+    spawn_local(async move {
+        match cache_urls(&urls).await {
+            Ok(()) => info!("Cached {} bundle files for offline use", urls.len()),
+            Err(err) => error!("Caching the app bundle failed: {:?}", err),
+        }
+    });
+}
+
+// Same-origin `.wasm` and `.js` resources the page has loaded. Trunk hashes
+// their filenames, so they are read from the page rather than hard-coded.
+fn bundle_urls() -> Vec<String> {
+    let Some(window) = web_sys::window() else { return Vec::new() };
+    let Ok(origin) = window.location().origin() else { return Vec::new() };
+    let entries = js_sys::Reflect::get(&window, &JsValue::from_str("performance"))
+        .ok()
+        .and_then(|performance| {
+            let get = js_sys::Reflect::get(&performance, &JsValue::from_str("getEntriesByType"))
+                .ok()?
+                .dyn_into::<js_sys::Function>()
+                .ok()?;
+            get.call1(&performance, &JsValue::from_str("resource")).ok()
+        })
+        .and_then(|entries| entries.dyn_into::<js_sys::Array>().ok())
+        .unwrap_or_default();
+
+    let mut urls: Vec<String> = entries
+        .iter()
+        .filter_map(|entry| js_sys::Reflect::get(&entry, &JsValue::from_str("name")).ok()?.as_string())
+        .filter(|url| is_bundle_url(url, &origin))
+        .collect();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+fn is_bundle_url(url: &str, origin: &str) -> bool {
+    let Some(path) = url.strip_prefix(origin).filter(|rest| rest.starts_with('/')) else {
+        return false;
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    path.ends_with(".wasm") || path.ends_with(".js")
+}
+
+// Open the worker's cache and add `urls` to it
+async fn cache_urls(urls: &[String]) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
+    let caches = js_sys::Reflect::get(&window, &JsValue::from_str("caches"))?;
+    if !caches.is_object() {
+        return Err(JsValue::from_str("The Cache API is not available"));
+    }
+    let cache = call_method(&caches, "open", &JsValue::from_str(CACHE_NAME)).await?;
+    let urls: js_sys::Array = urls.iter().map(|url| JsValue::from_str(url)).collect();
+    call_method(&cache, "addAll", &urls).await?;
+    Ok(())
+}
+
+// Call a promise-returning method on `target` and await its result
+async fn call_method(target: &JsValue, name: &str, arg: &JsValue) -> Result<JsValue, JsValue> {
+    let method = js_sys::Reflect::get(target, &JsValue::from_str(name))?.dyn_into::<js_sys::Function>()?;
+    let promise = method.call1(target, arg)?.dyn_into::<js_sys::Promise>()?;
+    JsFuture::from(promise).await
+}
+
+// Register through `navigator`, which may lack `serviceWorker`
+fn register_with(navigator: &JsValue, path: &str) -> bool {
+    let Some(container) = js_sys::Reflect::get(navigator, &JsValue::from_str("serviceWorker"))
+        .ok()
+        .filter(|container| container.is_object())
+    else {
+        info!("Service workers are not supported; offline caching is off");
+        return false;
+    };
+    let Some(register) = js_sys::Reflect::get(&container, &JsValue::from_str("register"))
+        .ok()
+        .and_then(|register| register.dyn_into::<js_sys::Function>().ok())
+    else {
+        info!("Service worker registration is not available; offline caching is off");
+        return false;
+    };
+
+    let promise = match register.call1(&container, &JsValue::from_str(path)) {
+        Ok(promise) => promise.dyn_into::<js_sys::Promise>(),
+        Err(err) => {
+            error!("Service worker registration failed: {:?}", err);
+            return false;
+        }
+    };
+    let Ok(promise) = promise else {
+        error!("Service worker registration did not return a promise");
+        return false;
+    };
+
+    let path = path.to_string();
+    spawn_local(async move {
+        match JsFuture::from(promise).await {
+            Ok(_) => info!("Service worker registered from {}", path),
+            Err(err) => error!("Service worker registration failed: {:?}", err),
+        }
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_missing_service_worker_support_is_a_no_op() {
+        let navigator = js_sys::Object::new();
+        assert!(!register_with(&navigator, "./sw.js"), "Nothing to register with");
+
+        // `serviceWorker` present but unusable
+        js_sys::Reflect::set(&navigator, &JsValue::from_str("serviceWorker"), &js_sys::Object::new()).unwrap();
+        assert!(!register_with(&navigator, "./sw.js"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bundle_urls_are_same_origin_wasm_and_js() {
+        let origin = "http://127.0.0.1:8080";
+        assert!(is_bundle_url("http://127.0.0.1:8080/app-1a2b_bg.wasm", origin));
+        assert!(is_bundle_url("http://127.0.0.1:8080/app-1a2b.js?v=2", origin));
+        assert!(!is_bundle_url("http://127.0.0.1:8080/tailwind-9f8e.css", origin));
+        assert!(!is_bundle_url("https://cdn.example.com/app.js", origin));
+        assert!(!is_bundle_url("http://127.0.0.1:80801/app.js", origin), "Different port");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_loaded_bundle_ends_up_cached() {
+        let urls = bundle_urls();
+        assert!(urls.iter().any(|url| url.ends_with(".wasm")), "The test page loaded a WASM bundle: {:?}", urls);
+
+        cache_urls(&urls).await.expect("Bundle should be cached");
+
+        let window = web_sys::window().unwrap();
+        let caches = js_sys::Reflect::get(&window, &JsValue::from_str("caches")).unwrap();
+        let cache = call_method(&caches, "open", &JsValue::from_str(CACHE_NAME)).await.unwrap();
+        for url in &urls {
+            let cached = call_method(&cache, "match", &JsValue::from_str(url)).await.unwrap();
+            assert!(!cached.is_undefined(), "{} should be in the offline cache", url);
+        }
+    }
+}
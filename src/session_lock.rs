@@ -0,0 +1,145 @@
+use leptos::*;
+use leptos::prelude::*;
+use log::{error, info};
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crate::clock;
+use crate::crypto::PassphraseKey;
+use crate::utils::{get_storage_item, remove_storage_item, set_storage_item, StorageError};
+
+// How often the idle time is checked while auto-lock is on
+pub const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Storage key for the opt-in auto-lock setting, in seconds
+pub const AUTO_LOCK_KEY: &str = "auto_lock_seconds";
+
+// Idle time offered by the data panel's auto-lock toggle
+pub const DEFAULT_AUTO_LOCK_AFTER: Duration = Duration::from_secs(5 * 60);
+
+thread_local! {
+    // Passphrase key derived this session, wiped from memory when dropped
+    static SESSION_KEY: RefCell<Option<PassphraseKey>> = const { RefCell::new(None) };
+    static LAST_ACTIVITY: Cell<Option<DateTime<Utc>>> = const { Cell::new(None) };
+}
+
+/// Opt in to keeping the key derived from an entered passphrase for the
+/// session until it has been idle for `after`. With `None`, the default,
+/// the passphrase is asked for on every operation and nothing is kept.
+pub fn set_auto_lock_after(after: Option<Duration>) -> Result<(), StorageError> {
+    match after {
+        Some(after) => set_storage_item(AUTO_LOCK_KEY, &after.as_secs().to_string()),
+        None => {
+            lock_session();
+            remove_storage_item(AUTO_LOCK_KEY)
+        }
+    }
+}
+
+// Idle time after which the session locks; `None` keeps nothing unlocked
+pub fn auto_lock_after() -> Option<Duration> {
+    get_storage_item(AUTO_LOCK_KEY)
+        .ok()
+        .flatten()
+        .and_then(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Keep `key` for later operations, when auto-lock is on
+pub fn unlock_session(key: PassphraseKey) {
+    if auto_lock_after().is_none() {
+        return;
+    }
+    SESSION_KEY.with(|current| *current.borrow_mut() = Some(key));
+    note_activity();
+}
+
+/// Forget the session key, zeroizing its memory
+pub fn lock_session() {
+    let was_unlocked = SESSION_KEY.with(|current| current.borrow_mut().take()).is_some();
+    if was_unlocked {
+        info!("Session locked; the passphrase will be asked for again");
+    }
+}
+
+/// Key kept from earlier in the session, unless it has idled out
+pub fn session_key() -> Option<PassphraseKey> {
+    lock_if_idle();
+    SESSION_KEY.with(|current| current.borrow().clone())
+}
+
+// Restart the idle timer
+pub fn note_activity() {
+    LAST_ACTIVITY.with(|last| last.set(Some(clock::now())));
+}
+
+/// Lock the session once it has been idle past the limit.
+/// Returns whether it was locked by this call.
+pub fn lock_if_idle() -> bool {
+    let unlocked = SESSION_KEY.with(|current| current.borrow().is_some());
+    let (Some(limit), Some(last)) = (auto_lock_after(), LAST_ACTIVITY.with(Cell::get)) else {
+        return false;
+    };
+    let idle = (clock::now() - last).to_std().unwrap_or_default();
+    if !unlocked || idle < limit {
+        return false;
+    }
+    lock_session();
+    true
+}
+
+/// Reset the idle timer on clicks and key presses, and check for an idle
+/// session every `AUTO_LOCK_CHECK_INTERVAL`
+pub fn use_idle_auto_lock() {
+    let on_click = window_event_listener(ev::click, |_| note_activity());
+    let on_key = window_event_listener(ev::keydown, |_| note_activity());
+    let interval = set_interval_with_handle(|| { lock_if_idle(); }, AUTO_LOCK_CHECK_INTERVAL)
+        .inspect_err(|err| error!("Failed to schedule the auto-lock check: {:?}", err))
+        .ok();
+    on_cleanup(move || {
+        on_click.remove();
+        on_key.remove();
+        if let Some(interval) = interval {
+            interval.clear();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{set_clock, FixedClock};
+    use crate::test_utils::reset_everything;
+    use chrono::TimeZone;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn test_session_locks_after_idle_limit() {
+        reset_everything();
+        let start = Utc.with_ymd_and_hms(2025, 4, 9, 12, 0, 0).unwrap();
+        set_clock(FixedClock(start));
+
+        let key = PassphraseKey::derive("kept for now", &[1u8; 16]);
+        unlock_session(key.clone());
+        assert!(session_key().is_none(), "Nothing is kept unless auto-lock is on");
+
+        set_auto_lock_after(Some(Duration::from_secs(300))).unwrap();
+        assert_eq!(auto_lock_after(), Some(Duration::from_secs(300)), "The setting is stored");
+        unlock_session(key.clone());
+        set_clock(FixedClock(start + chrono::Duration::minutes(4)));
+        assert_eq!(session_key(), Some(key), "The derived key is kept, not the passphrase");
+
+        // Activity restarts the timer
+        note_activity();
+        set_clock(FixedClock(start + chrono::Duration::minutes(8)));
+        assert!(!lock_if_idle());
+
+        set_clock(FixedClock(start + chrono::Duration::minutes(10)));
+        assert!(lock_if_idle(), "Five idle minutes should lock the session");
+        assert!(session_key().is_none());
+
+        reset_everything();
+    }
+}
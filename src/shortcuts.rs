@@ -0,0 +1,107 @@
+use leptos::*;
+use leptos::prelude::*;
+use leptos::ev;
+use std::time::Duration;
+use wasm_bindgen::JsCast;
+use crate::panel::{use_panel_nav, PanelId};
+use crate::theme::{use_theme, use_card_class, use_header_class, use_paragraph_class};
+
+/// What a keyboard shortcut does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortcutAction {
+    ToggleTheme,
+    FocusFriends,
+    ShowHelp,
+}
+
+/// A key and the action it triggers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    /// `KeyboardEvent.key` value that triggers it
+    pub key: &'static str,
+    pub description: &'static str,
+    pub action: ShortcutAction,
+}
+
+/// Every app-wide shortcut; the help overlay is built from this table
+pub const SHORTCUTS: &[Shortcut] = &[
+    Shortcut { key: "t", description: "Toggle light/dark theme", action: ShortcutAction::ToggleTheme },
+    Shortcut { key: "f", description: "Open the friends list", action: ShortcutAction::FocusFriends },
+    Shortcut { key: "?", description: "Show keyboard shortcuts", action: ShortcutAction::ShowHelp },
+];
+
+pub fn shortcut_for_key(key: &str) -> Option<&'static Shortcut> {
+    SHORTCUTS.iter().find(|shortcut| shortcut.key == key)
+}
+
+// Keys typed into a form field are text, not shortcuts
+fn is_typing(ev: &web_sys::KeyboardEvent) -> bool {
+    ev.target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|element| matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"))
+}
+
+fn focus_friends_list() {
+    let friends_list = web_sys::window()
+        .and_then(|win| win.document())
+        .and_then(|doc| doc.query_selector("[data-test-id='friends-list']").ok().flatten())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok());
+    if let Some(friends_list) = friends_list {
+        let _ = friends_list.focus();
+    }
+}
+
+/// Handles the app-wide keyboard shortcuts and shows their help overlay
+#[component]
+pub fn KeyboardShortcuts() -> impl IntoView {
+    let theme = use_theme();
+    let nav = use_panel_nav();
+    let (show_help, set_show_help) = signal(false);
+
+    let handle = window_event_listener(ev::keydown, move |ev: web_sys::KeyboardEvent| {
+        if ev.ctrl_key() || ev.meta_key() || ev.alt_key() || is_typing(&ev) {
+            return;
+        }
+        if ev.key() == "Escape" {
+            set_show_help.set(false);
+            return;
+        }
+        let Some(shortcut) = shortcut_for_key(&ev.key()) else { return };
+        match shortcut.action {
+            ShortcutAction::ToggleTheme => {
+                theme.toggle_theme.dispatch(());
+            },
+            ShortcutAction::FocusFriends => {
+                nav.open(PanelId::Friends);
+                // Focus once the panel has rendered
+                set_timeout(focus_friends_list, Duration::ZERO);
+            },
+            ShortcutAction::ShowHelp => set_show_help.set(true),
+        }
+    });
+    on_cleanup(move || handle.remove());
+
+    move || show_help.get().then(|| view! {
+        <div
+            class="fixed inset-0 flex items-center justify-center bg-black/50"
+            role="dialog"
+            aria-modal="true"
+            aria-label="Keyboard shortcuts"
+            data-test-id="shortcuts-help"
+            on:click={move |_| set_show_help.set(false)}
+        >
+            <div class={use_card_class()}>
+                <h2 class={use_header_class()}>"Keyboard shortcuts"</h2>
+                <ul class={use_paragraph_class()}>
+                    {SHORTCUTS.iter().map(|shortcut| view! {
+                        <li data-test-id={format!("shortcut-{}", shortcut.key)}>
+                            <kbd class="px-2 py-1 rounded border font-mono">{shortcut.key}</kbd>
+                            " "{shortcut.description}
+                        </li>
+                    }).collect_view()}
+                </ul>
+                <p class="text-sm italic">"Press Escape to close"</p>
+            </div>
+        </div>
+    })
+}
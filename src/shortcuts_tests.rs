@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod shortcuts_tests {
+    use leptos::*;
+    use leptos::prelude::*;
+    use wasm_bindgen_test::*;
+    use gloo_timers::future::TimeoutFuture;
+    use crate::test_utils::test::*;
+    use crate::theme::ThemeProvider;
+    use crate::shortcuts::{KeyboardShortcuts, SHORTCUTS};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    async fn press_key(key: &str) {
+        let init = web_sys::KeyboardEventInit::new();
+        init.set_key(key);
+        init.set_bubbles(true);
+        let event = web_sys::KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap();
+        let body = web_sys::window().unwrap().document().unwrap().body().unwrap();
+        body.dispatch_event(&event).unwrap();
+        TimeoutFuture::new(50).await;
+    }
+
+    fn help_is_open() -> bool {
+        let document = web_sys::window().unwrap().document().unwrap();
+        document.query_selector("[data-test-id='shortcuts-help']").unwrap().is_some()
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_question_mark_opens_shortcut_help() {
+        mount_to_body(|| view! { <ThemeProvider><KeyboardShortcuts /></ThemeProvider> });
+        assert!(!help_is_open(), "Help should start hidden");
+
+        press_key("?").await;
+        assert!(help_is_open(), "Pressing ? should open the help overlay");
+
+        // Every registered shortcut is listed
+        for shortcut in SHORTCUTS {
+            let entry = get_by_test_id(&format!("shortcut-{}", shortcut.key));
+            assert!(entry.text_content().unwrap().contains(shortcut.description), "{} should be listed", shortcut.key);
+        }
+        assert!(get_by_test_id("shortcut-t").text_content().unwrap().contains("theme"));
+
+        press_key("Escape").await;
+        assert!(!help_is_open(), "Escape should close the overlay");
+    }
+}
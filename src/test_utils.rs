@@ -1,6 +1,8 @@
 #[cfg(test)]
 pub(crate) mod test {
+    use leptos::prelude::*;
     use web_sys::wasm_bindgen::JsCast;
+    use crate::theme::ThemeProvider;
     use gloo_timers::future::TimeoutFuture;
     use std::path::Path;
     use std::fs;
@@ -12,6 +14,42 @@ pub(crate) mod test {
             .expect(&format!("Element with data-test-id='{}' not found", test_id))
     }
     
+    /// Put storage and every test override (clock, RNG, clipboard, limits,
+    /// forced errors) back to their defaults, for a clean test start
+    pub fn reset_everything() {
+        crate::utils::set_force_storage_error(None);
+        crate::utils::set_dropped_write_key(None);
+        crate::utils::set_failing_write_key(None);
+        crate::utils::localStorage::reset_all_storage();
+        crate::clock::reset_clock();
+        crate::crypto::reset_random_source();
+        crate::copy_button::reset_clipboard_writer();
+        crate::friends::set_max_friends(crate::friends::DEFAULT_MAX_FRIENDS);
+        crate::data::set_operation_timeout(crate::data::DEFAULT_OPERATION_TIMEOUT);
+        crate::data::set_large_import_threshold(crate::data::DEFAULT_LARGE_IMPORT_THRESHOLD);
+        crate::data::set_export_tamper_hook(None);
+        crate::safe_mode::set_safe_mode(false);
+        crate::crypto::set_encryption_key(None);
+        crate::invite::set_simulated_connections(false);
+        crate::utils::set_mock_storage_estimate(None);
+        crate::session_lock::lock_session();
+    }
+    
+    /// Reset the theme preference to `initial_dark` and mount `children`
+    /// inside a `ThemeProvider`
+    pub fn mount_with_theme<F, V>(initial_dark: bool, children: F)
+    where
+        F: FnOnce() -> V + Send + Sync + 'static,
+        V: IntoView + 'static,
+    {
+        crate::utils::localStorage::reset_theme_storage();
+        crate::utils::localStorage::set_storage_item("dark_mode", if initial_dark { "true" } else { "false" })
+            .expect("Should seed the dark mode preference");
+        mount_to_body(move || view! {
+            <ThemeProvider>{children()}</ThemeProvider>
+        });
+    }
+    
     pub async fn click_and_wait(element: &web_sys::Element, timeout_ms: u32) {
         let event = web_sys::MouseEvent::new("click").unwrap();
         element.dispatch_event(&event).unwrap();
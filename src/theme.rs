@@ -1,53 +1,465 @@
 use leptos::*;
 use leptos::prelude::*;
 use log::{error, info};
-use crate::utils::{get_dark_mode_preference, save_dark_mode_preference};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::Closure;
+use crate::utils::{get_dark_mode_preference, get_storage_item, listen_cross_tab_storage, save_dark_mode_preference, set_storage_item_verified, StorageError};
+use crate::data::register_backup_key;
+use crate::event_bus::{use_event_bus, AppEvent};
+
+/// The themes the app knows about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Follow the operating system's color scheme
+    System,
+    HighContrast,
+}
+
+/// A theme name that doesn't match any `Theme`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThemeParseError(pub String);
+
+impl fmt::Display for ThemeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown theme: {}", self.0)
+    }
+}
+
+impl std::error::Error for ThemeParseError {}
+
+/// Color tokens describing a theme, used for previews
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThemeVars {
+    pub background: &'static str,
+    pub surface: &'static str,
+    pub accent: &'static str,
+    pub text: &'static str,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Light, Theme::Dark, Theme::System, Theme::HighContrast];
+    
+    /// Stable lowercase name used in storage, URLs and exports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::System => "system",
+            Theme::HighContrast => "high-contrast",
+        }
+    }
+    
+    /// Whether the theme's own palette is dark. `System` counts as light
+    /// here, which keeps the stored `dark_mode` flag stable; use
+    /// `effective_dark` for what is actually shown.
+    pub fn is_dark(&self) -> bool {
+        matches!(self, Theme::Dark | Theme::HighContrast)
+    }
+    
+    pub fn from_dark_mode(is_dark: bool) -> Self {
+        if is_dark { Theme::Dark } else { Theme::Light }
+    }
+    
+    pub fn vars(&self) -> ThemeVars {
+        match self {
+            Theme::Light => ThemeVars {
+                background: "#eef2ff",
+                surface: "#ffffff",
+                accent: "#4f46e5",
+                text: "#1e1b4b",
+            },
+            Theme::Dark => ThemeVars {
+                background: "#111827",
+                surface: "#1f2937",
+                accent: "#8b5cf6",
+                text: "#f3f4f6",
+            },
+            // Previewed as light until the system preference is known
+            Theme::System => Theme::Light.vars(),
+            Theme::HighContrast => ThemeVars {
+                background: "#000000",
+                surface: "#000000",
+                accent: "#ffff00",
+                text: "#ffffff",
+            },
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ThemeParseError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.name() == s)
+            .ok_or_else(|| ThemeParseError(s.to_string()))
+    }
+}
+
+// Media query matching the operating system's dark color scheme
+const PREFERS_DARK_QUERY: &str = "(prefers-color-scheme: dark)";
+
+fn prefers_dark_query() -> Option<web_sys::MediaQueryList> {
+    web_sys::window()?.match_media(PREFERS_DARK_QUERY).ok().flatten()
+}
+
+/// The theme shown for `theme`: `System` becomes light or dark following
+/// the operating system's color scheme, the rest show as themselves
+pub fn effective_theme(theme: Theme) -> Theme {
+    match theme {
+        Theme::System => Theme::from_dark_mode(prefers_dark_query().is_some_and(|query| query.matches())),
+        theme => theme,
+    }
+}
+
+/// Whether `theme` is shown dark right now
+pub fn effective_dark(theme: Theme) -> bool {
+    effective_theme(theme).is_dark()
+}
+
+// Storage key for the theme preference
+pub const THEME_KEY: &str = "theme";
+
+/// The theme a stored theme name and dark mode flag describe.
+///
+/// The name wins unless the flag disagrees with it, which means the mode was
+/// written without it (an older version or backup); then the theme is
+/// migrated from the flag.
+pub fn resolve_theme(name: Option<&str>, is_dark: bool) -> Theme {
+    name.and_then(|name| name.parse::<Theme>().ok())
+        .filter(|theme| theme.is_dark() == is_dark)
+        .unwrap_or_else(|| Theme::from_dark_mode(is_dark))
+}
+
+// Load the theme preference, migrating from `dark_mode` when needed
+pub fn load_theme() -> Theme {
+    let is_dark = matches!(get_storage_item("dark_mode"), Ok(Some(value)) if value == "true");
+    resolve_theme(get_storage_item(THEME_KEY).ok().flatten().as_deref(), is_dark)
+}
+
+/// Save the theme name, and `dark_mode` alongside it for older versions
+pub fn save_theme(theme: Theme) -> Result<(), StorageError> {
+    set_storage_item_verified(THEME_KEY, theme.name())?;
+    save_dark_mode_preference(theme.is_dark())
+}
+
+// Storage key for the accent color preference
+pub const ACCENT_KEY: &str = "accent";
+
+/// Accent colors a user can pick in place of the theme's own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Accent {
+    Indigo,
+    Purple,
+    Teal,
+    Rose,
+}
+
+impl Accent {
+    pub const ALL: [Accent; 4] = [Accent::Indigo, Accent::Purple, Accent::Teal, Accent::Rose];
+    
+    /// Stable lowercase name used in storage and exports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Accent::Indigo => "indigo",
+            Accent::Purple => "purple",
+            Accent::Teal => "teal",
+            Accent::Rose => "rose",
+        }
+    }
+    
+    pub fn hex(&self) -> &'static str {
+        match self {
+            Accent::Indigo => "#4f46e5",
+            Accent::Purple => "#8b5cf6",
+            Accent::Teal => "#0d9488",
+            Accent::Rose => "#e11d48",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        Accent::ALL.into_iter().find(|accent| accent.name() == name)
+    }
+}
+
+impl ThemeVars {
+    /// These tokens with the accent replaced, if one is chosen
+    pub fn with_accent(self, accent: Option<Accent>) -> Self {
+        match accent {
+            Some(accent) => ThemeVars { accent: accent.hex(), ..self },
+            None => self,
+        }
+    }
+}
+
+// Include the theme and accent preferences in backups
+pub fn register_backup_keys() {
+    register_backup_key(THEME_KEY, true);
+    register_backup_key(ACCENT_KEY, true);
+}
+
+// Load the accent preference; unknown or missing values mean the theme's default
+pub fn load_accent() -> Option<Accent> {
+    get_storage_item(ACCENT_KEY).ok().flatten().and_then(|name| Accent::from_name(&name))
+}
+
+pub fn save_accent(accent: Accent) -> Result<(), StorageError> {
+    set_storage_item_verified(ACCENT_KEY, accent.name())
+}
+
+// WCAG AA minimum contrast for normal text
+pub const MIN_TEXT_CONTRAST: f64 = 4.5;
+
+/// A pair of theme colors that's hard to read together
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContrastWarning {
+    /// Names of the two tokens, e.g. `("text", "background")`
+    pub tokens: (&'static str, &'static str),
+    /// Contrast ratio, from 1.0 (same color) to 21.0 (black on white)
+    pub ratio: f64,
+}
+
+// Parse a "#rrggbb" or "#rgb" color into its channels
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#')?;
+    let expanded: String = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(expanded.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// WCAG relative luminance of an sRGB color
+fn relative_luminance([r, g, b]: [u8; 3]) -> f64 {
+    let linear = |channel: u8| {
+        let c = channel as f64 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+/// WCAG contrast ratio between two hex colors, or `None` if either isn't one
+pub fn contrast_ratio(first: &str, second: &str) -> Option<f64> {
+    let first = relative_luminance(parse_hex_color(first)?);
+    let second = relative_luminance(parse_hex_color(second)?);
+    let (lighter, darker) = if first > second { (first, second) } else { (second, first) };
+    Some((lighter + 0.05) / (darker + 0.05))
+}
+
+/// Text/background token pairs whose contrast is below WCAG AA.
+///
+/// Colors that aren't hex values can't be checked and are skipped.
+pub fn validate_contrast(vars: &ThemeVars) -> Vec<ContrastWarning> {
+    [
+        (("text", "background"), vars.text, vars.background),
+        (("text", "surface"), vars.text, vars.surface),
+    ]
+    .into_iter()
+    .filter_map(|(tokens, foreground, background)| {
+        let ratio = contrast_ratio(foreground, background)?;
+        (ratio < MIN_TEXT_CONTRAST).then_some(ContrastWarning { tokens, ratio })
+    })
+    .collect()
+}
 
 // Define our theme context
 #[derive(Copy, Clone)]
 pub struct ThemeState {
-    pub dark_mode: ReadSignal<bool>,
+    /// Whether the shown theme is dark, following the system for `System`
+    pub dark_mode: Signal<bool>,
+    /// The active theme; `dark_mode` follows it
+    pub theme: Signal<Theme>,
     pub toggle_theme: Action<(), ()>,
+    /// Switch to a specific theme and save it
+    pub set_theme: Action<Theme, ()>,
+    /// Set while a programmatic theme change (import, reset) is being applied
+    pub transition_lock: RwSignal<bool>,
+    /// Chosen accent, or `None` for the theme's own
+    pub accent: RwSignal<Option<Accent>>,
+    /// Color tokens of the active theme with the accent applied
+    pub vars: Signal<ThemeVars>,
+    current_theme: RwSignal<Theme>,
+    // The active theme with `System` resolved
+    shown_theme: Signal<Theme>,
+    // Whether theme changes are written to storage
+    persist: bool,
 }
 
-pub fn provide_theme() -> ThemeState {
-    // Create a signal to track dark mode state, initialized from localStorage
-    let (dark_mode, set_dark_mode) = create_signal(get_dark_mode_preference());
+impl ThemeState {
+    /// Switch the accent color and save it
+    pub fn set_accent(&self, accent: Accent) {
+        self.accent.set(Some(accent));
+        if self.persist {
+            if let Err(err) = save_accent(accent) {
+                error!("Failed to save accent preference: {:?}", err);
+            }
+        }
+    }
+    
+    // Update the theme, and the dark mode that follows it, without saving
+    fn show_theme(&self, theme: Theme) {
+        self.current_theme.set(theme);
+    }
+    
+    /// Apply a theme chosen programmatically rather than by the user.
+    ///
+    /// Unlike `set_theme` this holds the transition lock until the next tick,
+    /// so effects reconciling theme state don't react to the change and
+    /// bounce it back.
+    pub fn apply_theme(&self, theme: Theme) {
+        if self.current_theme.get_untracked() == theme {
+            return;
+        }
+        
+        self.transition_lock.set(true);
+        self.show_theme(theme);
+        
+        if self.persist {
+            if let Err(err) = save_theme(theme) {
+                error!("Failed to save theme preference: {:?}", err);
+            }
+        }
+        
+        let transition_lock = self.transition_lock;
+        set_timeout(move || transition_lock.set(false), Duration::ZERO);
+    }
+    
+    /// `apply_theme` for a plain dark or light mode
+    pub fn apply_dark_mode(&self, is_dark: bool) {
+        if self.dark_mode.get_untracked() != is_dark {
+            self.apply_theme(Theme::from_dark_mode(is_dark));
+        }
+    }
+    
+    /// The current mode's class strings as plain values
+    pub fn class_snapshot(&self) -> ThemeClassesSnapshot {
+        ThemeClassesSnapshot::for_dark_mode(self.dark_mode.get_untracked())
+    }
+}
+
+/// Provide theme state to this part of the tree.
+///
+/// With `persist` off the theme starts from the stored preference but
+/// changes stay in memory, and nothing is written to storage.
+pub fn provide_theme(persist: bool) -> ThemeState {
+    // Make sure a dark mode preference is stored before reading the theme
+    if persist {
+        get_dark_mode_preference();
+    }
+    let initial_theme = load_theme();
+    let current_theme = RwSignal::new(initial_theme);
+    
+    // Re-resolve `System` when the operating system's color scheme changes
+    let system_scheme = RwSignal::new(());
+    if let Some(query) = prefers_dark_query() {
+        let on_change = Closure::wrap(Box::new(move |_: web_sys::Event| {
+            system_scheme.set(());
+        }) as Box<dyn FnMut(_)>);
+        if let Err(err) = query.add_event_listener_with_callback("change", on_change.as_ref().unchecked_ref()) {
+            error!("Failed to follow the system color scheme: {:?}", err);
+        }
+        let scheme_listener = StoredValue::new_local(Some((query, on_change)));
+        on_cleanup(move || {
+            if let Some((query, on_change)) = scheme_listener.try_update_value(Option::take).flatten() {
+                let _ = query.remove_event_listener_with_callback("change", on_change.as_ref().unchecked_ref());
+            }
+        });
+    }
+    let shown_theme = Signal::derive(move || {
+        system_scheme.track();
+        effective_theme(current_theme.get())
+    });
+    let dark_mode = Signal::derive(move || shown_theme.get().is_dark());
     
     // Message for user feedback
     let (storage_message, set_storage_message) = create_signal(Option::<String>::None);
     
+    // Show a theme and save it, reporting save failures to the user
+    let choose_theme = move |theme: Theme| {
+        current_theme.set(theme);
+        
+        if !persist {
+            return;
+        }
+        
+        // Handle the result of saving the preference
+        match save_theme(theme) {
+            Ok(_) => {
+                // Clear any previous error messages
+                set_storage_message.set(None);
+            },
+            Err(err) => {
+                // Display the error message to the user
+                set_storage_message.set(Some(format!("Failed to save preference: {:?}", err)));
+                
+                // Log the error for debugging
+                error!("Failed to save theme preference: {:?}", err);
+            }
+        };
+    };
+    
     // Create an action to toggle the theme
     let toggle_theme = create_action(move |_: &()| {
-        set_dark_mode.update(|dark| {
-            *dark = !*dark;
-            
-            // Handle the result of saving the preference
-            match save_dark_mode_preference(*dark) {
-                Ok(_) => {
-                    // Clear any previous error messages
-                    set_storage_message.set(None);
-                },
-                Err(err) => {
-                    // Display the error message to the user
-                    set_storage_message.set(Some(format!("Failed to save preference: {:?}", err)));
-                    
-                    // Log the error for debugging
-                    error!("Failed to save dark mode preference: {:?}", err);
-                }
-            };
-        });
+        choose_theme(Theme::from_dark_mode(!dark_mode.get_untracked()));
         
         // Return unit for the action
         async {}
     });
     
+    // Create an action to switch to a chosen theme
+    let set_theme = Action::new(move |theme: &Theme| {
+        choose_theme(*theme);
+        async {}
+    });
+    
     // Create the ThemeState
+    let accent = RwSignal::new(load_accent());
+    let theme: Signal<Theme> = current_theme.into();
     let theme_state = ThemeState {
         dark_mode,
+        theme,
         toggle_theme,
+        set_theme,
+        transition_lock: RwSignal::new(false),
+        accent,
+        vars: Signal::derive(move || shown_theme.get().vars().with_accent(accent.get())),
+        current_theme,
+        shown_theme,
+        persist,
     };
     
+    // Follow theme changes made in other tabs
+    if persist && !crate::safe_mode::is_safe_mode() {
+        let handle = listen_cross_tab_storage(move |key, value| {
+            if key == THEME_KEY {
+                if let Some(theme) = value.and_then(|name| name.parse::<Theme>().ok()) {
+                    theme_state.show_theme(theme);
+                }
+            } else if key == "dark_mode" {
+                // Older tabs only write the mode; keep the theme if it agrees
+                let is_dark = value.as_deref() == Some("true");
+                if current_theme.get_untracked().is_dark() != is_dark {
+                    theme_state.show_theme(Theme::from_dark_mode(is_dark));
+                }
+            }
+        });
+        on_cleanup(move || handle.remove());
+    }
+    
     // Provide the theme state to the context
     provide_context(theme_state);
     
@@ -55,109 +467,129 @@ pub fn provide_theme() -> ThemeState {
     theme_state
 }
 
+/// Every themed class string for one mode, as plain values.
+/// The `use_*_class` hooks read from here, so tests can check class output
+/// for a mode without rendering anything.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThemeClassesSnapshot {
+    pub container: &'static str,
+    pub card: &'static str,
+    pub skeleton: &'static str,
+    pub dark_mode_toggle_button: &'static str,
+    pub error_message: &'static str,
+    pub header: &'static str,
+    pub paragraph: &'static str,
+    pub button: &'static str,
+    pub toggle: &'static str,
+    pub data_panel: &'static str,
+    pub data_header: &'static str,
+    pub data_content: &'static str,
+    pub data_close_button: &'static str,
+    pub player_id: &'static str,
+}
+
+impl ThemeClassesSnapshot {
+    /// Class strings for dark or light mode
+    pub fn for_dark_mode(is_dark: bool) -> Self {
+        if is_dark {
+            Self {
+                container: "min-h-screen bg-gradient-to-b from-gray-900 to-gray-800 text-white flex flex-col items-center justify-center p-4 dark",
+                card: "bg-gray-800 rounded-xl shadow-lg p-8 max-w-md w-full",
+                skeleton: "animate-pulse rounded-md bg-gray-700",
+                dark_mode_toggle_button: "ml-4 px-3 py-1 bg-[var(--accent)] hover:brightness-110 focus:outline-none focus:ring-2 focus:ring-[var(--accent)] focus:ring-offset-2 focus:ring-offset-gray-800 text-white rounded text-sm transition",
+                error_message: "mt-2 p-2 bg-red-900 text-red-300 rounded-md border border-red-800",
+                header: "text-3xl font-bold text-center text-purple-400 mb-6",
+                paragraph: "text-gray-300 text-center mb-6",
+                button: "bg-[var(--accent)] hover:brightness-110 focus:outline-none focus:ring-2 focus:ring-[var(--accent)] focus:ring-offset-2 focus:ring-offset-gray-800 text-white font-medium py-2 px-4 rounded-lg transition mr-2",
+                toggle: "bg-amber-700 hover:bg-amber-800 text-gray-100 font-medium py-2 px-4 rounded-lg transition-colors flex items-center",
+                data_panel: "bg-gray-800 rounded-lg shadow-lg p-4 border border-gray-700",
+                data_header: "text-xl font-semibold text-purple-400",
+                data_content: "p-4 bg-gray-700 rounded border border-gray-600 text-gray-200 font-medium",
+                data_close_button: "bg-gray-600 hover:bg-gray-500 text-gray-200 p-1 rounded-lg",
+                player_id: "mt-2 pt-2 border-t border-gray-600 text-purple-400",
+            }
+        } else {
+            Self {
+                container: "min-h-screen bg-gradient-to-b from-blue-50 to-indigo-100 flex flex-col items-center justify-center p-4",
+                card: "bg-white rounded-xl shadow-lg p-8 max-w-md w-full",
+                skeleton: "animate-pulse rounded-md bg-gray-200",
+                dark_mode_toggle_button: "ml-4 px-3 py-1 bg-[var(--accent)] hover:brightness-110 focus:outline-none focus:ring-2 focus:ring-[var(--accent)] focus:ring-offset-2 text-white rounded text-sm transition",
+                error_message: "mt-2 p-2 bg-red-100 text-red-700 rounded-md border border-red-200",
+                header: "text-3xl font-bold text-center text-indigo-600 mb-6",
+                paragraph: "text-gray-600 text-center mb-6",
+                button: "bg-[var(--accent)] hover:brightness-110 focus:outline-none focus:ring-2 focus:ring-[var(--accent)] focus:ring-offset-2 text-white font-medium py-2 px-4 rounded-lg transition mr-2",
+                toggle: "bg-gray-700 hover:bg-gray-800 text-white font-medium py-2 px-4 rounded-lg transition-colors flex items-center",
+                data_panel: "bg-white rounded-lg shadow-lg p-4 border border-gray-200",
+                data_header: "text-xl font-semibold text-indigo-700",
+                data_content: "p-4 bg-indigo-50 rounded border border-indigo-100 text-indigo-900 font-medium",
+                data_close_button: "bg-gray-200 hover:bg-gray-300 text-gray-800 p-1 rounded-lg",
+                player_id: "mt-2 pt-2 border-t border-indigo-200 text-indigo-700",
+            }
+        }
+    }
+}
+
 // Component wrappers for common theme patterns
 pub fn use_container_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "min-h-screen bg-gradient-to-b from-gray-900 to-gray-800 text-white flex flex-col items-center justify-center p-4 dark".to_string()
-        } else {
-            "min-h-screen bg-gradient-to-b from-blue-50 to-indigo-100 flex flex-col items-center justify-center p-4".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).container.to_string()
 }
 
 pub fn use_card_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "bg-gray-800 rounded-xl shadow-lg p-8 max-w-md w-full".to_string()
-        } else {
-            "bg-white rounded-xl shadow-lg p-8 max-w-md w-full".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).card.to_string()
+}
+
+pub fn use_skeleton_class() -> impl Fn() -> String {
+    let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
+    
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).skeleton.to_string()
 }
 
 pub fn use_dark_mode_toggle_button_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "ml-4 px-3 py-1 bg-purple-600 hover:bg-purple-700 text-white rounded text-sm transition-colors".to_string()
-        } else {
-            "ml-4 px-3 py-1 bg-indigo-500 hover:bg-indigo-600 text-white rounded text-sm transition-colors".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).dark_mode_toggle_button.to_string()
 }
 
 pub fn use_error_message_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "mt-2 p-2 bg-red-900 text-red-300 rounded-md border border-red-800".to_string()
-        } else {
-            "mt-2 p-2 bg-red-100 text-red-700 rounded-md border border-red-200".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).error_message.to_string()
 }
 
 pub fn use_header_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "text-3xl font-bold text-center text-purple-400 mb-6".to_string()
-        } else {
-            "text-3xl font-bold text-center text-indigo-600 mb-6".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).header.to_string()
 }
 
 pub fn use_paragraph_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "text-gray-300 text-center mb-6".to_string()
-        } else {
-            "text-gray-600 text-center mb-6".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).paragraph.to_string()
 }
 
 pub fn use_button_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "bg-purple-600 hover:bg-purple-700 text-white font-medium py-2 px-4 rounded-lg transition-colors mr-2".to_string()
-        } else {
-            "bg-indigo-500 hover:bg-indigo-600 text-white font-medium py-2 px-4 rounded-lg transition-colors mr-2".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).button.to_string()
 }
 
 pub fn use_toggle_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "bg-amber-700 hover:bg-amber-800 text-gray-100 font-medium py-2 px-4 rounded-lg transition-colors flex items-center".to_string()
-        } else {
-            "bg-gray-700 hover:bg-gray-800 text-white font-medium py-2 px-4 rounded-lg transition-colors flex items-center".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).toggle.to_string()
 }
 
 pub fn use_toggle_text() -> impl Fn() -> &'static str {
@@ -177,77 +609,268 @@ pub fn use_data_panel_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "bg-gray-800 rounded-lg shadow-lg p-4 border border-gray-700".to_string()
-        } else {
-            "bg-white rounded-lg shadow-lg p-4 border border-gray-200".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).data_panel.to_string()
 }
 
 pub fn use_data_header_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "text-xl font-semibold text-purple-400".to_string()
-        } else {
-            "text-xl font-semibold text-indigo-700".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).data_header.to_string()
 }
 
 pub fn use_data_content_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "p-4 bg-gray-700 rounded border border-gray-600 text-gray-200 font-medium".to_string()
-        } else {
-            "p-4 bg-indigo-50 rounded border border-indigo-100 text-indigo-900 font-medium".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).data_content.to_string()
 }
 
 pub fn use_data_close_button_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "bg-gray-600 hover:bg-gray-500 text-gray-200 p-1 rounded-lg".to_string()
-        } else {
-            "bg-gray-200 hover:bg-gray-300 text-gray-800 p-1 rounded-lg".to_string()
-        }
-    }
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).data_close_button.to_string()
 }
 
 pub fn use_player_id_class() -> impl Fn() -> String {
     let theme_state = use_context::<ThemeState>().expect("ThemeState should be provided");
     let dark_mode = MaybeSignal::derive(move || theme_state.dark_mode);
     
-    move || {
-        if theme_state.dark_mode.get() {
-            "mt-2 pt-2 border-t border-gray-600 text-purple-400".to_string()
-        } else {
-            "mt-2 pt-2 border-t border-indigo-200 text-indigo-700".to_string()
+    move || ThemeClassesSnapshot::for_dark_mode(theme_state.dark_mode.get()).player_id.to_string()
+}
+
+/// Row of color swatches, one per theme, that applies a theme on click
+#[component]
+pub fn ThemePicker() -> impl IntoView {
+    let theme_state = use_theme();
+    
+    view! {
+        <div data-test-id="theme-picker" class="flex justify-center space-x-2 mt-4">
+            {Theme::ALL.into_iter().map(|theme| {
+                let vars = theme.vars();
+                let is_active = move || theme_state.theme.get() == theme;
+                view! {
+                    <button
+                        data-test-id={format!("theme-swatch-{}", theme.name())}
+                        title={theme.name()}
+                        aria-pressed={move || is_active().to_string()}
+                        class={move || if is_active() {
+                            "w-8 h-8 rounded-full border-2 ring-2 ring-offset-2 ring-indigo-500"
+                        } else {
+                            "w-8 h-8 rounded-full border-2"
+                        }}
+                        style={format!("background: {}; border-color: {};", vars.background, vars.accent)}
+                        on:click={move |_| { theme_state.set_theme.dispatch(theme); }}
+                    >
+                        <span
+                            class="block w-3 h-3 mx-auto rounded-full"
+                            style={format!("background: {};", vars.surface)}
+                        />
+                    </button>
+                }
+            }).collect_view()}
+        </div>
+    }
+}
+
+/// Row of accent swatches that applies an accent on click
+#[component]
+pub fn AccentPicker() -> impl IntoView {
+    let theme_state = use_theme();
+    
+    view! {
+        <div data-test-id="accent-picker" class="flex space-x-2">
+            {Accent::ALL.into_iter().map(|accent| {
+                let is_active = move || theme_state.accent.get() == Some(accent);
+                view! {
+                    <button
+                        data-test-id={format!("accent-swatch-{}", accent.name())}
+                        title={accent.name()}
+                        aria-pressed={move || is_active().to_string()}
+                        class={move || if is_active() {
+                            "w-6 h-6 rounded-full ring-2 ring-offset-2 ring-gray-400"
+                        } else {
+                            "w-6 h-6 rounded-full"
+                        }}
+                        style={format!("background: {};", accent.hex())}
+                        on:click={move |_| theme_state.set_accent(accent)}
+                    />
+                }
+            }).collect_view()}
+        </div>
+    }
+}
+
+impl ContrastWarning {
+    pub fn describe(&self) -> String {
+        format!("{} on {}: {:.1}:1, below {}:1", self.tokens.0, self.tokens.1, self.ratio, MIN_TEXT_CONTRAST)
+    }
+}
+
+/// Diagnostics line listing theme colors that are hard to read together
+#[component]
+pub fn ContrastCheck(#[prop(into)] vars: Signal<ThemeVars>) -> impl IntoView {
+    view! {
+        {move || {
+            let warnings = validate_contrast(&vars.get());
+            if warnings.is_empty() {
+                view! { <p data-test-id="contrast-ok" class="text-sm">"Theme contrast: OK"</p> }.into_any()
+            } else {
+                view! {
+                    <ul data-test-id="contrast-warnings" class="text-sm text-red-600">
+                        {warnings.into_iter().map(|warning| view! {
+                            <li>{format!("Low contrast, {}", warning.describe())}</li>
+                        }).collect_view()}
+                    </ul>
+                }.into_any()
+            }
+        }}
+    }
+}
+
+// Scrollbar and text selection styles, which Tailwind utilities can't reach
+pub fn chrome_styles(is_dark: bool) -> &'static str {
+    if is_dark {
+        "::-webkit-scrollbar { width: 10px; background: #1f2937; } \
+         ::-webkit-scrollbar-thumb { background: #4b5563; border-radius: 5px; } \
+         ::selection { background: #7c3aed; color: #f9fafb; }"
+    } else {
+        "::-webkit-scrollbar { width: 10px; background: #f3f4f6; } \
+         ::-webkit-scrollbar-thumb { background: #a5b4fc; border-radius: 5px; } \
+         ::selection { background: #c7d2fe; color: #1e1b4b; }"
+    }
+}
+
+// Paint the page behind the app container, so the browser's default white
+// never shows around it or during overscroll while switching themes
+fn apply_page_background(color: &str) {
+    let Some(document) = web_sys::window().and_then(|win| win.document()) else { return };
+    let root = document.document_element().and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok());
+    for element in root.into_iter().chain(document.body()) {
+        if let Err(err) = element.style().set_property("background-color", color) {
+            error!("Failed to set page background: {:?}", err);
+        }
+    }
+}
+
+/// Color for browser UI around the page, like the mobile address bar;
+/// the theme's background, so the bar runs into the page
+pub fn theme_color(theme: Theme) -> &'static str {
+    theme.vars().background
+}
+
+// Set every `<meta name="theme-color">` to `color`, adding one if there are
+// none. index.html has a light and a dark one for before the app loads, and
+// whichever matches the media query applies.
+fn apply_theme_color(color: &str) {
+    let Some(document) = web_sys::window().and_then(|win| win.document()) else { return };
+    let Ok(metas) = document.query_selector_all("meta[name='theme-color']") else { return };
+    if metas.length() == 0 {
+        let Ok(meta) = document.create_element("meta") else { return };
+        let _ = meta.set_attribute("name", "theme-color");
+        if let Some(head) = document.query_selector("head").ok().flatten() {
+            let _ = head.append_child(&meta);
+        }
+    }
+    let Ok(metas) = document.query_selector_all("meta[name='theme-color']") else { return };
+    for index in 0..metas.length() {
+        let Some(meta) = metas.item(index).and_then(|node| node.dyn_into::<web_sys::Element>().ok()) else { continue };
+        if let Err(err) = meta.set_attribute("content", color) {
+            error!("Failed to set theme color: {:?}", err);
+        }
+    }
+}
+
+/// Favicon drawn in the theme's colors, as a data URL
+pub fn theme_icon_href(vars: &ThemeVars) -> String {
+    let svg = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 16 16'><rect width='16' height='16' rx='3' fill='{}'/><circle cx='8' cy='8' r='5' fill='{}'/></svg>",
+        vars.background, vars.accent,
+    );
+    // '#' would end the URL early
+    format!("data:image/svg+xml,{}", svg.replace('#', "%23"))
+}
+
+// Point the page's themed favicon at `href`, adding the link the first time
+fn apply_theme_icon(href: &str) {
+    let Some(document) = web_sys::window().and_then(|win| win.document()) else { return };
+    let link = match document.query_selector("link[data-theme-icon]") {
+        Ok(Some(link)) => link,
+        _ => {
+            let Ok(link) = document.create_element("link") else { return };
+            let _ = link.set_attribute("rel", "icon");
+            let _ = link.set_attribute("data-theme-icon", "");
+            if let Some(head) = document.query_selector("head").ok().flatten() {
+                let _ = head.append_child(&link);
+            }
+            link
         }
+    };
+    if let Err(err) = link.set_attribute("href", href) {
+        error!("Failed to set favicon: {:?}", err);
     }
 }
 
 #[component]
 pub fn ThemeProvider(
+    /// Also theme the page background, scrollbars, text selection and the
+    /// mobile address bar
+    #[prop(optional)] chrome_styles: bool,
+    /// Name the theme in the document title and draw the favicon in its colors
+    #[prop(optional)] document_indicators: bool,
+    /// Save theme changes to storage; turn off for previews that shouldn't stick
+    #[prop(default = true)] persist: bool,
     /// Optional children to render inside the theme provider
     #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
     // Provide theme context to the app
-    let _theme_state = provide_theme();
+    let theme_state = provide_theme(persist);
+    
+    // Tell the rest of the app about theme changes, but not the theme the page loads with
+    let bus = use_event_bus();
+    Effect::new(move |previous: Option<Theme>| {
+        let theme = theme_state.theme.get();
+        if previous.is_some_and(|was| was != theme) {
+            bus.emit(AppEvent::ThemeChanged(theme));
+        }
+        theme
+    });
+    
+    // The page background matches the top of the container gradient
+    if chrome_styles {
+        Effect::new(move |_| {
+            let theme = theme_state.shown_theme.get();
+            apply_page_background(theme.vars().background);
+            apply_theme_color(theme_color(theme));
+        });
+    }
+    
+    // "Title (dark)"; the title without the suffix comes back on unmount
+    if document_indicators {
+        if let Some(document) = web_sys::window().and_then(|win| win.document()) {
+            let base_title = document.title();
+            let restore_title = base_title.clone();
+            let title_document = document.clone();
+            Effect::new(move |_| {
+                title_document.set_title(&format!("{} ({})", base_title, theme_state.theme.get()));
+                apply_theme_icon(&theme_icon_href(&theme_state.vars.get()));
+            });
+            on_cleanup(move || document.set_title(&restore_title));
+        }
+    }
     
     // Return children with the provided theme
     view! {
+        // Expose the accent as a CSS variable for styles that follow it
+        <style data-test-id="theme-accent">
+            {move || format!(":root {{ --accent: {}; }}", theme_state.vars.get().accent)}
+        </style>
+        {chrome_styles.then(|| view! {
+            <style data-test-id="theme-chrome-styles">
+                {move || self::chrome_styles(theme_state.dark_mode.get())}
+            </style>
+        })}
         {children.map(|children| children())}
     }
 }
@@ -255,4 +878,10 @@ pub fn ThemeProvider(
 // Helper to get the theme context
 pub fn use_theme() -> ThemeState {
     use_context::<ThemeState>().expect("ThemeState should be provided")
+}
+
+// Helper for components that only need the current dark mode value
+pub fn use_dark_mode() -> Signal<bool> {
+    let theme_state = use_theme();
+    Signal::derive(move || theme_state.dark_mode.get())
 }
\ No newline at end of file
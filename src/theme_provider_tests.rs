@@ -4,7 +4,12 @@ mod theme_provider_tests {
     use leptos::prelude::*;
     use wasm_bindgen_test::*;
     use crate::test_utils::test::*;
-    use crate::theme::{ThemeProvider, use_theme};
+    use crate::theme::{ThemeProvider, Theme, use_theme, theme_icon_href, theme_color, provide_theme, effective_dark, ThemeClassesSnapshot};
+    use crate::utils::localStorage;
+    use wasm_bindgen::JsCast;
+    use gloo_timers::future::TimeoutFuture;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
     
     wasm_bindgen_test_configure!(run_in_browser);
     
@@ -50,4 +55,300 @@ mod theme_provider_tests {
                 child.text_content().unwrap() == "Dark", 
                 "Child should have access to theme context");
     }
-}
\ No newline at end of file
+    
+    #[wasm_bindgen_test]
+    async fn test_chrome_styles_follow_theme() {
+        #[component]
+        fn ToggleButton() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <button data-test-id="chrome-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                    "Toggle"
+                </button>
+            }
+        }
+        
+        mount_to_body(|| view! {
+            <ThemeProvider chrome_styles=true>
+                <ToggleButton />
+            </ThemeProvider>
+        });
+        
+        let style = get_by_test_id("theme-chrome-styles");
+        let before = style.text_content().unwrap();
+        assert!(before.contains("::selection"), "Style element should theme text selection");
+        assert!(before.contains("::-webkit-scrollbar"), "Style element should theme scrollbars");
+        
+        click_and_wait(&get_by_test_id("chrome-toggle"), 100).await;
+        let after = style.text_content().unwrap();
+        assert_ne!(before, after, "Chrome styles should change with the theme");
+        
+        // Restore the original theme
+        click_and_wait(&get_by_test_id("chrome-toggle"), 100).await;
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_page_background_follows_theme() {
+        localStorage::reset_theme_storage();
+        
+        #[component]
+        fn ToggleButton() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <button data-test-id="background-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                    "Toggle"
+                </button>
+            }
+        }
+        
+        mount_to_body(|| view! {
+            <ThemeProvider chrome_styles=true>
+                <ToggleButton />
+            </ThemeProvider>
+        });
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        let body_background = || document.body().unwrap().style().get_property_value("background-color").unwrap();
+        let html_background = || {
+            document.document_element().unwrap().unchecked_into::<web_sys::HtmlElement>()
+                .style().get_property_value("background-color").unwrap()
+        };
+        
+        // Browsers report inline colors as rgb()
+        assert_eq!(body_background(), "rgb(238, 242, 255)", "Light theme should paint the body");
+        
+        click_and_wait(&get_by_test_id("background-toggle"), 100).await;
+        assert_eq!(body_background(), "rgb(17, 24, 39)", "Dark theme should paint the body");
+        assert_eq!(html_background(), body_background(), "The root element should match the body");
+        
+        click_and_wait(&get_by_test_id("background-toggle"), 100).await;
+        localStorage::reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_non_persistent_provider_leaves_storage_untouched() {
+        localStorage::reset_theme_storage();
+        
+        #[component]
+        fn PreviewControls() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <button data-test-id="preview-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                    "Toggle"
+                </button>
+                <button data-test-id="preview-dark" on:click={move |_| { theme.set_theme.dispatch(Theme::Dark); }}>
+                    "Dark"
+                </button>
+                <p data-test-id="preview-mode">
+                    {move || if theme.dark_mode.get() { "dark" } else { "light" }}
+                </p>
+            }
+        }
+        
+        mount_to_body(|| view! {
+            <ThemeProvider persist=false>
+                <PreviewControls />
+            </ThemeProvider>
+        });
+        
+        let mode = get_by_test_id("preview-mode");
+        click_and_wait(&get_by_test_id("preview-toggle"), 100).await;
+        assert_eq!(mode.text_content().unwrap(), "dark", "Toggle should still apply in memory");
+        click_and_wait(&get_by_test_id("preview-toggle"), 100).await;
+        click_and_wait(&get_by_test_id("preview-dark"), 100).await;
+        assert_eq!(mode.text_content().unwrap(), "dark", "Chosen theme should apply in memory");
+        
+        assert_eq!(
+            localStorage::get_storage_item("dark_mode").unwrap(),
+            None,
+            "Nothing should be written to storage"
+        );
+    }
+    
+    // Dispatch a `storage` event as another tab writing `key` would
+    fn dispatch_storage_event(key: &str, new_value: &str) {
+        let init = web_sys::StorageEventInit::new();
+        init.set_key(Some(key));
+        init.set_new_value(Some(new_value));
+        let event = web_sys::StorageEvent::new_with_event_init_dict("storage", &init).unwrap();
+        web_sys::window().unwrap().dispatch_event(&event).unwrap();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_storage_events_for_foreign_keys_are_ignored() {
+        localStorage::reset_theme_storage();
+        let dark_mode_updates = Arc::new(AtomicU32::new(0));
+        
+        #[component]
+        fn SyncedMode(updates: Arc<AtomicU32>) -> impl IntoView {
+            let theme = use_theme();
+            Effect::new(move |_| {
+                theme.dark_mode.get();
+                updates.fetch_add(1, Ordering::SeqCst);
+            });
+            view! {
+                <p data-test-id="sync-mode">
+                    {move || if theme.dark_mode.get() { "dark" } else { "light" }}
+                </p>
+            }
+        }
+        
+        let updates = dark_mode_updates.clone();
+        mount_to_body(move || view! {
+            <ThemeProvider>
+                <SyncedMode updates={updates.clone()} />
+            </ThemeProvider>
+        });
+        TimeoutFuture::new(50).await;
+        let mode = get_by_test_id("sync-mode");
+        let initial_updates = dark_mode_updates.load(Ordering::SeqCst);
+        
+        dispatch_storage_event("other_app_dark_mode", "true");
+        TimeoutFuture::new(50).await;
+        assert_eq!(dark_mode_updates.load(Ordering::SeqCst), initial_updates, "Another app's key shouldn't touch app signals");
+        assert_eq!(mode.text_content().unwrap(), "light");
+        
+        dispatch_storage_event("dark_mode", "true");
+        TimeoutFuture::new(50).await;
+        assert!(dark_mode_updates.load(Ordering::SeqCst) > initial_updates, "The app's own key should update the theme");
+        assert_eq!(mode.text_content().unwrap(), "dark");
+        
+        localStorage::reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_document_title_and_favicon_follow_theme() {
+        localStorage::reset_theme_storage();
+        let _ = localStorage::reset_storage_item(crate::theme::ACCENT_KEY);
+        let document = web_sys::window().unwrap().document().unwrap();
+        document.set_title("Friends");
+        
+        #[component]
+        fn IndicatorToggle() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <button data-test-id="indicator-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                    "Toggle"
+                </button>
+            }
+        }
+        
+        mount_to_body(|| view! {
+            <ThemeProvider document_indicators=true>
+                <IndicatorToggle />
+            </ThemeProvider>
+        });
+        TimeoutFuture::new(50).await;
+        
+        let icon = document.query_selector("link[data-theme-icon]").unwrap().expect("Favicon link should be added");
+        assert_eq!(document.title(), "Friends (light)");
+        assert_eq!(icon.get_attribute("href").unwrap(), theme_icon_href(&Theme::Light.vars()));
+        
+        click_and_wait(&get_by_test_id("indicator-toggle"), 100).await;
+        assert_eq!(document.title(), "Friends (dark)", "The suffix should be replaced, not appended");
+        assert_eq!(icon.get_attribute("href").unwrap(), theme_icon_href(&Theme::Dark.vars()));
+        assert!(!icon.get_attribute("href").unwrap().contains('#'));
+        
+        localStorage::reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_theme_color_meta_follows_theme() {
+        localStorage::reset_theme_storage();
+        
+        #[component]
+        fn ThemeColorToggle() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <button data-test-id="theme-color-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                    "Toggle"
+                </button>
+            }
+        }
+        
+        mount_to_body(|| view! {
+            <ThemeProvider chrome_styles=true>
+                <ThemeColorToggle />
+            </ThemeProvider>
+        });
+        TimeoutFuture::new(50).await;
+        
+        let document = web_sys::window().unwrap().document().unwrap();
+        let content = || document.query_selector("meta[name='theme-color']").unwrap()
+            .expect("The theme-color meta should be added")
+            .get_attribute("content");
+        assert_eq!(content().as_deref(), Some(theme_color(Theme::Light)));
+        
+        click_and_wait(&get_by_test_id("theme-color-toggle"), 100).await;
+        assert_eq!(content().as_deref(), Some(theme_color(Theme::Dark)));
+        assert_ne!(theme_color(Theme::Light), theme_color(Theme::Dark));
+        
+        click_and_wait(&get_by_test_id("theme-color-toggle"), 100).await;
+        localStorage::reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_class_snapshot_follows_mode() {
+        localStorage::reset_theme_storage();
+        let owner = Owner::new();
+        let theme = owner.with(|| provide_theme(false));
+        
+        theme.apply_dark_mode(true);
+        let snapshot = theme.class_snapshot();
+        assert_eq!(
+            snapshot.container,
+            "min-h-screen bg-gradient-to-b from-gray-900 to-gray-800 text-white flex flex-col items-center justify-center p-4 dark"
+        );
+        assert_eq!(snapshot, ThemeClassesSnapshot::for_dark_mode(true));
+        
+        theme.apply_dark_mode(false);
+        assert_eq!(theme.class_snapshot(), ThemeClassesSnapshot::for_dark_mode(false));
+        assert_ne!(theme.class_snapshot().container, snapshot.container);
+        
+        localStorage::reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_system_theme_follows_color_scheme() {
+        use wasm_bindgen::JsValue;
+        
+        localStorage::reset_theme_storage();
+        let window = web_sys::window().unwrap();
+        let set = |name: &str, value: &JsValue| js_sys::Reflect::set(&window, &JsValue::from_str(name), value).unwrap();
+        
+        // A stand-in for matchMedia whose answer the test controls
+        let original = js_sys::Reflect::get(&window, &JsValue::from_str("matchMedia")).unwrap();
+        let mock = js_sys::Function::new_with_args(
+            "query",
+            "return { media: query, get matches() { return window.__prefersDark === true; }, \
+             addEventListener: (_, listener) => { window.__schemeListener = listener; }, removeEventListener: () => {} };",
+        );
+        set("matchMedia", &mock);
+        set("__prefersDark", &JsValue::TRUE);
+        
+        let owner = Owner::new();
+        let theme = owner.with(|| provide_theme(false));
+        theme.apply_theme(Theme::System);
+        assert!(effective_dark(Theme::System));
+        assert!(theme.dark_mode.get_untracked(), "System should show dark when the OS prefers it");
+        assert_eq!(theme.vars.get_untracked().background, Theme::Dark.vars().background);
+        
+        // The OS switches to light
+        set("__prefersDark", &JsValue::FALSE);
+        let listener = js_sys::Reflect::get(&window, &JsValue::from_str("__schemeListener")).unwrap()
+            .dyn_into::<js_sys::Function>()
+            .expect("The provider should listen for scheme changes");
+        listener.call1(&JsValue::NULL, &web_sys::Event::new("change").unwrap()).unwrap();
+        assert!(!theme.dark_mode.get_untracked(), "System should follow the OS to light");
+        
+        // Fixed themes ignore the system
+        theme.apply_theme(Theme::Dark);
+        assert!(theme.dark_mode.get_untracked());
+        assert!(!Theme::System.is_dark(), "The stored flag treats System as light");
+        
+        set("matchMedia", &original);
+        set("__prefersDark", &JsValue::UNDEFINED);
+        set("__schemeListener", &JsValue::UNDEFINED);
+        localStorage::reset_theme_storage();
+    }
+}
@@ -4,8 +4,11 @@ mod theme_tests {
     use leptos::prelude::*;
     use wasm_bindgen_test::*;
     use crate::test_utils::test::*;
-    use crate::theme::{ThemeProvider, use_theme};
+    use crate::theme::{ThemeProvider, ThemePicker, Theme, ThemeParseError, ThemeVars, validate_contrast, use_theme, use_dark_mode};
     use crate::utils::localStorage::reset_theme_storage;
+    use crate::announcer::LiveRegion;
+    use crate::theme::{Accent, ContrastCheck, ThemeState, THEME_KEY, load_theme, register_backup_keys};
+    use crate::utils::localStorage;
     
     wasm_bindgen_test_configure!(run_in_browser);
     
@@ -35,15 +38,8 @@ mod theme_tests {
     
     #[wasm_bindgen_test]
     async fn test_theme_toggle() {
-        // Reset theme storage to start with a clean state
-        reset_theme_storage();
-        
-        // Mount the test component
-        mount_to_body(|| view! {
-            <ThemeProvider>
-                <TestThemeComponent />
-            </ThemeProvider>
-        });
+        // Mount the test component, starting in light mode
+        mount_with_theme(false, || view! { <TestThemeComponent /> });
         
         // Get status and toggle elements
         let theme_status = get_by_test_id("theme-status");
@@ -65,4 +61,287 @@ mod theme_tests {
         let final_theme = theme_status.text_content().unwrap();
         assert_eq!(initial_theme, final_theme, "Theme should revert to initial state after toggling twice");
     }
-}
\ No newline at end of file
+    
+    #[wasm_bindgen_test]
+    async fn test_mount_with_theme_starts_in_seeded_mode() {
+        #[component]
+        fn SeededThemeStatus() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <p data-test-id="seeded-theme-status">
+                    {move || if theme.dark_mode.get() { "dark" } else { "light" }}
+                </p>
+            }
+        }
+        
+        mount_with_theme(true, || view! { <SeededThemeStatus /> });
+        
+        assert_eq!(get_by_test_id("seeded-theme-status").text_content().unwrap(), "dark");
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_theme_swatch_applies_theme() {
+        mount_with_theme(false, || view! { <ThemePicker /> });
+        
+        let light_swatch = get_by_test_id("theme-swatch-light");
+        let dark_swatch = get_by_test_id("theme-swatch-dark");
+        assert_eq!(light_swatch.get_attribute("aria-pressed").as_deref(), Some("true"), "Light should start active");
+        
+        click_and_wait(&dark_swatch, 100).await;
+        
+        assert_eq!(dark_swatch.get_attribute("aria-pressed").as_deref(), Some("true"), "Clicked swatch should be active");
+        assert_eq!(light_swatch.get_attribute("aria-pressed").as_deref(), Some("false"));
+        assert_eq!(
+            crate::utils::localStorage::get_storage_item("dark_mode").unwrap(),
+            Some("true".to_string()),
+            "Chosen theme should be stored"
+        );
+        assert_eq!(Theme::from_dark_mode(true), Theme::Dark);
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_high_contrast_swatch_persists_theme_name() {
+        mount_with_theme(false, || view! { <ThemePicker /> });
+        
+        let swatch = get_by_test_id("theme-swatch-high-contrast");
+        click_and_wait(&swatch, 100).await;
+        
+        assert_eq!(swatch.get_attribute("aria-pressed").as_deref(), Some("true"));
+        assert_eq!(localStorage::get_storage_item(THEME_KEY).unwrap(), Some("high-contrast".to_string()));
+        assert_eq!(localStorage::get_storage_item("dark_mode").unwrap(), Some("true".to_string()), "Older versions still see dark mode");
+        assert_eq!(load_theme(), Theme::HighContrast, "High contrast should read back rather than collapse to dark");
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_theme_is_migrated_from_dark_mode() {
+        reset_theme_storage();
+        let _ = localStorage::set_storage_item("dark_mode", "true");
+        assert_eq!(load_theme(), Theme::Dark, "A lone dark_mode flag should migrate");
+        
+        let _ = localStorage::set_storage_item(THEME_KEY, "system");
+        assert_eq!(load_theme(), Theme::Dark, "A theme name the flag disagrees with is stale");
+        
+        let _ = localStorage::set_storage_item("dark_mode", "false");
+        assert_eq!(load_theme(), Theme::System);
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_use_dark_mode_flips_after_toggle() {
+        #[component]
+        fn DarkModeHookComponent() -> impl IntoView {
+            let theme = use_theme();
+            let is_dark = use_dark_mode();
+            
+            view! {
+                <div>
+                    <p data-test-id="dark-mode-hook-status">
+                        {move || if is_dark.get() { "dark" } else { "light" }}
+                    </p>
+                    <button
+                        data-test-id="dark-mode-hook-toggle"
+                        on:click=move |_| { theme.toggle_theme.dispatch(()); }
+                    >
+                        "Toggle"
+                    </button>
+                </div>
+            }
+        }
+        
+        mount_with_theme(false, || view! { <DarkModeHookComponent /> });
+        
+        let status = get_by_test_id("dark-mode-hook-status");
+        let toggle_button = get_by_test_id("dark-mode-hook-toggle");
+        
+        assert_eq!(status.text_content().unwrap(), "light", "Hook should start in light mode after reset");
+        
+        click_and_wait(&toggle_button, 200).await;
+        
+        assert_eq!(status.text_content().unwrap(), "dark", "Hook value should flip after toggling");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_theme_names_round_trip() {
+        for theme in Theme::ALL {
+            let name = theme.to_string();
+            assert_eq!(name.parse::<Theme>(), Ok(theme), "{} should parse back", name);
+        }
+        assert_eq!(Theme::HighContrast.to_string(), "high-contrast");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_unknown_theme_name_errors() {
+        assert_eq!("sepia".parse::<Theme>(), Err(ThemeParseError("sepia".to_string())));
+        assert!("Dark".parse::<Theme>().is_err(), "Names are lowercase only");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_low_contrast_tokens_are_flagged() {
+        let vars = ThemeVars {
+            background: "#ffffff",
+            surface: "#ffffff",
+            accent: "#4f46e5",
+            text: "#cccccc",
+        };
+        let warnings = validate_contrast(&vars);
+        assert_eq!(warnings.len(), 2, "Light gray text should fail on both backgrounds: {:?}", warnings);
+        assert_eq!(warnings[0].tokens, ("text", "background"));
+        assert!(warnings[0].ratio < 4.5);
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_contrast_check_lists_low_contrast_pairs() {
+        let vars = RwSignal::new(ThemeVars {
+            background: "#ffffff",
+            surface: "#ffffff",
+            accent: "#4f46e5",
+            text: "#cccccc",
+        });
+        mount_to_body(move || view! { <ContrastCheck vars={vars} /> });
+        
+        let warnings = get_by_test_id("contrast-warnings").text_content().unwrap();
+        assert!(warnings.contains("text on background"), "{}", warnings);
+        assert!(warnings.contains("text on surface"), "{}", warnings);
+        
+        vars.set(Theme::Light.vars());
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        assert!(get_by_test_id("contrast-ok").text_content().unwrap().contains("OK"));
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_data_panel_shows_theme_contrast() {
+        mount_with_theme(false, || view! { <crate::data::DataButton inline=true /> });
+        
+        let inline = get_by_test_id("data-inline");
+        assert!(
+            inline.query_selector("[data-test-id='contrast-ok']").unwrap().is_some(),
+            "Diagnostics should report the built-in theme's contrast"
+        );
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_high_contrast_tokens_pass() {
+        let vars = ThemeVars {
+            background: "#fff",
+            surface: "#ffffff",
+            accent: "#4f46e5",
+            text: "#000000",
+        };
+        assert!(validate_contrast(&vars).is_empty());
+        
+        // The built-in themes should be readable
+        for theme in Theme::ALL {
+            assert!(validate_contrast(&theme.vars()).is_empty(), "{} should meet AA", theme);
+        }
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_theme_toggle_is_announced() {
+        reset_theme_storage();
+        
+        #[component]
+        fn AnnouncedToggle() -> impl IntoView {
+            let theme = use_theme();
+            view! {
+                <div data-test-id="announce-test">
+                    <button data-test-id="announce-toggle" on:click={move |_| { theme.toggle_theme.dispatch(()); }}>
+                        "Toggle"
+                    </button>
+                    <LiveRegion />
+                </div>
+            }
+        }
+        
+        mount_to_body(|| view! { <ThemeProvider><AnnouncedToggle /></ThemeProvider> });
+        
+        let container = get_by_test_id("announce-test");
+        let region = container.query_selector("[data-test-id='live-region']").unwrap().unwrap();
+        assert_eq!(region.get_attribute("aria-live").as_deref(), Some("polite"));
+        assert_eq!(region.text_content().unwrap(), "", "Nothing should be announced on load");
+        
+        click_and_wait(&get_by_test_id("announce-toggle"), 100).await;
+        assert_eq!(region.text_content().unwrap(), "Switched to dark mode");
+        
+        click_and_wait(&get_by_test_id("announce-toggle"), 100).await;
+        assert_eq!(region.text_content().unwrap(), "Switched to light mode");
+        
+        reset_theme_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_accent_survives_export_and_import() {
+        register_backup_keys();
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "accent_player");
+        
+        // Capture the theme context from inside the provider
+        let captured = RwSignal::new(Option::<ThemeState>::None);
+        
+        #[component]
+        fn ThemeProbe(capture: RwSignal<Option<ThemeState>>) -> impl IntoView {
+            capture.set(Some(use_theme()));
+        }
+        
+        mount_to_body(move || view! {
+            <ThemeProvider>
+                <ThemeProbe capture={captured} />
+            </ThemeProvider>
+        });
+        let theme = captured.get_untracked().expect("Theme context should be captured");
+        
+        theme.set_accent(Accent::Teal);
+        assert_eq!(theme.vars.get_untracked().accent, Accent::Teal.hex(), "Accent should override the theme token");
+        let backup = crate::data::export_data().expect("Export should succeed");
+        
+        // Fresh storage, then restore the backup
+        localStorage::reset_all_storage();
+        crate::data::sync_theme_from_storage(theme);
+        assert_eq!(theme.accent.get_untracked(), None, "Accent should be gone with the storage");
+        
+        crate::data::import_data(&backup).expect("Import should succeed");
+        crate::data::sync_theme_from_storage(theme);
+        
+        assert_eq!(theme.accent.get_untracked(), Some(Accent::Teal), "Accent should be restored");
+        assert_eq!(theme.vars.get_untracked().accent, Accent::Teal.hex(), "Restored accent should be applied");
+        
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_buttons_follow_the_accent() {
+        let _ = localStorage::reset_storage_item(crate::theme::ACCENT_KEY);
+        
+        #[component]
+        fn AccentButton() -> impl IntoView {
+            let theme = use_theme();
+            let button_class = crate::theme::use_button_class();
+            view! {
+                <button data-test-id="accent-button" class={button_class} on:click={move |_| theme.set_accent(Accent::Rose)}>
+                    "Rose"
+                </button>
+            }
+        }
+        
+        mount_with_theme(false, || view! { <AccentButton /> });
+        let button = get_by_test_id("accent-button");
+        let class = button.get_attribute("class").unwrap();
+        assert!(class.contains("bg-[var(--accent)]"), "Buttons should be filled with the accent: {}", class);
+        assert!(class.contains("focus:ring-[var(--accent)]"), "Focus rings should use the accent: {}", class);
+        
+        click_and_wait(&button, 100).await;
+        let accent_style = get_by_test_id("theme-accent").text_content().unwrap();
+        assert!(accent_style.contains(Accent::Rose.hex()), "The accent variable should carry the chosen color: {}", accent_style);
+        
+        let _ = localStorage::reset_storage_item(crate::theme::ACCENT_KEY);
+        reset_theme_storage();
+    }
+}
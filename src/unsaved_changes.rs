@@ -0,0 +1,83 @@
+use leptos::*;
+use leptos::prelude::*;
+use std::rc::Rc;
+use crate::confirm_modal::ConfirmModal;
+
+pub const UNSAVED_CHANGES_MESSAGE: &str = "You have unsaved changes, continue?";
+
+/// Tracks edits that haven't been committed yet, so destructive actions
+/// can ask before throwing them away.
+#[derive(Copy, Clone)]
+pub struct UnsavedChanges {
+    /// Set while some edit is uncommitted
+    pub unsaved: RwSignal<bool>,
+    /// Set while the user is being asked whether to continue
+    pub prompting: RwSignal<bool>,
+    // Action waiting on the user's answer
+    pending: StoredValue<Option<Rc<dyn Fn()>>, LocalStorage>,
+}
+
+impl UnsavedChanges {
+    pub fn set_unsaved(&self, unsaved: bool) {
+        if self.unsaved.get_untracked() != unsaved {
+            self.unsaved.set(unsaved);
+        }
+    }
+
+    /// Run a destructive action, asking first if there are unsaved changes
+    pub fn guard(&self, action: impl Fn() + 'static) {
+        if self.unsaved.get_untracked() {
+            self.pending.set_value(Some(Rc::new(action)));
+            self.prompting.set(true);
+        } else {
+            action();
+        }
+    }
+
+    /// The user chose to continue; run the waiting action
+    pub fn confirm(&self) {
+        self.prompting.set(false);
+        let action = self.pending.get_value();
+        self.pending.set_value(None);
+        if let Some(action) = action {
+            action();
+        }
+    }
+
+    /// The user backed out; drop the waiting action
+    pub fn cancel(&self) {
+        self.prompting.set(false);
+        self.pending.set_value(None);
+    }
+}
+
+// Provide the unsaved changes tracker to this part of the tree
+pub fn provide_unsaved_changes() -> UnsavedChanges {
+    let unsaved_changes = UnsavedChanges {
+        unsaved: RwSignal::new(false),
+        prompting: RwSignal::new(false),
+        pending: StoredValue::new_local(None),
+    };
+    provide_context(unsaved_changes);
+    unsaved_changes
+}
+
+// Helper to get the unsaved changes tracker, creating one if none is provided
+pub fn use_unsaved_changes() -> UnsavedChanges {
+    use_context::<UnsavedChanges>().unwrap_or_else(provide_unsaved_changes)
+}
+
+/// Confirm modal shown when a guarded action would discard unsaved changes
+#[component]
+pub fn UnsavedChangesPrompt() -> impl IntoView {
+    let unsaved_changes = use_unsaved_changes();
+
+    move || unsaved_changes.prompting.get().then(|| view! {
+        <ConfirmModal
+            message=UNSAVED_CHANGES_MESSAGE
+            test_id="unsaved-changes"
+            on_confirm=Callback::new(move |_| unsaved_changes.confirm())
+            on_cancel=Callback::new(move |_| unsaved_changes.cancel())
+        />
+    })
+}
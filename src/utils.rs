@@ -3,6 +3,7 @@ use uuid::Uuid;
 use wasm_bindgen::JsValue;
 use log::{error, info, warn};  // Import log macros
 use leptos::*;
+use std::rc::Rc;
 
 // Error type for localStorage operations
 #[derive(Debug, Clone)]
@@ -11,8 +12,26 @@ pub enum StorageError {
     GetError(String),
     SetError(String),
     RemoveError(String),
+    NotFound(String),
+    /// The write reported success but reading the key back gave a different value
+    VerificationFailed(String),
 }
 
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StorageError::StorageUnavailable => write!(f, "Local storage is unavailable"),
+            StorageError::GetError(msg) => write!(f, "Failed to read from storage: {}", msg),
+            StorageError::SetError(msg) => write!(f, "Failed to write to storage: {}", msg),
+            StorageError::RemoveError(msg) => write!(f, "Failed to remove from storage: {}", msg),
+            StorageError::NotFound(key) => write!(f, "Nothing stored under '{}'", key),
+            StorageError::VerificationFailed(msg) => write!(f, "Storage did not keep a write: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
 impl From<JsValue> for StorageError {
     fn from(js_value: JsValue) -> Self {
         let error_msg = js_value.as_string().unwrap_or_else(|| "Unknown JS error".to_string());
@@ -20,8 +39,24 @@ impl From<JsValue> for StorageError {
     }
 }
 
+#[cfg(test)]
+thread_local! {
+    static FORCED_STORAGE_ERROR: std::cell::RefCell<Option<StorageError>> = const { std::cell::RefCell::new(None) };
+}
+
+// Test hook: make storage access fail with `error` until cleared with `None`
+#[cfg(test)]
+pub fn set_force_storage_error(error: Option<StorageError>) {
+    FORCED_STORAGE_ERROR.with(|forced| *forced.borrow_mut() = error);
+}
+
 // Helper functions for localStorage
 pub fn get_storage() -> Result<Storage, StorageError> {
+    #[cfg(test)]
+    if let Some(err) = FORCED_STORAGE_ERROR.with(|forced| forced.borrow().clone()) {
+        return Err(err);
+    }
+    
     web_sys::window()
         .and_then(|win| win.local_storage().ok())
         .flatten()
@@ -34,16 +69,283 @@ pub fn get_storage_item(key: &str) -> Result<Option<String>, StorageError> {
     storage.get_item(key).map_err(|e| StorageError::GetError(format!("Failed to get '{}': {:?}", key, e)))
 }
 
+// Helper function to get an item that must exist, treating a missing key as an error
+pub fn get_storage_item_required(key: &str) -> Result<String, StorageError> {
+    get_storage_item(key)?.ok_or_else(|| StorageError::NotFound(key.to_string()))
+}
+
+#[cfg(test)]
+thread_local! {
+    static FAILING_WRITE_KEY: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+// Test hook: make writes to `key` fail, e.g. to interrupt a batch part way
+#[cfg(test)]
+pub fn set_failing_write_key(key: Option<&str>) {
+    FAILING_WRITE_KEY.with(|failing| *failing.borrow_mut() = key.map(str::to_string));
+}
+
+#[cfg(test)]
+thread_local! {
+    static DROPPED_WRITE_KEY: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+// Test hook: silently ignore writes to `key`, like storage that drops writes
+// in private mode or when full
+#[cfg(test)]
+pub fn set_dropped_write_key(key: Option<&str>) {
+    DROPPED_WRITE_KEY.with(|dropped| *dropped.borrow_mut() = key.map(str::to_string));
+}
+
+// Write one item without notifying listeners
+fn write_item(storage: &Storage, key: &str, value: &str) -> Result<(), StorageError> {
+    #[cfg(test)]
+    if FAILING_WRITE_KEY.with(|failing| failing.borrow().as_deref() == Some(key)) {
+        return Err(StorageError::SetError(format!("Failed to set '{}': forced failure", key)));
+    }
+    #[cfg(test)]
+    if DROPPED_WRITE_KEY.with(|dropped| dropped.borrow().as_deref() == Some(key)) {
+        return Ok(());
+    }
+    
+    storage.set_item(key, value).map_err(|e| StorageError::SetError(format!("Failed to set '{}': {:?}", key, e)))
+}
+
+// Read `key` back and check it holds `value`
+fn verify_item(storage: &Storage, key: &str, value: &str) -> Result<(), StorageError> {
+    let stored = storage.get_item(key)
+        .map_err(|e| StorageError::GetError(format!("Failed to get '{}': {:?}", key, e)))?;
+    if stored.as_deref() != Some(value) {
+        return Err(StorageError::VerificationFailed(format!("'{}' did not keep the written value", key)));
+    }
+    Ok(())
+}
+
+// Called with the keys that changed after each write or batch of writes
+type StorageListener = Rc<dyn Fn(&[String])>;
+
+thread_local! {
+    static STORAGE_LISTENERS: std::cell::RefCell<Vec<(usize, StorageListener)>> = std::cell::RefCell::new(Vec::new());
+    static NEXT_LISTENER_ID: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Get told which keys changed after writes made through these helpers.
+/// A `batch_write` is reported once, with all its keys.
+/// Returns an id for `unsubscribe_storage_changes`.
+pub fn subscribe_storage_changes(listener: impl Fn(&[String]) + 'static) -> usize {
+    let id = NEXT_LISTENER_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    STORAGE_LISTENERS.with(|listeners| listeners.borrow_mut().push((id, Rc::new(listener))));
+    id
+}
+
+pub fn unsubscribe_storage_changes(id: usize) {
+    STORAGE_LISTENERS.with(|listeners| listeners.borrow_mut().retain(|(listener_id, _)| *listener_id != id));
+}
+
+fn notify_storage_change(keys: &[String]) {
+    // Copy the list so listeners can subscribe or unsubscribe while being called
+    let listeners: Vec<StorageListener> = STORAGE_LISTENERS.with(|listeners| {
+        listeners.borrow().iter().map(|(_, listener)| listener.clone()).collect()
+    });
+    for listener in listeners {
+        listener(keys);
+    }
+}
+
+// Keys the app stores that `crate::data::list_storage_keys` doesn't cover
+const EXTRA_APP_STORAGE_KEYS: [&str; 1] = [crate::data::RECOVERY_KEY_KEY];
+
+/// Every storage key the app uses
+pub fn app_storage_keys() -> Vec<String> {
+    let mut keys = crate::data::list_storage_keys();
+    keys.extend(EXTRA_APP_STORAGE_KEYS.iter().map(|key| key.to_string()));
+    keys
+}
+
+/// Whether `key` belongs to this app rather than another one on the same origin
+pub fn is_app_storage_key(key: &str) -> bool {
+    app_storage_keys().iter().any(|app_key| app_key == key)
+}
+
+/// Call `listener` with the key and new value when another tab changes one
+/// of the app's keys. The browser reports every key on the origin, so other
+/// apps' keys are dropped here, as are `clear()` events, which carry no key.
+pub fn listen_cross_tab_storage(listener: impl Fn(&str, Option<String>) + 'static) -> leptos::prelude::WindowListenerHandle {
+    leptos::prelude::window_event_listener(leptos::ev::storage, move |ev: web_sys::StorageEvent| {
+        let Some(key) = ev.key() else { return };
+        if !is_app_storage_key(&key) {
+            return;
+        }
+        listener(&key, ev.new_value());
+    })
+}
+
 // Helper function to set an item in localStorage with error handling
 pub fn set_storage_item(key: &str, value: &str) -> Result<(), StorageError> {
     let storage = get_storage()?;
-    storage.set_item(key, value).map_err(|e| StorageError::SetError(format!("Failed to set '{}': {:?}", key, e)))
+    write_item(&storage, key, value)?;
+    notify_storage_change(&[key.to_string()]);
+    Ok(())
+}
+
+/// Like `set_storage_item`, but reads the key back to catch storage that
+/// accepts a write without keeping it
+pub fn set_storage_item_verified(key: &str, value: &str) -> Result<(), StorageError> {
+    let storage = get_storage()?;
+    write_item(&storage, key, value)?;
+    verify_item(&storage, key, value)?;
+    notify_storage_change(&[key.to_string()]);
+    Ok(())
 }
 
 // Helper function to remove an item from localStorage with error handling
 pub fn remove_storage_item(key: &str) -> Result<(), StorageError> {
     let storage = get_storage()?;
-    storage.remove_item(key).map_err(|e| StorageError::RemoveError(format!("Failed to remove '{}': {:?}", key, e)))
+    storage.remove_item(key).map_err(|e| StorageError::RemoveError(format!("Failed to remove '{}': {:?}", key, e)))?;
+    notify_storage_change(&[key.to_string()]);
+    Ok(())
+}
+
+/// Every stored key starting with `prefix`
+pub fn list_storage_keys_with_prefix(prefix: &str) -> Result<Vec<String>, StorageError> {
+    let storage = get_storage()?;
+    let length = storage.length().map_err(|e| StorageError::GetError(format!("Failed to count keys: {:?}", e)))?;
+    let mut keys = Vec::new();
+    for index in 0..length {
+        let key = storage.key(index).map_err(|e| StorageError::GetError(format!("Failed to read key {}: {:?}", index, e)))?;
+        if let Some(key) = key.filter(|key| key.starts_with(prefix)) {
+            keys.push(key);
+        }
+    }
+    Ok(keys)
+}
+
+// Rough per-origin localStorage allowance; browsers give at least 5 MB
+pub const STORAGE_QUOTA_ESTIMATE: usize = 5 * 1024 * 1024;
+
+/// Bytes a key and its value take up; storage keeps strings as UTF-16
+pub fn stored_size(key: &str, value: &str) -> usize {
+    (key.encode_utf16().count() + value.encode_utf16().count()) * 2
+}
+
+/// Bytes used by everything in localStorage, including other apps' keys
+pub fn storage_usage() -> Result<usize, StorageError> {
+    let mut total = 0;
+    for key in list_storage_keys_with_prefix("")? {
+        let value = get_storage_item(&key)?.unwrap_or_default();
+        total += stored_size(&key, &value);
+    }
+    Ok(total)
+}
+
+/// Storage in use against what's available, in bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageEstimate {
+    pub usage: usize,
+    pub quota: usize,
+}
+
+impl StorageEstimate {
+    /// Share of the quota in use, from 0.0 up
+    pub fn fraction(&self) -> f64 {
+        if self.quota == 0 { 1.0 } else { self.usage as f64 / self.quota as f64 }
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static MOCK_STORAGE_ESTIMATE: std::cell::Cell<Option<StorageEstimate>> = const { std::cell::Cell::new(None) };
+}
+
+// Test hook: report `estimate` instead of measuring storage, until cleared with `None`
+#[cfg(test)]
+pub fn set_mock_storage_estimate(estimate: Option<StorageEstimate>) {
+    MOCK_STORAGE_ESTIMATE.with(|mock| mock.set(estimate));
+}
+
+/// Current usage against `STORAGE_QUOTA_ESTIMATE`
+pub fn storage_estimate() -> Result<StorageEstimate, StorageError> {
+    #[cfg(test)]
+    if let Some(estimate) = MOCK_STORAGE_ESTIMATE.with(|mock| mock.get()) {
+        return Ok(estimate);
+    }
+    
+    Ok(StorageEstimate { usage: storage_usage()?, quota: STORAGE_QUOTA_ESTIMATE })
+}
+
+/// Remove every app key starting with `prefix`, returning how many were
+/// removed. Other apps' keys on the origin are left alone, even when they
+/// share the prefix. An empty prefix is refused rather than clearing all of storage.
+pub fn remove_storage_by_prefix(prefix: &str) -> Result<usize, StorageError> {
+    if prefix.is_empty() {
+        return Err(StorageError::RemoveError("Refusing to remove keys with an empty prefix".to_string()));
+    }
+    
+    // Collect first; removing while walking by index would skip keys
+    let keys: Vec<String> = list_storage_keys_with_prefix(prefix)?
+        .into_iter()
+        .filter(|key| is_app_storage_key(key))
+        .collect();
+    let storage = get_storage()?;
+    for key in &keys {
+        storage.remove_item(key).map_err(|e| StorageError::RemoveError(format!("Failed to remove '{}': {:?}", key, e)))?;
+    }
+    
+    if !keys.is_empty() {
+        notify_storage_change(&keys);
+    }
+    Ok(keys.len())
+}
+
+/// Write several items as one change: if any write fails, every key is put
+/// back the way it was and the error is returned. Listeners hear about the
+/// batch once. (The browser still fires a cross-tab `storage` event per key.)
+pub fn batch_write(entries: &[(&str, &str)]) -> Result<(), StorageError> {
+    write_batch(entries, false)
+}
+
+/// `batch_write` that also reads every key back, rolling back if any
+/// write didn't stick
+pub fn batch_write_verified(entries: &[(&str, &str)]) -> Result<(), StorageError> {
+    write_batch(entries, true)
+}
+
+fn write_batch(entries: &[(&str, &str)], verify: bool) -> Result<(), StorageError> {
+    let storage = get_storage()?;
+    
+    // Snapshot the keys we're about to touch
+    let mut snapshot = Vec::with_capacity(entries.len());
+    for (key, _) in entries {
+        let previous = storage.get_item(key)
+            .map_err(|e| StorageError::GetError(format!("Failed to get '{}': {:?}", key, e)))?;
+        snapshot.push((*key, previous));
+    }
+    
+    for (key, value) in entries {
+        let written = write_item(&storage, key, value)
+            .and_then(|()| if verify { verify_item(&storage, key, value) } else { Ok(()) });
+        if let Err(err) = written {
+            error!("Batch write failed at '{}', rolling back: {:?}", key, err);
+            for (key, previous) in &snapshot {
+                let restored = match previous {
+                    Some(value) => storage.set_item(key, value),
+                    None => storage.remove_item(key),
+                };
+                if let Err(restore_err) = restored {
+                    error!("Failed to roll back '{}': {:?}", key, restore_err);
+                }
+            }
+            return Err(err);
+        }
+    }
+    
+    let keys: Vec<String> = entries.iter().map(|(key, _)| key.to_string()).collect();
+    notify_storage_change(&keys);
+    Ok(())
 }
 
 // Uses the uuid crate to generate a player ID
@@ -51,6 +353,12 @@ pub fn generate_player_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// `id` in the canonical form player IDs are stored in, if it's a valid
+/// UUID in any of its usual spellings (braces, no hyphens, upper case)
+pub fn normalize_player_id(id: &str) -> Option<String> {
+    Uuid::parse_str(id.trim()).ok().map(|uuid| uuid.to_string())
+}
+
 // Helper function to get or create player ID from localStorage
 pub fn get_player_id() -> String {
     match get_storage_item("player_id") {
@@ -84,7 +392,56 @@ pub fn get_dark_mode_preference() -> bool {
 
 // Helper function to save dark mode preference to localStorage
 pub fn save_dark_mode_preference(is_dark: bool) -> Result<(), StorageError> {
-    set_storage_item("dark_mode", if is_dark { "true" } else { "false" })
+    set_storage_item_verified("dark_mode", if is_dark { "true" } else { "false" })
+}
+
+// Locale used when neither storage nor the browser provide one
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+// Helper function to get the browser's preferred language
+pub fn browser_locale() -> String {
+    web_sys::window()
+        .and_then(|win| win.navigator().language())
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+// Helper function to get the locale preference, defaulting to the browser language
+pub fn get_locale() -> String {
+    match get_storage_item("locale") {
+        Ok(Some(locale)) if !locale.is_empty() => locale,
+        _ => browser_locale(),
+    }
+}
+
+// Helper function to save the locale preference to localStorage
+pub fn set_locale(locale: &str) -> Result<(), StorageError> {
+    set_storage_item("locale", locale)
+}
+
+// Read a `panel` value from a query string or fragment,
+// e.g. "?panel=data" or "#data". Query parameters win over the fragment.
+pub fn parse_panel_param(search: &str, hash: &str) -> Option<String> {
+    query_param(search, "panel")
+        .or_else(|| Some(hash.trim_start_matches('#').to_string()))
+        .filter(|panel| !panel.is_empty())
+}
+
+// Value of `name` in a query string like "?a=1&b=2", taken as-is
+pub fn query_param(search: &str, name: &str) -> Option<String> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+// Helper function to get the panel requested by the page URL, if any
+pub fn requested_panel() -> Option<String> {
+    let location = web_sys::window()?.location();
+    let search = location.search().unwrap_or_default();
+    let hash = location.hash().unwrap_or_default();
+    parse_panel_param(&search, &hash)
 }
 
 // Add a new localStorage module with test-friendly helpers
@@ -120,12 +477,14 @@ pub mod localStorage {
     /// Test helper to reset localStorage for tests
     pub fn reset_theme_storage() {
         let _ = reset_storage_item("dark_mode");
+        let _ = reset_storage_item(crate::theme::THEME_KEY);
     }
 
     /// Test helper to reset all app storage 
     pub fn reset_all_storage() {
-        let _ = reset_storage_item("dark_mode");
-        let _ = reset_storage_item("player_id");
+        for key in app_storage_keys() {
+            let _ = reset_storage_item(&key);
+        }
     }
 }
 
@@ -148,6 +507,7 @@ mod tests {
         if let Ok(storage) = get_storage() {
             let _ = storage.remove_item("dark_mode");
             let _ = storage.remove_item("player_id");
+            let _ = storage.remove_item("locale");
             // Wait a bit for storage operations to complete
             TimeoutFuture::new(50).await;
         }
@@ -200,6 +560,28 @@ mod tests {
         let _ = storage.remove_item(test_key);
     }
 
+    #[wasm_bindgen_test]
+    async fn test_get_storage_item_required() {
+        reset_storage().await;
+        
+        let test_key = "test_required_key";
+        let storage = get_storage().unwrap();
+        let _ = storage.remove_item(test_key);
+        
+        // A missing key is reported as NotFound rather than Ok(None)
+        match get_storage_item_required(test_key) {
+            Err(StorageError::NotFound(key)) => assert_eq!(key, test_key, "NotFound should name the missing key"),
+            other => panic!("Missing key should return NotFound, got {:?}", other),
+        }
+        
+        // A present key is returned directly
+        let _ = storage.set_item(test_key, "present");
+        assert_eq!(get_storage_item_required(test_key).unwrap(), "present");
+        
+        // Clean up
+        let _ = storage.remove_item(test_key);
+    }
+
     #[wasm_bindgen_test]
     async fn test_set_storage_item() {
         reset_storage().await;
@@ -322,6 +704,26 @@ mod tests {
             "Light mode preference should be stored correctly");
     }
 
+    #[wasm_bindgen_test]
+    async fn test_get_locale_defaults_to_browser_language() {
+        reset_storage().await;
+        
+        // With no stored locale the browser language is used
+        assert_eq!(get_locale(), browser_locale(), "Locale should default to navigator.language");
+        assert!(!get_locale().is_empty(), "Default locale should not be empty");
+    }
+    
+    #[wasm_bindgen_test]
+    async fn test_set_locale() {
+        reset_storage().await;
+        
+        let result = set_locale("fr-FR");
+        assert!(result.is_ok(), "Should successfully save locale");
+        assert_eq!(get_locale(), "fr-FR", "Stored locale should take precedence over the browser language");
+        
+        reset_storage().await;
+    }
+
     #[wasm_bindgen_test]
     async fn test_logging() {
         // Mount the test component
@@ -376,6 +778,135 @@ mod tests {
         let _ = localStorage::reset_storage_item(test_key);
     }
     
+    #[wasm_bindgen_test]
+    fn test_forced_storage_error_is_returned() {
+        set_force_storage_error(Some(StorageError::SetError("quota exceeded".to_string())));
+        let result = save_dark_mode_preference(true);
+        set_force_storage_error(None);
+        
+        assert!(
+            matches!(result, Err(StorageError::SetError(ref msg)) if msg == "quota exceeded"),
+            "save_dark_mode_preference should return the forced error: {:?}", result
+        );
+        assert!(save_dark_mode_preference(false).is_ok(), "Storage should work again once cleared");
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_reset_everything_clears_storage_and_overrides() {
+        crate::data::register_backup_key("reset_test_key", false);
+        let _ = localStorage::set_storage_item("reset_test_key", "leftover");
+        let _ = localStorage::set_storage_item("player_id", "leftover_player");
+        crate::friends::set_max_friends(1);
+        set_force_storage_error(Some(StorageError::StorageUnavailable));
+        
+        reset_everything();
+        
+        assert!(get_storage().is_ok(), "Forced storage errors should be cleared");
+        assert_eq!(get_storage_item("reset_test_key").unwrap(), None, "Registered keys should be cleared");
+        assert_eq!(get_storage_item("player_id").unwrap(), None);
+        assert_eq!(crate::friends::max_friends(), crate::friends::DEFAULT_MAX_FRIENDS);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_app_storage_keys_follow_the_registry() {
+        assert!(is_app_storage_key("player_id"));
+        assert!(is_app_storage_key(crate::autosave::AUTOSAVE_KEY), "Local-only keys belong to the app");
+        assert!(is_app_storage_key(crate::data::RECOVERY_KEY_KEY));
+        assert!(!is_app_storage_key("app_key_test_setting"));
+
+        crate::data::register_backup_key("app_key_test_setting", false);
+        assert!(is_app_storage_key("app_key_test_setting"), "Registered keys should count without editing a list");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_batch_write_notifies_once() {
+        localStorage::reset_all_storage();
+        let notifications: Rc<std::cell::RefCell<Vec<Vec<String>>>> = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen = notifications.clone();
+        let id = subscribe_storage_changes(move |keys| seen.borrow_mut().push(keys.to_vec()));
+        
+        batch_write(&[("player_id", "batch_player"), ("dark_mode", "true"), ("locale", "fr-FR")]).unwrap();
+        unsubscribe_storage_changes(id);
+        
+        assert_eq!(*notifications.borrow(), vec![vec!["player_id", "dark_mode", "locale"]], "One notification for the whole batch");
+        assert_eq!(get_storage_item("locale").unwrap(), Some("fr-FR".to_string()));
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_remove_by_prefix_only_touches_that_prefix() {
+        localStorage::reset_all_storage();
+        for key in ["profile_a_name", "profile_a_avatar", "profile_b_name"] {
+            crate::data::register_backup_key(key, false);
+        }
+        for key in ["profile_a_name", "profile_a_avatar", "profile_b_name", "player_id"] {
+            let _ = localStorage::set_storage_item(key, "value");
+        }
+        
+        assert_eq!(remove_storage_by_prefix("profile_a_").unwrap(), 2, "Both keys under the prefix should be removed");
+        assert_eq!(get_storage_item("profile_a_name").unwrap(), None);
+        assert_eq!(get_storage_item("profile_a_avatar").unwrap(), None);
+        assert_eq!(get_storage_item("profile_b_name").unwrap(), Some("value".to_string()), "Other prefixes should be kept");
+        assert_eq!(get_storage_item("player_id").unwrap(), Some("value".to_string()), "Unrelated keys should be kept");
+        
+        assert_eq!(remove_storage_by_prefix("profile_a_").unwrap(), 0, "Nothing left to remove");
+        assert!(remove_storage_by_prefix("").is_err(), "An empty prefix would match everything");
+        
+        let _ = localStorage::reset_storage_item("profile_b_name");
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_verified_writes_catch_dropped_writes() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "original_player");
+        set_dropped_write_key(Some("dark_mode"));
+        
+        // A plain write can't tell the value was dropped
+        assert!(set_storage_item("dark_mode", "true").is_ok());
+        
+        let result = save_dark_mode_preference(true);
+        assert!(matches!(result, Err(StorageError::VerificationFailed(_))), "{:?}", result);
+        
+        let result = batch_write_verified(&[("player_id", "new_player"), ("dark_mode", "true")]);
+        assert!(matches!(result, Err(StorageError::VerificationFailed(_))), "{:?}", result);
+        assert_eq!(get_storage_item("player_id").unwrap(), Some("original_player".to_string()), "Verified batches should roll back too");
+        
+        set_dropped_write_key(None);
+        assert!(save_dark_mode_preference(true).is_ok(), "Writes that stick should verify");
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_batch_write_is_all_or_nothing() {
+        localStorage::reset_all_storage();
+        let _ = localStorage::set_storage_item("player_id", "original_player");
+        let notified = Rc::new(std::cell::Cell::new(false));
+        let seen = notified.clone();
+        let id = subscribe_storage_changes(move |_| seen.set(true));
+        
+        set_failing_write_key(Some("locale"));
+        let result = batch_write(&[("player_id", "new_player"), ("dark_mode", "true"), ("locale", "fr-FR")]);
+        set_failing_write_key(None);
+        unsubscribe_storage_changes(id);
+        
+        assert!(matches!(result, Err(StorageError::SetError(_))), "{:?}", result);
+        assert_eq!(get_storage_item("player_id").unwrap(), Some("original_player".to_string()), "Earlier writes should be rolled back");
+        assert_eq!(get_storage_item("dark_mode").unwrap(), None, "Keys that didn't exist should be removed again");
+        assert!(!notified.get(), "A failed batch shouldn't notify");
+        localStorage::reset_all_storage();
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_parse_panel_param() {
+        assert_eq!(parse_panel_param("?panel=data", ""), Some("data".to_string()));
+        assert_eq!(parse_panel_param("?invite=abc&panel=data", ""), Some("data".to_string()));
+        assert_eq!(parse_panel_param("", "#data"), Some("data".to_string()));
+        assert_eq!(parse_panel_param("?panel=data", "#other"), Some("data".to_string()), "Query should win over the fragment");
+        assert_eq!(parse_panel_param("?invite=abc", ""), None);
+        assert_eq!(parse_panel_param("", "#"), None);
+    }
+    
     #[wasm_bindgen_test]
     async fn test_localStorage_helpers() {
         // Use "dark_mode" instead of "test_localStorage_key"
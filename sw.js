@@ -0,0 +1,39 @@
+// Offline cache for the app shell and WASM bundle.
+// Requests are answered from the network when possible and from the cache
+// when offline; every successful same-origin GET refreshes the cache. The page
+// adds the WASM bundle and JS glue it loaded before this worker was installed
+// (service_worker::cache_bundle), so keep CACHE in step with CACHE_NAME there.
+const CACHE = "friends-connect-v1";
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(caches.open(CACHE).then((cache) => cache.add("./")));
+  self.skipWaiting();
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches.keys().then((keys) =>
+      Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key)))
+    )
+  );
+  self.clients.claim();
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  if (request.method !== "GET" || new URL(request.url).origin !== self.location.origin) {
+    return;
+  }
+  event.respondWith(
+    fetch(request)
+      .then((response) => {
+        // Keep the last good copy rather than caching a 404 or 500
+        if (response.ok) {
+          const copy = response.clone();
+          caches.open(CACHE).then((cache) => cache.put(request, copy));
+        }
+        return response;
+      })
+      .catch(() => caches.match(request))
+  );
+});